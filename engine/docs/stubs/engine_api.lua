@@ -19,7 +19,7 @@ end
 --- @param entity_id number The ID of the entity
 --- @param x number The X position
 --- @param y number The Y position
---- @param rotation number The rotation (in degrees)
+--- @param rotation number The rotation (in radians), relative to the parent if the entity has one
 --- @param scale_x number The X scale
 --- @param scale_y number The Y scale
 function set_transform(entity_id, x, y, rotation, scale_x, scale_y) end
@@ -99,7 +99,8 @@ function create_entity()
 	return 0 -- Default return value
 end
 
---- Destroys an entity.
+--- Destroys an entity and all of its components, calling `on_destroy(entity_id)`
+--- first if the script defines it. Its ID stays invalid; using it again raises an error.
 --- @param entity_id number The ID of the entity to destroy
 function destroy_entity(entity_id) end
 
@@ -111,3 +112,133 @@ function destroy_entity(entity_id) end
 function is_colliding(entity1, entity2)
 	return false -- Default return value
 end
+
+-- Query API
+--- Tags an entity.
+--- @param entity_id number The ID of the entity
+--- @param tag string The tag to add, e.g. "enemy"
+function add_tag(entity_id, tag) end
+
+--- Removes a tag from an entity.
+--- @param entity_id number The ID of the entity
+--- @param tag string The tag to remove
+--- @return boolean Whether the entity had the tag
+function remove_tag(entity_id, tag)
+	return false -- Default return value
+end
+
+--- Checks if an entity has a tag.
+--- @param entity_id number The ID of the entity
+--- @param tag string The tag to look for
+--- @return boolean Whether the entity has the tag
+function has_tag(entity_id, tag)
+	return false -- Default return value
+end
+
+--- Lists an entity's tags.
+--- @param entity_id number The ID of the entity
+--- @return string[] The entity's tags in alphabetical order
+function get_tags(entity_id)
+	return {} -- Default return value
+end
+
+--- Finds the entities with all of the given components and tags.
+--- @param components string|string[]|nil Component names, e.g. {"transform", "velocity"}
+--- @param tags string|string[]|nil Tags, e.g. "enemy"
+--- @return number[] The matching entity IDs
+function query(components, tags)
+	return {} -- Default return value
+end
+
+--- Iterates over the entities `query` would return, skipping ones destroyed during the loop.
+--- @param components string|string[]|nil Component names
+--- @param tags string|string[]|nil Tags
+--- @return fun(): number|nil
+function each_entity(components, tags)
+	return function() return nil end -- Default return value
+end
+
+--- Counts the entities `query` would return.
+--- @param components string|string[]|nil Component names
+--- @param tags string|string[]|nil Tags
+--- @return number
+function count_entities(components, tags)
+	return 0 -- Default return value
+end
+
+-- Hierarchy API
+--- Makes an entity a child of another; it keeps its place in the world and then follows the parent.
+--- @param child_id number The entity to attach
+--- @param parent_id number The entity to follow
+function attach(child_id, parent_id) end
+
+--- Unlinks an entity from its parent, leaving it where it is.
+--- @param child_id number The entity to detach
+--- @return boolean Whether the entity had a parent
+function detach(child_id)
+	return false -- Default return value
+end
+
+--- Gets an entity's parent.
+--- @param entity_id number The ID of the entity
+--- @return number|nil The parent's ID, or nil
+function get_parent(entity_id)
+	return nil -- Default return value
+end
+
+--- Lists an entity's direct children.
+--- @param entity_id number The ID of the entity
+--- @return number[] The children's IDs
+function get_children(entity_id)
+	return {} -- Default return value
+end
+
+--- Gets where an entity is in the world, taking its parents into account.
+--- @param entity_id number The ID of the entity
+--- @return number x, number y, number rotation
+function get_world_transform(entity_id)
+	return 0, 0, 0 -- Default return value
+end
+
+-- Prefab API
+--- Defines a named entity template.
+--- @param name string The name to spawn it by
+--- @param components table Optional transform, shape, velocity, gravity, draggable and tags
+function define_prefab(name, components) end
+
+--- Creates an entity from a prefab.
+--- @param name string The prefab to spawn
+--- @param overrides table|nil Components laid over the prefab's, field by field
+--- @return number The new entity's ID
+function spawn(name, overrides)
+	return 0 -- Default return value
+end
+
+-- Scene API
+--- Defines a named scene.
+--- @param name string The name to push it by
+--- @param callbacks table Optional on_enter, on_frame, on_fixed_update, on_exit and draw_when_paused
+function define_scene(name, callbacks) end
+
+--- Pauses the current scene and pushes a new one over it, calling its on_enter.
+--- @param name string The scene to push
+function push_scene(name) end
+
+--- Calls the top scene's on_exit, pops it and destroys the entities it created.
+function pop_scene() end
+
+--- Pops the top scene, if any, and pushes another in its place.
+--- @param name string The scene to switch to
+function switch_scene(name) end
+
+--- Gets the name of the scene on top of the stack.
+--- @return string|nil The scene's name, or nil
+function current_scene()
+	return nil -- Default return value
+end
+
+--- Lists the pushed scenes.
+--- @return string[] Their names, bottom first
+function get_scene_stack()
+	return {} -- Default return value
+end
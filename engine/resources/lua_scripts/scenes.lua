@@ -0,0 +1,85 @@
+-- Scene API example: a title screen, a bouncing ball and a pause menu.
+-- SPACE starts the game, P pauses it and SPACE resumes it.
+
+local WINDOW_WIDTH = 800
+local WINDOW_HEIGHT = 600
+local BALL_RADIUS = 16
+
+local ball
+
+local function label(text_id, y)
+	local entity = create_entity()
+	set_transform(entity, WINDOW_WIDTH / 2, y, 0)
+	add_text(entity, text_id, {
+		color = { 255, 255, 255 },
+		scale = 1.0,
+		h_align = ALIGN.CENTER,
+		v_align = ALIGN.MIDDLE,
+	})
+end
+
+------------------------------------------------
+-- Scenes
+------------------------------------------------
+
+-- Entities created in on_enter belong to the scene and go when it's popped
+define_scene("title", {
+	on_enter = function()
+		label(TEXT.READY, WINDOW_HEIGHT / 2 - 30)
+		label(TEXT.PRESS_SPACE, WINDOW_HEIGHT / 2 + 30)
+	end,
+	on_frame = function(delta_time)
+		if is_key_pressed("SPACE") then
+			switch_scene("play")
+		end
+	end,
+})
+
+define_scene("play", {
+	on_enter = function()
+		ball = create_entity()
+		set_transform(ball, WINDOW_WIDTH / 2, WINDOW_HEIGHT / 2, 0)
+		add_shape(ball, "circle", 0, 255, 0, { radius = BALL_RADIUS })
+		set_velocity(ball, 180, 120)
+	end,
+	on_frame = function(delta_time)
+		local x, y = get_transform(ball)
+		local vx, vy = get_velocity(ball)
+		if (x < BALL_RADIUS and vx < 0) or (x > WINDOW_WIDTH - BALL_RADIUS and vx > 0) then
+			vx = -vx
+		end
+		if (y < BALL_RADIUS and vy < 0) or (y > WINDOW_HEIGHT - BALL_RADIUS and vy > 0) then
+			vy = -vy
+		end
+		set_velocity(ball, vx, vy)
+
+		if is_key_pressed("P") then
+			push_scene("pause")
+		end
+	end,
+	-- The ball stays on screen, frozen, under the pause menu
+	draw_when_paused = true,
+})
+
+define_scene("pause", {
+	on_enter = function()
+		label(TEXT.GAME_PAUSED, WINDOW_HEIGHT / 2)
+	end,
+	on_frame = function(delta_time)
+		if is_key_pressed("SPACE") then
+			pop_scene()
+		end
+	end,
+})
+
+------------------------------------------------
+-- Script Callbacks
+------------------------------------------------
+
+function on_start()
+	push_scene("title")
+end
+
+function on_end()
+	print("Scene example ending.")
+end
@@ -1,3 +1,5 @@
+use tracing::debug;
+
 // Define the different types of primitive shapes the engine supports
 #[derive(Debug)]
 pub enum PrimitiveShape {
@@ -74,9 +76,9 @@ impl AssetManager {
                 id: 4,
             },
         ];
-        println!("Initialized AssetManager with assets:");
+        debug!("Initialized AssetManager with assets:");
         for asset in &assets {
-            println!("  - Asset '{}' (ID: {})", asset.name, asset.id);
+            debug!("  - Asset '{}' (ID: {})", asset.name, asset.id);
         }
 
         Self { assets }
@@ -0,0 +1,128 @@
+use mlua::Lua;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// First bytes of every precompiled Lua chunk
+const LUA_SIGNATURE: &[u8; 4] = b"\x1bLua";
+/// `LUAC_DATA`, bytes that catch chunks mangled by text-mode transfers
+const LUAC_DATA: &[u8; 6] = b"\x19\x93\r\n\x1a\n";
+/// `LUAC_INT`, stored as a `lua_Integer` so its byte order gives the endianness
+const LUAC_INT: u64 = 0x5678;
+
+/// What Lua bytecode depends on besides the script: the Lua version and bytecode
+/// format, the sizes of its instruction, integer and float types, and byte order.
+/// Bytecode only loads into a Lua built with the same ABI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BytecodeAbi {
+    /// `0x54` for Lua 5.4
+    pub lua_version: u8,
+    pub format: u8,
+    pub instruction_size: u8,
+    pub integer_size: u8,
+    pub number_size: u8,
+    pub big_endian: bool,
+}
+
+impl BytecodeAbi {
+    /// Bytes of the metadata entry that records an ABI
+    pub const ENCODED_SIZE: usize = 6;
+
+    /// The ABI of the Lua this engine was built with, read from a chunk it compiles
+    pub fn native() -> Self {
+        static NATIVE: OnceLock<BytecodeAbi> = OnceLock::new();
+        *NATIVE.get_or_init(|| {
+            let lua = Lua::new();
+            let function = lua
+                .load("")
+                .into_function()
+                .expect("an empty chunk always compiles");
+            Self::of_chunk(&function.dump(true)).expect("Lua dumps a valid chunk header")
+        })
+    }
+
+    /// The ABI in a precompiled chunk's header; `None` for source or a header
+    /// that isn't Lua 5.4's layout
+    pub fn of_chunk(code: &[u8]) -> Option<Self> {
+        // signature, version, format, LUAC_DATA, then the three type sizes
+        let header = code.get(..15)?;
+        if !is_bytecode(header) || &header[6..12] != LUAC_DATA {
+            return None;
+        }
+        let integer_size = header[13];
+        let check = code.get(15..15 + integer_size as usize)?;
+        let big_endian = if check.first() == Some(&(LUAC_INT as u8)) {
+            false
+        } else if check.last() == Some(&(LUAC_INT as u8)) {
+            true
+        } else {
+            return None;
+        };
+        Some(Self {
+            lua_version: header[4],
+            format: header[5],
+            instruction_size: header[12],
+            integer_size,
+            number_size: header[14],
+            big_endian,
+        })
+    }
+
+    pub fn to_bytes(self) -> [u8; Self::ENCODED_SIZE] {
+        [
+            self.lua_version,
+            self.format,
+            self.instruction_size,
+            self.integer_size,
+            self.number_size,
+            self.big_endian as u8,
+        ]
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; Self::ENCODED_SIZE] = bytes.try_into().ok()?;
+        Some(Self {
+            lua_version: bytes[0],
+            format: bytes[1],
+            instruction_size: bytes[2],
+            integer_size: bytes[3],
+            number_size: bytes[4],
+            big_endian: match bytes[5] {
+                0 => false,
+                1 => true,
+                _ => return None,
+            },
+        })
+    }
+}
+
+impl fmt::Display for BytecodeAbi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Lua {}.{}",
+            self.lua_version >> 4,
+            self.lua_version & 0x0f
+        )?;
+        // Format 0 is the official one; anything else is a modified Lua
+        if self.format != 0 {
+            write!(f, " (format {})", self.format)?;
+        }
+        write!(
+            f,
+            ", {}-byte integers, {}-byte floats, {}-byte instructions, {}",
+            self.integer_size,
+            self.number_size,
+            self.instruction_size,
+            if self.big_endian {
+                "big-endian"
+            } else {
+                "little-endian"
+            }
+        )
+    }
+}
+
+/// Whether a chunk is precompiled bytecode rather than source
+pub fn is_bytecode(code: &[u8]) -> bool {
+    code.starts_with(LUA_SIGNATURE)
+}
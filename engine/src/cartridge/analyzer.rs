@@ -0,0 +1,457 @@
+use super::container::CartridgeMetadata;
+use super::lexer::{tokenize, Token, TokenKind};
+use super::loader::file_stem;
+use super::manifest::ApiManifest;
+use super::packer::{self, Build};
+use super::scope::{resolve_names, GlobalCall};
+use super::{qr, CartridgeError};
+use crate::lua::api_module_globals;
+use qrcode::EcLevel;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::PathBuf;
+use tracing::debug;
+
+pub const ANALYZE_USAGE: &str = "\
+Usage: qr-game-engine analyze <script.lua> [options]
+
+Options:
+  --json              Print the report as JSON
+  --keep-debug        Analyze a build that keeps debug info, like pack --keep-debug";
+
+/// Strings shown in the human-readable report; the JSON report lists them all
+const STRINGS_SHOWN: usize = 10;
+
+const EC_LEVELS: [EcLevel; 4] = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H];
+
+#[derive(Debug, Clone)]
+pub struct AnalyzeOptions {
+    pub script_path: PathBuf,
+    pub json: bool,
+    pub strip: bool,
+}
+
+impl AnalyzeOptions {
+    /// Parse the arguments that follow `analyze` on the command line
+    pub fn from_args(args: &[String]) -> Result<Self, CartridgeError> {
+        let mut script_path = None;
+        let mut json = false;
+        let mut strip = true;
+        for arg in args {
+            match arg.as_str() {
+                "--json" => json = true,
+                "--keep-debug" => strip = false,
+                other if other.starts_with("--") => {
+                    return Err(CartridgeError::InvalidArguments(format!(
+                        "Unknown option: {}\n\n{}",
+                        other, ANALYZE_USAGE
+                    )));
+                }
+                other => script_path = Some(PathBuf::from(other)),
+            }
+        }
+
+        let script_path = script_path.ok_or_else(|| {
+            CartridgeError::InvalidArguments(format!("Missing script path\n\n{}", ANALYZE_USAGE))
+        })?;
+        Ok(Self {
+            script_path,
+            json,
+            strip,
+        })
+    }
+}
+
+/// Where a script's cartridge bytes go.
+///
+/// Every `packed_cost` is marginal: how much smaller the finished cartridge gets when
+/// that one item is taken out and the script is packed again. Compression shares
+/// bytes between items, so the costs don't add up to the cartridge size. A cost is
+/// `None` when the script no longer packs without the item.
+#[derive(Debug, Serialize)]
+pub struct AnalysisReport {
+    pub script: String,
+    pub source_size: usize,
+    pub bytecode_size: usize,
+    pub minified_size: Option<usize>,
+    pub payload: &'static str,
+    pub compression: String,
+    pub cartridge_size: usize,
+    /// QR codes needed at each error-correction level
+    pub qr_codes: Vec<QrFit>,
+    /// Named functions defined in the main chunk, largest packed cost first
+    pub functions: Vec<FunctionCost>,
+    /// Constant strings, largest packed cost first
+    pub strings: Vec<StringCost>,
+    /// Engine API modules the script calls into, largest packed cost first
+    pub api_modules: Vec<ModuleCost>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QrFit {
+    pub ec_level: String,
+    /// QR version of each code, one entry per chunk
+    pub versions: Vec<i16>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionCost {
+    pub name: String,
+    pub line: usize,
+    /// Characters in the function body
+    pub source_size: usize,
+    pub packed_cost: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StringCost {
+    /// The literal as written, quotes included
+    pub literal: String,
+    pub count: usize,
+    /// Characters across every occurrence
+    pub source_size: usize,
+    pub packed_cost: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleCost {
+    pub module: String,
+    pub calls: usize,
+    /// Characters across every call, arguments included
+    pub source_size: usize,
+    pub packed_cost: Option<i64>,
+}
+
+/// Pack a script the same way `pack` does, then pack it again without each
+/// function, string and API module to see what each one costs
+pub fn analyze(options: &AnalyzeOptions) -> Result<AnalysisReport, CartridgeError> {
+    let source = fs::read_to_string(&options.script_path)?;
+    let stem = file_stem(&options.script_path);
+    let metadata = CartridgeMetadata {
+        title: Some(stem.clone()),
+        api_modules: ApiManifest::infer(&source).ok(),
+        ..Default::default()
+    };
+    let base = packer::build(
+        source.as_bytes(),
+        &stem,
+        options.strip,
+        false,
+        false,
+        &metadata,
+    )?;
+
+    let analysis = Analysis {
+        chars: source.chars().collect(),
+        tokens: tokenize(&source)?,
+        stem,
+        strip: options.strip,
+        metadata,
+        base_size: base.bytes.len(),
+    };
+    let resolution = resolve_names(&analysis.tokens)?;
+
+    let qr_codes = EC_LEVELS
+        .iter()
+        .map(|&ec_level| {
+            let codes = packer::encode_codes(&base.bytes, ec_level, None)?;
+            Ok(QrFit {
+                ec_level: format!("{:?}", ec_level),
+                versions: codes
+                    .iter()
+                    .map(|(code, _)| qr::version_number(code.version()))
+                    .collect(),
+            })
+        })
+        .collect::<Result<Vec<_>, CartridgeError>>()?;
+
+    let mut functions: Vec<FunctionCost> = resolution
+        .functions
+        .iter()
+        .map(|function| {
+            let body = analysis.char_range(&function.body);
+            FunctionCost {
+                name: function.name.clone(),
+                line: function.line,
+                source_size: body.len(),
+                packed_cost: analysis.cost_without(&[(body, "")]),
+            }
+        })
+        .collect();
+    functions.sort_by_key(|function| std::cmp::Reverse(function.packed_cost));
+
+    let mut strings = analysis.string_costs();
+    strings.sort_by_key(|string| std::cmp::Reverse(string.packed_cost));
+
+    let mut api_modules = analysis.module_costs(&resolution.global_calls)?;
+    api_modules.sort_by_key(|module| std::cmp::Reverse(module.packed_cost));
+
+    debug!(
+        script = %options.script_path.display(),
+        cartridge_size = base.bytes.len(),
+        "Analyzed cartridge"
+    );
+
+    Ok(AnalysisReport {
+        script: options.script_path.display().to_string(),
+        source_size: source.len(),
+        bytecode_size: base.bytecode_size,
+        minified_size: base.minified_size,
+        payload: base.payload.describe(),
+        compression: format!("{:?}", base.compression),
+        cartridge_size: base.bytes.len(),
+        qr_codes,
+        functions,
+        strings,
+        api_modules,
+    })
+}
+
+/// A script being analyzed and the build settings every variant is packed with
+struct Analysis {
+    chars: Vec<char>,
+    tokens: Vec<Token>,
+    stem: String,
+    strip: bool,
+    metadata: CartridgeMetadata,
+    base_size: usize,
+}
+
+impl Analysis {
+    /// Character range in the source covered by a range of tokens
+    fn char_range(&self, tokens: &Range<usize>) -> Range<usize> {
+        let start = self.tokens[tokens.start].start;
+        let end = if tokens.end > tokens.start {
+            self.tokens[tokens.end - 1].end
+        } else {
+            start
+        };
+        start..end
+    }
+
+    /// Cartridge bytes saved by applying the replacements, which must not overlap
+    fn cost_without(&self, replacements: &[(Range<usize>, &str)]) -> Option<i64> {
+        let mut replacements = replacements.to_vec();
+        replacements.sort_by_key(|(range, _)| range.start);
+
+        let mut variant = String::with_capacity(self.chars.len());
+        let mut pos = 0;
+        for (range, replacement) in replacements {
+            variant.extend(&self.chars[pos..range.start]);
+            variant.push_str(replacement);
+            pos = range.end;
+        }
+        variant.extend(&self.chars[pos..]);
+
+        match self.build(&variant) {
+            Ok(build) => Some(self.base_size as i64 - build.bytes.len() as i64),
+            Err(e) => {
+                debug!(error = %e, "Variant of the script does not pack");
+                None
+            }
+        }
+    }
+
+    fn build(&self, source: &str) -> Result<Build, CartridgeError> {
+        packer::build(
+            source.as_bytes(),
+            &self.stem,
+            self.strip,
+            false,
+            false,
+            &self.metadata,
+        )
+    }
+
+    /// Every distinct string literal, costed by emptying all of its occurrences
+    fn string_costs(&self) -> Vec<StringCost> {
+        let mut occurrences: BTreeMap<&str, Vec<Range<usize>>> = BTreeMap::new();
+        for token in &self.tokens {
+            if token.kind == TokenKind::String {
+                occurrences
+                    .entry(&token.text)
+                    .or_default()
+                    .push(token.start..token.end);
+            }
+        }
+
+        occurrences
+            .into_iter()
+            .map(|(literal, ranges)| {
+                let replacements: Vec<_> =
+                    ranges.iter().map(|range| (range.clone(), "\"\"")).collect();
+                StringCost {
+                    literal: literal.to_string(),
+                    count: ranges.len(),
+                    source_size: ranges.iter().map(|range| range.len()).sum(),
+                    packed_cost: self.cost_without(&replacements),
+                }
+            })
+            .collect()
+    }
+
+    /// Calls grouped by the API module that registers the callee, costed by
+    /// dropping call statements and turning calls inside expressions into `nil`
+    fn module_costs(&self, calls: &[GlobalCall]) -> Result<Vec<ModuleCost>, CartridgeError> {
+        let modules = api_module_globals()?;
+        let module_of = |name: &str| {
+            let root = name.split('.').next().unwrap_or(name);
+            modules
+                .iter()
+                .find(|(_, globals)| globals.iter().any(|global| global == name))
+                .or_else(|| {
+                    modules.iter().find(|(_, globals)| {
+                        globals
+                            .iter()
+                            .any(|global| global.split('.').next() == Some(root))
+                    })
+                })
+                .map(|(module, _)| module.trim_end_matches("_api"))
+        };
+
+        let mut by_module: BTreeMap<&str, Vec<&GlobalCall>> = BTreeMap::new();
+        for call in calls {
+            if let Some(module) = module_of(&call.name) {
+                by_module.entry(module).or_default().push(call);
+            }
+        }
+
+        Ok(by_module
+            .into_iter()
+            .map(|(module, calls)| {
+                let ranges: Vec<_> = calls
+                    .iter()
+                    .map(|call| (self.char_range(&call.tokens), call.statement))
+                    .collect();
+                // A call nested in another call's arguments goes away with it
+                let mut replacements: Vec<(Range<usize>, &str)> = Vec::new();
+                for (range, statement) in &ranges {
+                    let nested = ranges.iter().any(|(outer, _)| {
+                        outer != range && outer.start <= range.start && range.end <= outer.end
+                    });
+                    if !nested {
+                        replacements.push((range.clone(), if *statement { "" } else { "nil" }));
+                    }
+                }
+                ModuleCost {
+                    module: module.to_string(),
+                    calls: calls.len(),
+                    source_size: ranges.iter().map(|(range, _)| range.len()).sum(),
+                    packed_cost: self.cost_without(&replacements),
+                }
+            })
+            .collect())
+    }
+}
+
+fn format_cost(cost: Option<i64>) -> String {
+    cost.map_or_else(|| "-".to_string(), |cost| cost.to_string())
+}
+
+/// Entry point for the `analyze` subcommand
+pub fn run(args: &[String]) -> Result<(), CartridgeError> {
+    let options = AnalyzeOptions::from_args(args)?;
+    let report = analyze(&options)?;
+
+    if options.json {
+        let json = serde_json::to_string_pretty(&report).map_err(io::Error::other)?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    println!("Analyzed {}", report.script);
+    println!("  source:    {} bytes", report.source_size);
+    println!("  bytecode:  {} bytes", report.bytecode_size);
+    match report.minified_size {
+        Some(size) => println!("  minified:  {} bytes", size),
+        None => println!("  minified:  skipped"),
+    }
+    println!(
+        "  cartridge: {} bytes ({}, {} compression)",
+        report.cartridge_size, report.payload, report.compression
+    );
+
+    println!();
+    println!("QR codes by error-correction level:");
+    for fit in &report.qr_codes {
+        let versions: Vec<String> = fit.versions.iter().map(ToString::to_string).collect();
+        if fit.versions.len() == 1 {
+            println!("  {}  version {}", fit.ec_level, versions[0]);
+        } else {
+            println!(
+                "  {}  {} codes, versions {}",
+                fit.ec_level,
+                fit.versions.len(),
+                versions.join(", ")
+            );
+        }
+    }
+
+    println!();
+    println!("Packed costs are what removing each item saves; they overlap and don't add up.");
+
+    println!();
+    println!(
+        "  {:<28} {:>5} {:>8} {:>8}",
+        "function", "line", "source", "packed"
+    );
+    for function in &report.functions {
+        println!(
+            "  {:<28} {:>5} {:>8} {:>8}",
+            function.name,
+            function.line,
+            function.source_size,
+            format_cost(function.packed_cost)
+        );
+    }
+
+    println!();
+    println!(
+        "  {:<28} {:>5} {:>8} {:>8}",
+        "string", "count", "source", "packed"
+    );
+    for string in report.strings.iter().take(STRINGS_SHOWN) {
+        let literal: String = if string.literal.chars().count() > 28 {
+            string
+                .literal
+                .chars()
+                .take(25)
+                .chain("...".chars())
+                .collect()
+        } else {
+            string.literal.clone()
+        };
+        println!(
+            "  {:<28} {:>5} {:>8} {:>8}",
+            literal,
+            string.count,
+            string.source_size,
+            format_cost(string.packed_cost)
+        );
+    }
+    if report.strings.len() > STRINGS_SHOWN {
+        println!(
+            "  ... {} more, see --json",
+            report.strings.len() - STRINGS_SHOWN
+        );
+    }
+
+    println!();
+    println!(
+        "  {:<28} {:>5} {:>8} {:>8}",
+        "API module", "calls", "source", "packed"
+    );
+    for module in &report.api_modules {
+        println!(
+            "  {:<28} {:>5} {:>8} {:>8}",
+            module.module,
+            module.calls,
+            module.source_size,
+            format_cost(module.packed_cost)
+        );
+    }
+    Ok(())
+}
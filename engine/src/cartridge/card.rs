@@ -0,0 +1,167 @@
+use super::container::CartridgeMetadata;
+use super::manifest::ApiManifest;
+use super::{qr, Cartridge, CartridgeError};
+use crate::ecs::{render_system, GameState, InputSystem};
+use crate::engine::managers::state_manager::StateManager;
+use crate::engine::rendering::{Renderer, SoftwareRenderer, WINDOW_HEIGHT, WINDOW_WIDTH};
+use crate::lua::limits::{install_limits, ScriptLimits};
+use crate::lua::sandbox::create_lua;
+use crate::lua::{call_on_start, load_game, register_engine_api};
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+use mlua::Result as LuaResult;
+use qrcode::QrCode;
+use sdl2::pixels::Color;
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+/// The first frame, scaled down to half the window
+const THUMBNAIL_WIDTH: u32 = WINDOW_WIDTH / 2;
+const THUMBNAIL_HEIGHT: u32 = WINDOW_HEIGHT / 2;
+const MARGIN: u32 = 32;
+const GAP: u32 = 24;
+/// Codes of a multi-code cartridge side by side before starting a new row
+const CODES_PER_ROW: usize = 3;
+
+const BACKGROUND: Rgba<u8> = Rgba([24, 24, 24, 255]);
+const TITLE_COLOR: Color = Color::RGB(240, 240, 240);
+const AUTHOR_COLOR: Color = Color::RGB(160, 160, 160);
+const TITLE_SCALE: f32 = 2.0;
+const AUTHOR_SCALE: f32 = 1.0;
+
+/// Run a game's main chunk and `on_start` without a window, then render its first
+/// frame with one `render_system` pass. The game is sandboxed and held to its
+/// script limits, as when it is played.
+pub fn render_first_frame(game: &Cartridge) -> LuaResult<RgbaImage> {
+    let state_manager = Rc::new(RefCell::new(StateManager::new(
+        Rc::new(RefCell::new(GameState::new())),
+        Rc::new(RefCell::new(InputSystem::new())),
+    )));
+
+    let lua = create_lua(false)?;
+    install_limits(&lua, ScriptLimits::for_cartridge(&game.metadata))?;
+    let manifest = game.metadata.api_modules.unwrap_or_else(ApiManifest::all);
+    register_engine_api(&lua, Rc::clone(&state_manager), manifest)?;
+    load_game(&lua, &state_manager, game, false)?;
+    call_on_start(&lua)?;
+
+    let mut renderer = SoftwareRenderer::new(WINDOW_WIDTH, WINDOW_HEIGHT);
+    render_system(state_manager, &mut renderer, 1.0, 1.0, false);
+    Ok(renderer.into_image())
+}
+
+/// Lay out a game card: the thumbnail, the title and author, and the cartridge's
+/// QR code(s) below them. Without a first frame the card is just text and codes.
+pub fn render_card(
+    codes: &[QrCode],
+    metadata: &CartridgeMetadata,
+    first_frame: Option<&RgbaImage>,
+) -> RgbaImage {
+    let codes: Vec<RgbaImage> = codes
+        .iter()
+        .map(|code| {
+            code.render::<Rgba<u8>>()
+                .module_dimensions(qr::MODULE_SIZE, qr::MODULE_SIZE)
+                .build()
+        })
+        .collect();
+    let rows: Vec<&[RgbaImage]> = codes.chunks(CODES_PER_ROW).collect();
+    let row_width = |row: &[RgbaImage]| row.iter().map(|code| code.width()).sum::<u32>();
+    let row_height = |row: &[RgbaImage]| row.iter().map(|code| code.height()).max().unwrap_or(0);
+
+    let content_width = rows
+        .iter()
+        .map(|row| row_width(row))
+        .max()
+        .unwrap_or(0)
+        .max(THUMBNAIL_WIDTH);
+    let title = metadata.title.as_deref().unwrap_or("Untitled");
+    let title_scale = fit_text(title, TITLE_SCALE, content_width);
+    let author = metadata
+        .author
+        .as_ref()
+        .map(|author| format!("by {}", author));
+    let author_scale = author.as_deref().map_or(AUTHOR_SCALE, |author| {
+        fit_text(author, AUTHOR_SCALE, content_width)
+    });
+    let text_height = SoftwareRenderer::text_height;
+
+    let width = content_width + 2 * MARGIN;
+    let mut height = MARGIN + text_height(title_scale) + GAP;
+    if first_frame.is_some() {
+        height += THUMBNAIL_HEIGHT + GAP;
+    }
+    if author.is_some() {
+        height += text_height(author_scale) + GAP;
+    }
+    height += rows.iter().map(|row| row_height(row)).sum::<u32>() + MARGIN;
+
+    let mut renderer = SoftwareRenderer::new(width, height);
+    for pixel in renderer.image_mut().pixels_mut() {
+        *pixel = BACKGROUND;
+    }
+    let center_x = width as i32 / 2;
+    let mut y = MARGIN;
+
+    if let Some(frame) = first_frame {
+        let thumbnail = imageops::resize(
+            frame,
+            THUMBNAIL_WIDTH,
+            THUMBNAIL_HEIGHT,
+            FilterType::Triangle,
+        );
+        let left = (width - THUMBNAIL_WIDTH) / 2;
+        imageops::overlay(renderer.image_mut(), &thumbnail, left as i64, y as i64);
+        y += THUMBNAIL_HEIGHT + GAP;
+    }
+
+    let line_height = text_height(title_scale);
+    let line_center = (y + line_height / 2) as i32;
+    renderer.draw_text(
+        title.to_string(),
+        center_x,
+        line_center,
+        TITLE_COLOR,
+        title_scale,
+    );
+    y += line_height + GAP;
+
+    if let Some(author) = author {
+        let line_height = text_height(author_scale);
+        let line_center = (y + line_height / 2) as i32;
+        renderer.draw_text(author, center_x, line_center, AUTHOR_COLOR, author_scale);
+        y += line_height + GAP;
+    }
+
+    // Rendered codes carry their own white quiet zone, so they can sit edge to edge
+    for row in rows {
+        let mut x = (width - row_width(row)) / 2;
+        for code in row {
+            imageops::overlay(renderer.image_mut(), code, x as i64, y as i64);
+            x += code.width();
+        }
+        y += row_height(row);
+    }
+
+    renderer.into_image()
+}
+
+pub fn write_card(
+    codes: &[QrCode],
+    metadata: &CartridgeMetadata,
+    first_frame: Option<&RgbaImage>,
+    path: &Path,
+) -> Result<(), CartridgeError> {
+    render_card(codes, metadata, first_frame).save(path)?;
+    Ok(())
+}
+
+/// Largest scale up to `scale` at which `text` fits in `width` pixels
+fn fit_text(text: &str, scale: f32, width: u32) -> f32 {
+    let mut scale = scale;
+    while scale > 0.5 && SoftwareRenderer::text_width(text, scale) > width {
+        scale -= 0.25;
+    }
+    scale
+}
@@ -0,0 +1,217 @@
+use super::CartridgeError;
+use std::collections::BTreeMap;
+
+/// Marks a QR payload as one piece of a larger cartridge
+pub const CHUNK_MAGIC: &[u8; 4] = b"QRCK";
+
+/// magic + cartridge id + index + total
+pub const CHUNK_HEADER_SIZE: usize = 4 + 4 + 1 + 1;
+
+pub const MAX_CHUNKS: usize = u8::MAX as usize;
+
+/// One numbered slice of a serialized cartridge, small enough for a single QR code.
+///
+/// Layout:
+///
+/// | Offset | Size | Field                                          |
+/// |--------|------|------------------------------------------------|
+/// | 0      | 4    | magic `QRCK`                                   |
+/// | 4      | 4    | cartridge id: CRC-32 of the whole cartridge    |
+/// | 8      | 1    | chunk index, starting at 0                     |
+/// | 9      | 1    | total number of chunks                         |
+/// | 10     | ..   | chunk data                                     |
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    pub cartridge_id: u32,
+    pub index: u8,
+    pub total: u8,
+    pub data: Vec<u8>,
+}
+
+impl Chunk {
+    pub fn is_chunk(bytes: &[u8]) -> bool {
+        bytes.starts_with(CHUNK_MAGIC)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(CHUNK_HEADER_SIZE + self.data.len());
+        bytes.extend_from_slice(CHUNK_MAGIC);
+        bytes.extend_from_slice(&self.cartridge_id.to_le_bytes());
+        bytes.push(self.index);
+        bytes.push(self.total);
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CartridgeError> {
+        if !Self::is_chunk(bytes) {
+            return Err(CartridgeError::InvalidMagic);
+        }
+        if bytes.len() < CHUNK_HEADER_SIZE {
+            return Err(CartridgeError::Truncated {
+                expected: CHUNK_HEADER_SIZE,
+                actual: bytes.len(),
+            });
+        }
+
+        let cartridge_id = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        let index = bytes[8];
+        let total = bytes[9];
+        if total == 0 || index >= total {
+            return Err(CartridgeError::InvalidChunk(
+                "chunk index is outside the chunk count",
+            ));
+        }
+
+        Ok(Self {
+            cartridge_id,
+            index,
+            total,
+            data: bytes[CHUNK_HEADER_SIZE..].to_vec(),
+        })
+    }
+}
+
+/// Split a serialized cartridge into chunks whose encoded size is at most `max_chunk_size`.
+/// Chunks are balanced so every QR code ends up roughly the same size.
+pub fn split(cartridge: &[u8], max_chunk_size: usize) -> Result<Vec<Chunk>, CartridgeError> {
+    if max_chunk_size <= CHUNK_HEADER_SIZE {
+        return Err(CartridgeError::InvalidArguments(format!(
+            "Chunk size must be larger than the {} byte chunk header",
+            CHUNK_HEADER_SIZE
+        )));
+    }
+
+    let max_data = max_chunk_size - CHUNK_HEADER_SIZE;
+    let count = cartridge.len().div_ceil(max_data).max(1);
+    if count > MAX_CHUNKS {
+        return Err(CartridgeError::TooManyChunks {
+            chunks: count,
+            max: MAX_CHUNKS,
+        });
+    }
+
+    let cartridge_id = crc32fast::hash(cartridge);
+    let data_size = cartridge.len().div_ceil(count).max(1);
+    Ok(cartridge
+        .chunks(data_size)
+        .enumerate()
+        .map(|(index, data)| Chunk {
+            cartridge_id,
+            index: index as u8,
+            total: count as u8,
+            data: data.to_vec(),
+        })
+        .collect())
+}
+
+/// Collects chunks in any order and joins them back into the original cartridge bytes
+#[derive(Debug, Default)]
+pub struct Reassembler {
+    cartridge_id: Option<u32>,
+    total: u8,
+    chunks: BTreeMap<u8, Vec<u8>>,
+}
+
+impl Reassembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn add(&mut self, chunk: Chunk) -> Result<(), CartridgeError> {
+        match self.cartridge_id {
+            None => {
+                self.cartridge_id = Some(chunk.cartridge_id);
+                self.total = chunk.total;
+            }
+            Some(id) if id != chunk.cartridge_id || self.total != chunk.total => {
+                return Err(CartridgeError::InvalidChunk(
+                    "chunks belong to different cartridges",
+                ));
+            }
+            Some(_) => {}
+        }
+
+        // The same code scanned twice is harmless, two different payloads for one slot are not
+        if let Some(existing) = self.chunks.get(&chunk.index) {
+            if *existing != chunk.data {
+                return Err(CartridgeError::InvalidChunk(
+                    "two different chunks share the same number",
+                ));
+            }
+            return Ok(());
+        }
+        self.chunks.insert(chunk.index, chunk.data);
+        Ok(())
+    }
+
+    /// 1-based numbers of the chunks that have not been seen yet
+    pub fn missing(&self) -> Vec<usize> {
+        (0..self.total)
+            .filter(|index| !self.chunks.contains_key(index))
+            .map(|index| index as usize + 1)
+            .collect()
+    }
+
+    /// Join the chunks and check the result against the cartridge id
+    pub fn finish(self) -> Result<Vec<u8>, CartridgeError> {
+        let missing = self.missing();
+        if !missing.is_empty() {
+            return Err(CartridgeError::MissingChunks {
+                missing,
+                total: self.total as usize,
+            });
+        }
+
+        let bytes: Vec<u8> = self.chunks.into_values().flatten().collect();
+        let expected = self.cartridge_id.unwrap_or_default();
+        let actual = crc32fast::hash(&bytes);
+        if actual != expected {
+            return Err(CartridgeError::ChecksumMismatch { expected, actual });
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cartridge() -> Vec<u8> {
+        (0..=255u8).cycle().take(1000).collect()
+    }
+
+    #[test]
+    fn reassembles_out_of_order_with_duplicates() {
+        let bytes = cartridge();
+        let chunks = split(&bytes, 100).unwrap();
+        assert_eq!(chunks.len(), 12);
+
+        let mut reassembler = Reassembler::new();
+        for chunk in chunks.iter().rev().chain(&chunks[3..6]) {
+            let chunk = Chunk::from_bytes(&chunk.to_bytes()).unwrap();
+            reassembler.add(chunk).unwrap();
+        }
+        assert_eq!(reassembler.finish().unwrap(), bytes);
+    }
+
+    #[test]
+    fn reports_missing_chunks() {
+        let chunks = split(&cartridge(), 100).unwrap();
+        let mut reassembler = Reassembler::new();
+        for chunk in chunks.into_iter().filter(|chunk| chunk.index % 5 != 1) {
+            reassembler.add(chunk).unwrap();
+        }
+        match reassembler.finish() {
+            Err(CartridgeError::MissingChunks { missing, total }) => {
+                assert_eq!(missing, vec![2, 7, 12]);
+                assert_eq!(total, 12);
+            }
+            other => panic!("expected missing chunks, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use super::CartridgeError;
+use mlua::Lua;
+use tracing::debug;
+
+/// Compile Lua source into Lua 5.4 bytecode.
+///
+/// Stripping removes line info and local names, which is usually most of the
+/// size difference between a debug build and what ends up in the QR code.
+pub fn compile_script(
+    source: &[u8],
+    chunk_name: &str,
+    strip: bool,
+) -> Result<Vec<u8>, CartridgeError> {
+    // A throwaway state is enough: compiling does not run any of the script
+    let lua = Lua::new();
+    let function = lua
+        .load(source)
+        .set_name(format!("={}", chunk_name))
+        .into_function()?;
+
+    let bytecode = function.dump(strip);
+    debug!(
+        chunk_name,
+        source_size = source.len(),
+        bytecode_size = bytecode.len(),
+        strip,
+        "Compiled script"
+    );
+    Ok(bytecode)
+}
@@ -0,0 +1,617 @@
+use super::abi::{self, BytecodeAbi};
+use super::manifest::ApiManifest;
+use super::prefabs;
+use super::{dictionary, CartridgeError};
+use crate::ecs::components::prefab::Prefab;
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use ed25519_dalek::{PUBLIC_KEY_LENGTH, SIGNATURE_LENGTH};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use std::io::{Read, Write};
+use tracing::warn;
+
+/// Bytes every cartridge starts with
+pub const MAGIC: &[u8; 4] = b"QRCA";
+
+/// Version of the byte layout written by this engine
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Version of the Lua API this engine registers. Bumped whenever a global
+/// is removed or changes behavior, so old engines can refuse newer games.
+pub const ENGINE_API_VERSION: u16 = 1;
+
+/// magic + format version + API version + flags + checksum + metadata length + payload length
+pub const HEADER_SIZE: usize = 4 + 1 + 2 + 1 + 4 + 2 + 4;
+
+const FLAG_COMPRESSED: u8 = 0b0000_0001;
+/// Only valid together with `FLAG_COMPRESSED`
+const FLAG_PRESET_DICTIONARY: u8 = 0b0000_0010;
+const FLAG_SIGNED: u8 = 0b0000_0100;
+const FLAG_FALLBACK_SOURCE: u8 = 0b0000_1000;
+const FLAG_PREFABS: u8 = 0b0001_0000;
+const KNOWN_FLAGS: u8 =
+    FLAG_COMPRESSED | FLAG_PRESET_DICTIONARY | FLAG_SIGNED | FLAG_FALLBACK_SOURCE | FLAG_PREFABS;
+
+/// Offset of the flags byte in the header
+const FLAGS_OFFSET: usize = 7;
+
+/// Public key + signature appended to a signed cartridge
+pub const SIGNATURE_BLOCK_SIZE: usize = PUBLIC_KEY_LENGTH + SIGNATURE_LENGTH;
+
+const TAG_TITLE: u8 = 1;
+const TAG_AUTHOR: u8 = 2;
+const TAG_INSTRUCTION_BUDGET: u8 = 3;
+const TAG_MEMORY_LIMIT: u8 = 4;
+const TAG_API_MODULES: u8 = 5;
+const TAG_BYTECODE_ABI: u8 = 6;
+const TAG_STEP_RATE: u8 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Deflate,
+    /// Deflate primed with the engine's preset dictionary, see [`dictionary`]
+    Dictionary,
+}
+
+/// The Ed25519 signature carried by a signed cartridge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartridgeSignature {
+    pub public_key: [u8; PUBLIC_KEY_LENGTH],
+    pub signature: [u8; SIGNATURE_LENGTH],
+}
+
+/// Descriptive fields shown to players before a game runs, plus what the game
+/// needs from the engine and the limits it asks to run with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CartridgeMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// Lua instructions each callback may run, see [`crate::lua::limits`]
+    pub instruction_budget: Option<u32>,
+    /// Bytes the game's Lua state may allocate
+    pub memory_limit: Option<u32>,
+    /// API modules the game uses; every module is registered when absent
+    pub api_modules: Option<ApiManifest>,
+    /// The Lua ABI a bytecode payload was compiled for
+    pub bytecode_abi: Option<BytecodeAbi>,
+    /// Fixed updates per second, see [`crate::engine::timestep::FixedTimestep`]
+    pub step_rate: Option<u32>,
+}
+
+/// A packed game: a Lua chunk plus the header that describes and protects it.
+///
+/// Layout (all integers little-endian):
+///
+/// | Offset | Size | Field                                  |
+/// |--------|------|----------------------------------------|
+/// | 0      | 4    | magic `QRCA`                           |
+/// | 4      | 1    | format version                         |
+/// | 5      | 2    | required engine API version            |
+/// | 7      | 1    | flags (bit 0: payload is deflated,     |
+/// |        |      | bit 1: with the preset dictionary,     |
+/// |        |      | bit 3: with a fallback source,         |
+/// |        |      | bit 4: with prefabs)                   |
+/// | 8      | 4    | CRC-32 of metadata + payload           |
+/// | 12     | 2    | metadata length                        |
+/// | 14     | 4    | payload length                         |
+/// | 18     | ..   | metadata entries (tag, length, value)  |
+/// | ..     | ..   | payload                                |
+/// | ..     | 96   | signed only: public key + signature    |
+///
+/// A payload deflated with the preset dictionary starts with the 4 byte
+/// dictionary id it was compressed against. A signed cartridge has flag bit 2
+/// set and ends with an Ed25519 public key and a signature over every byte
+/// before it, header included. With flag bit 4 the uncompressed payload starts
+/// with the 4 byte length of the prefab table, then the table. With flag bit 3
+/// what follows is the 4 byte length of the chunk, the chunk, then the fallback
+/// source.
+#[derive(Debug, Clone)]
+pub struct Cartridge {
+    pub api_version: u16,
+    pub compression: Compression,
+    pub metadata: CartridgeMetadata,
+    /// Lua chunk handed to `lua.load`, always stored here uncompressed
+    pub code: Vec<u8>,
+    /// Source shipped next to bytecode, run instead of it by engines whose Lua
+    /// can't load the bytecode. `from_bytes` has already made that swap.
+    pub fallback_source: Option<Vec<u8>>,
+    /// Prefabs the packer lifted out of the script, defined before the chunk runs.
+    /// See [`prefabs::extract`].
+    pub prefabs: Vec<(String, Prefab)>,
+    /// Set when the cartridge was read from signed bytes. `to_bytes` never
+    /// writes it back; sign the serialized cartridge with [`sign_bytes`].
+    pub signature: Option<CartridgeSignature>,
+}
+
+impl Cartridge {
+    pub fn new(code: Vec<u8>) -> Self {
+        Self {
+            api_version: ENGINE_API_VERSION,
+            compression: Compression::None,
+            metadata: CartridgeMetadata::default(),
+            code,
+            fallback_source: None,
+            prefabs: Vec::new(),
+            signature: None,
+        }
+    }
+
+    pub fn with_metadata(mut self, metadata: CartridgeMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_fallback_source(mut self, source: Vec<u8>) -> Self {
+        self.fallback_source = Some(source);
+        self
+    }
+
+    pub fn with_prefabs(mut self, prefabs: Vec<(String, Prefab)>) -> Self {
+        self.prefabs = prefabs;
+        self
+    }
+
+    /// Serialize the cartridge, unsigned, into the bytes stored in the QR code
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CartridgeError> {
+        let metadata = encode_metadata(&self.metadata)?;
+        let mut flags = 0;
+        let mut code = Vec::new();
+        if !self.prefabs.is_empty() {
+            let table = prefabs::encode(&self.prefabs);
+            code.extend_from_slice(&length_prefix(&table)?);
+            code.extend_from_slice(&table);
+            flags |= FLAG_PREFABS;
+        }
+        match &self.fallback_source {
+            Some(source) => {
+                code.extend_from_slice(&length_prefix(&self.code)?);
+                code.extend_from_slice(&self.code);
+                code.extend_from_slice(source);
+                flags |= FLAG_FALLBACK_SOURCE;
+            }
+            None => code.extend_from_slice(&self.code),
+        }
+        let payload = match self.compression {
+            Compression::None => code,
+            Compression::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(&code)?;
+                flags |= FLAG_COMPRESSED;
+                encoder.finish()?
+            }
+            Compression::Dictionary => {
+                let mut payload = dictionary::current().id.to_le_bytes().to_vec();
+                payload.extend(dictionary::compress(&code)?);
+                flags |= FLAG_COMPRESSED | FLAG_PRESET_DICTIONARY;
+                payload
+            }
+        };
+
+        let metadata_len = u16::try_from(metadata.len())
+            .map_err(|_| CartridgeError::InvalidMetadata("metadata is too long"))?;
+        let payload_len = u32::try_from(payload.len()).map_err(|_| CartridgeError::TooLarge {
+            size: payload.len(),
+            capacity: u32::MAX as usize,
+        })?;
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&metadata);
+        hasher.update(&payload);
+
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + metadata.len() + payload.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&self.api_version.to_le_bytes());
+        bytes.push(flags);
+        bytes.extend_from_slice(&hasher.finalize().to_le_bytes());
+        bytes.extend_from_slice(&metadata_len.to_le_bytes());
+        bytes.extend_from_slice(&payload_len.to_le_bytes());
+        bytes.extend_from_slice(&metadata);
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Parse and verify a serialized cartridge
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CartridgeError> {
+        let mut reader = ByteReader::new(bytes);
+
+        if !bytes.starts_with(MAGIC) {
+            return Err(CartridgeError::InvalidMagic);
+        }
+        reader.take(MAGIC.len())?;
+
+        let format_version = reader.u8()?;
+        if format_version != FORMAT_VERSION {
+            return Err(CartridgeError::UnsupportedFormatVersion(format_version));
+        }
+
+        let api_version = reader.u16()?;
+        if api_version > ENGINE_API_VERSION {
+            return Err(CartridgeError::UnsupportedApiVersion {
+                required: api_version,
+                supported: ENGINE_API_VERSION,
+            });
+        }
+
+        let flags = reader.u8()?;
+        let dictionary_without_deflate =
+            flags & FLAG_PRESET_DICTIONARY != 0 && flags & FLAG_COMPRESSED == 0;
+        if flags & !KNOWN_FLAGS != 0 || dictionary_without_deflate {
+            return Err(CartridgeError::UnknownFlags(flags));
+        }
+
+        let checksum = reader.u32()?;
+        let metadata_len = reader.u16()? as usize;
+        let payload_len = reader.u32()? as usize;
+
+        let signed_len = HEADER_SIZE + metadata_len + payload_len;
+        let expected_len = if flags & FLAG_SIGNED != 0 {
+            signed_len + SIGNATURE_BLOCK_SIZE
+        } else {
+            signed_len
+        };
+        if bytes.len() < expected_len {
+            return Err(CartridgeError::Truncated {
+                expected: expected_len,
+                actual: bytes.len(),
+            });
+        }
+
+        let metadata = reader.take(metadata_len)?;
+        let payload = reader.take(payload_len)?;
+        // Checked before anything is decompressed, so a tampered cartridge is
+        // reported as such instead of as whatever the edit happened to break.
+        // Whether the key belongs to a trusted publisher is up to the loader.
+        let signature = if flags & FLAG_SIGNED != 0 {
+            let public_key = reader.array::<PUBLIC_KEY_LENGTH>()?;
+            let signature = reader.array::<SIGNATURE_LENGTH>()?;
+            if !verify_signature(&bytes[..signed_len], &public_key, &signature) {
+                return Err(CartridgeError::SignatureMismatch);
+            }
+            Some(CartridgeSignature {
+                public_key,
+                signature,
+            })
+        } else {
+            None
+        };
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(metadata);
+        hasher.update(payload);
+        let actual = hasher.finalize();
+        if actual != checksum {
+            return Err(CartridgeError::ChecksumMismatch {
+                expected: checksum,
+                actual,
+            });
+        }
+
+        // A game this engine can't run is turned away before its payload is unpacked
+        let mut metadata = decode_metadata(metadata)?;
+        if let Some(manifest) = metadata.api_modules {
+            manifest.check_supported()?;
+        }
+
+        let (compression, code) = if flags & FLAG_PRESET_DICTIONARY != 0 {
+            let mut payload = ByteReader::new(payload);
+            let id = payload.u32()?;
+            let dictionary = dictionary::find(id).ok_or(CartridgeError::UnknownDictionary(id))?;
+            (
+                Compression::Dictionary,
+                dictionary::decompress(dictionary, payload.rest())?,
+            )
+        } else if flags & FLAG_COMPRESSED != 0 {
+            let mut code = Vec::new();
+            DeflateDecoder::new(payload)
+                .read_to_end(&mut code)
+                .map_err(CartridgeError::Decompress)?;
+            (Compression::Deflate, code)
+        } else {
+            (Compression::None, payload.to_vec())
+        };
+
+        let mut payload = ByteReader::new(&code);
+        let prefabs = if flags & FLAG_PREFABS != 0 {
+            let table_len = payload.u32()? as usize;
+            prefabs::decode(payload.take(table_len)?)?
+        } else {
+            Vec::new()
+        };
+        let (mut code, mut fallback_source) = if flags & FLAG_FALLBACK_SOURCE != 0 {
+            let code_len = payload.u32()? as usize;
+            let chunk = payload.take(code_len)?.to_vec();
+            (chunk, Some(payload.rest().to_vec()))
+        } else {
+            (payload.rest().to_vec(), None)
+        };
+
+        // Bytecode from a Lua with another ABI fails to load or misreads its
+        // constants, so it never reaches `lua.load`
+        if abi::is_bytecode(&code) {
+            let producer = metadata
+                .bytecode_abi
+                .or_else(|| BytecodeAbi::of_chunk(&code));
+            let native = BytecodeAbi::native();
+            if producer != Some(native) {
+                let Some(source) = fallback_source.take() else {
+                    return Err(CartridgeError::IncompatibleBytecode { producer });
+                };
+                warn!(
+                    ?producer,
+                    %native,
+                    "Cartridge bytecode was compiled for another Lua ABI, running its source"
+                );
+                code = source;
+                metadata.bytecode_abi = None;
+            }
+        }
+
+        Ok(Self {
+            api_version,
+            compression,
+            metadata,
+            code,
+            fallback_source,
+            prefabs,
+            signature,
+        })
+    }
+}
+
+/// Sign a serialized cartridge: set the signed flag, then append the public key
+/// and an Ed25519 signature over everything before it
+pub fn sign_bytes(bytes: &[u8], key: &SigningKey) -> Result<Vec<u8>, CartridgeError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(CartridgeError::InvalidMagic);
+    }
+    if bytes.len() < HEADER_SIZE {
+        return Err(CartridgeError::Truncated {
+            expected: HEADER_SIZE,
+            actual: bytes.len(),
+        });
+    }
+    if bytes[FLAGS_OFFSET] & FLAG_SIGNED != 0 {
+        return Err(CartridgeError::InvalidArguments(
+            "Cartridge is already signed".to_string(),
+        ));
+    }
+
+    let mut signed = Vec::with_capacity(bytes.len() + SIGNATURE_BLOCK_SIZE);
+    signed.extend_from_slice(bytes);
+    signed[FLAGS_OFFSET] |= FLAG_SIGNED;
+    let signature = key.sign(&signed);
+    signed.extend_from_slice(key.verifying_key().as_bytes());
+    signed.extend_from_slice(&signature.to_bytes());
+    Ok(signed)
+}
+
+/// A section's length as the 4 bytes written in front of it
+fn length_prefix(section: &[u8]) -> Result<[u8; 4], CartridgeError> {
+    let len = u32::try_from(section.len()).map_err(|_| CartridgeError::TooLarge {
+        size: section.len(),
+        capacity: u32::MAX as usize,
+    })?;
+    Ok(len.to_le_bytes())
+}
+
+fn verify_signature(
+    signed: &[u8],
+    public_key: &[u8; PUBLIC_KEY_LENGTH],
+    signature: &[u8; SIGNATURE_LENGTH],
+) -> bool {
+    // A malformed key can't have produced a valid signature, so it counts as tampering
+    VerifyingKey::from_bytes(public_key).is_ok_and(|key| {
+        key.verify_strict(signed, &Signature::from_bytes(signature))
+            .is_ok()
+    })
+}
+
+fn encode_metadata(metadata: &CartridgeMetadata) -> Result<Vec<u8>, CartridgeError> {
+    let mut bytes = Vec::new();
+    let texts = [(TAG_TITLE, &metadata.title), (TAG_AUTHOR, &metadata.author)];
+    for (tag, value) in texts {
+        if let Some(value) = value {
+            let len = u8::try_from(value.len())
+                .map_err(|_| CartridgeError::InvalidMetadata("field is longer than 255 bytes"))?;
+            bytes.push(tag);
+            bytes.push(len);
+            bytes.extend_from_slice(value.as_bytes());
+        }
+    }
+
+    let numbers = [
+        (TAG_INSTRUCTION_BUDGET, metadata.instruction_budget),
+        (TAG_MEMORY_LIMIT, metadata.memory_limit),
+        (TAG_STEP_RATE, metadata.step_rate),
+    ];
+    for (tag, value) in numbers {
+        if let Some(value) = value {
+            bytes.push(tag);
+            bytes.push(4);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    // Trailing zero bytes are dropped: a game using only the first eight modules
+    // costs one byte
+    if let Some(manifest) = metadata.api_modules {
+        let bits = manifest.bits().to_le_bytes();
+        let len = bits
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(1, |last| last + 1);
+        bytes.push(TAG_API_MODULES);
+        bytes.push(len as u8);
+        bytes.extend_from_slice(&bits[..len]);
+    }
+
+    if let Some(abi) = metadata.bytecode_abi {
+        bytes.push(TAG_BYTECODE_ABI);
+        bytes.push(BytecodeAbi::ENCODED_SIZE as u8);
+        bytes.extend_from_slice(&abi.to_bytes());
+    }
+    Ok(bytes)
+}
+
+fn decode_metadata(bytes: &[u8]) -> Result<CartridgeMetadata, CartridgeError> {
+    let mut metadata = CartridgeMetadata::default();
+    let mut reader = ByteReader::new(bytes);
+
+    while !reader.is_empty() {
+        let tag = reader.u8()?;
+        let len = reader.u8()? as usize;
+        let value = reader.take(len)?;
+        let text = || {
+            String::from_utf8(value.to_vec())
+                .map_err(|_| CartridgeError::InvalidMetadata("field is not valid UTF-8"))
+        };
+        let number = || {
+            let bytes: [u8; 4] = value
+                .try_into()
+                .map_err(|_| CartridgeError::InvalidMetadata("number is not a 4 byte integer"))?;
+            Ok::<_, CartridgeError>(u32::from_le_bytes(bytes))
+        };
+
+        match tag {
+            TAG_TITLE => metadata.title = Some(text()?),
+            TAG_AUTHOR => metadata.author = Some(text()?),
+            TAG_INSTRUCTION_BUDGET => metadata.instruction_budget = Some(number()?),
+            TAG_MEMORY_LIMIT => metadata.memory_limit = Some(number()?),
+            TAG_STEP_RATE => metadata.step_rate = Some(number()?),
+            TAG_API_MODULES => {
+                if value.is_empty() || value.len() > 4 {
+                    return Err(CartridgeError::InvalidMetadata("invalid API module list"));
+                }
+                let mut bits = [0; 4];
+                bits[..value.len()].copy_from_slice(value);
+                metadata.api_modules = Some(ApiManifest::from_bits(u32::from_le_bytes(bits)));
+            }
+            TAG_BYTECODE_ABI => {
+                metadata.bytecode_abi = Some(
+                    BytecodeAbi::from_bytes(value)
+                        .ok_or(CartridgeError::InvalidMetadata("invalid bytecode ABI"))?,
+                );
+            }
+            // Entries from newer packers are skipped so they stay loadable
+            _ => {}
+        }
+    }
+
+    Ok(metadata)
+}
+
+/// Cursor over a byte slice that reports running out of bytes as truncation
+pub(super) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub(super) fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    pub(super) fn take(&mut self, len: usize) -> Result<&'a [u8], CartridgeError> {
+        let end = self.pos + len;
+        if end > self.bytes.len() {
+            return Err(CartridgeError::Truncated {
+                expected: end,
+                actual: self.bytes.len(),
+            });
+        }
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    /// Everything not read yet
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.bytes[self.pos.min(self.bytes.len())..];
+        self.pos = self.bytes.len();
+        rest
+    }
+
+    pub(super) fn array<const N: usize>(&mut self) -> Result<[u8; N], CartridgeError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    pub(super) fn u8(&mut self) -> Result<u8, CartridgeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, CartridgeError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, CartridgeError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cartridge() -> Cartridge {
+        Cartridge::new(b"function on_frame(dt) print(dt) end".to_vec()).with_metadata(
+            CartridgeMetadata {
+                title: Some("Test".to_string()),
+                author: Some("QRcade".to_string()),
+                instruction_budget: Some(100_000),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn round_trips_with_every_compression() {
+        for compression in [
+            Compression::None,
+            Compression::Deflate,
+            Compression::Dictionary,
+        ] {
+            let original = cartridge().with_compression(compression);
+            let decoded = Cartridge::from_bytes(&original.to_bytes().unwrap()).unwrap();
+            assert_eq!(decoded.compression, compression);
+            assert_eq!(decoded.code, original.code);
+            assert_eq!(decoded.metadata, original.metadata);
+            assert_eq!(decoded.api_version, ENGINE_API_VERSION);
+            assert!(decoded.signature.is_none());
+        }
+    }
+
+    #[test]
+    fn rejects_a_flipped_checksum_byte() {
+        let mut bytes = cartridge().to_bytes().unwrap();
+        bytes[8] ^= 0xff;
+        assert!(matches!(
+            Cartridge::from_bytes(&bytes),
+            Err(CartridgeError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_a_bad_magic() {
+        let mut bytes = cartridge().to_bytes().unwrap();
+        bytes[..MAGIC.len()].copy_from_slice(b"QRCB");
+        assert!(matches!(
+            Cartridge::from_bytes(&bytes),
+            Err(CartridgeError::InvalidMagic)
+        ));
+    }
+}
@@ -0,0 +1,317 @@
+use super::CartridgeError;
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress, Status};
+use std::io;
+
+/// A preset deflate dictionary for QRcade cartridges
+#[derive(Debug)]
+pub struct Dictionary {
+    /// CRC-32 of `bytes`, stored in front of every payload compressed against it
+    pub id: u32,
+    pub bytes: &'static [u8],
+}
+
+/// Every preset dictionary the engine has shipped, oldest first. `pack` compresses
+/// with the newest; the loader picks whichever one a cartridge's id names.
+///
+/// Each holds the global names the Lua API registered at the time, the
+/// `on_start`/`on_frame`/`on_end` callbacks (no version has the later scene,
+/// `on_destroy` or `on_fixed_update` callbacks) and common standard library calls, as
+/// source text followed by the same calls compiled into a stripped Lua 5.4 chunk
+/// (8 byte integers and floats, little-endian), so the bytecode header and the usual
+/// global-call instruction patterns are in there too.
+///
+/// The files are frozen: a dictionary that changed would break every cartridge
+/// packed against it. New API names go into a new version appended to the list;
+/// the tests below rebuild every version from its names.
+const DICTIONARIES: &[Dictionary] = &[
+    Dictionary {
+        id: 0x4b10_f294,
+        bytes: include_bytes!("dictionaries/v1.bin"),
+    },
+    // Tags and queries
+    Dictionary {
+        id: 0x1088_2d0d,
+        bytes: include_bytes!("dictionaries/v2.bin"),
+    },
+    // Hierarchy
+    Dictionary {
+        id: 0x5efb_d469,
+        bytes: include_bytes!("dictionaries/v3.bin"),
+    },
+    // Prefabs
+    Dictionary {
+        id: 0x8b4e_5aa8,
+        bytes: include_bytes!("dictionaries/v4.bin"),
+    },
+    // Scenes
+    Dictionary {
+        id: 0x7447_b6ce,
+        bytes: include_bytes!("dictionaries/v5.bin"),
+    },
+];
+
+/// The dictionary new cartridges are compressed with
+pub fn current() -> &'static Dictionary {
+    &DICTIONARIES[DICTIONARIES.len() - 1]
+}
+
+/// The dictionary a cartridge was compressed with, if this engine has it
+pub fn find(id: u32) -> Option<&'static Dictionary> {
+    DICTIONARIES.iter().find(|dictionary| dictionary.id == id)
+}
+
+/// Raw deflate against the current preset dictionary
+pub fn compress(data: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let mut compress = Compress::new(Compression::best(), false);
+    compress
+        .set_dictionary(current().bytes)
+        .map_err(io::Error::other)?;
+
+    let mut output = Vec::with_capacity(data.len() + 64);
+    loop {
+        let consumed = compress.total_in() as usize;
+        let status = compress
+            .compress_vec(&data[consumed..], &mut output, FlushCompress::Finish)
+            .map_err(io::Error::other)?;
+        if status == Status::StreamEnd {
+            return Ok(output);
+        }
+        output.reserve(1024);
+    }
+}
+
+/// Inverse of [`compress`], against the dictionary the payload was compressed with
+pub fn decompress(dictionary: &Dictionary, payload: &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let invalid = |e| CartridgeError::Decompress(io::Error::new(io::ErrorKind::InvalidData, e));
+
+    let mut decompress = Decompress::new(false);
+    decompress
+        .set_dictionary(dictionary.bytes)
+        .map_err(invalid)?;
+
+    let mut output = Vec::with_capacity(payload.len() * 4);
+    loop {
+        let consumed = decompress.total_in() as usize;
+        let produced = decompress.total_out();
+        let status = decompress
+            .decompress_vec(&payload[consumed..], &mut output, FlushDecompress::Finish)
+            .map_err(invalid)?;
+        if status == Status::StreamEnd {
+            return Ok(output);
+        }
+        if output.len() == output.capacity() {
+            output.reserve(4096);
+        } else if decompress.total_out() == produced && decompress.total_in() as usize == consumed {
+            return Err(CartridgeError::Decompress(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "compressed payload ends early",
+            )));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cartridge::compiler::compile_script;
+    use crate::lua::api_module_globals;
+
+    /// Callbacks the engine looks up by name, present in nearly every game
+    const CALLBACK_NAMES: &[&str] = &["on_start", "on_frame", "on_end"];
+
+    /// Standard library calls that show up across the example games
+    const STDLIB_NAMES: &[&str] = &[
+        "print",
+        "ipairs",
+        "pairs",
+        "tostring",
+        "math.random",
+        "math.floor",
+        "math.abs",
+        "math.min",
+        "math.max",
+        "table.insert",
+        "table.remove",
+        "string.format",
+    ];
+
+    /// The API globals each version added, in [`DICTIONARIES`] order. A new version
+    /// is a new entry here; `write_new_dictionary` then produces its file.
+    const ADDED_NAMES: &[&[&str]] = &[
+        // v1
+        &[
+            "ALIGN.BOTTOM",
+            "ALIGN.CENTER",
+            "ALIGN.LEFT",
+            "ALIGN.MIDDLE",
+            "ALIGN.RIGHT",
+            "ALIGN.TOP",
+            "TEXT.GAME_OVER",
+            "TEXT.GAME_PAUSED",
+            "TEXT.GO",
+            "TEXT.HIGH_SCORE",
+            "TEXT.LEVEL",
+            "TEXT.LIVES",
+            "TEXT.NEW_HIGH_SCORE",
+            "TEXT.PRESS_ESC_TO_QUIT",
+            "TEXT.PRESS_SPACE",
+            "TEXT.READY",
+            "TEXT.SCORE",
+            "TEXT.TRY_AGAIN",
+            "TEXT.YOU_WIN",
+            "add_attractive_gravity",
+            "add_downward_gravity",
+            "add_repulsive_gravity",
+            "add_shape",
+            "add_text",
+            "can_drag_entity",
+            "check_position_walkable",
+            "clear_tile",
+            "create_entity",
+            "create_tilemap",
+            "destroy_entity",
+            "end_drag",
+            "get_dimensions",
+            "get_text",
+            "get_tile_size",
+            "get_tilemap",
+            "get_transform",
+            "get_velocity",
+            "is_colliding",
+            "is_dragging",
+            "is_key_pressed",
+            "is_mouse_pressed",
+            "is_walkable",
+            "make_entity_draggable",
+            "query_tilemap",
+            "remove_entity_draggable",
+            "set_angular_velocity",
+            "set_draggable_enabled",
+            "set_gravity_enabled",
+            "set_horizontal_velocity",
+            "set_rotation_velocity",
+            "set_text_color",
+            "set_text_scale",
+            "set_text_value",
+            "set_text_visibility",
+            "set_tile",
+            "set_transform",
+            "set_velocity",
+            "set_zero_velocity",
+            "start_drag",
+            "timer.clear",
+            "timer.set_interval",
+            "update_drag",
+            "update_text",
+        ],
+        // v2: Tags and queries
+        &[
+            "add_tag",
+            "count_entities",
+            "each_entity",
+            "get_tags",
+            "has_tag",
+            "query",
+            "remove_tag",
+        ],
+        // v3: Hierarchy
+        &[
+            "attach",
+            "detach",
+            "get_children",
+            "get_parent",
+            "get_world_transform",
+        ],
+        // v4: Prefabs
+        &["define_prefab", "spawn"],
+        // v5: Scenes
+        &[
+            "current_scene",
+            "define_scene",
+            "get_scene_stack",
+            "pop_scene",
+            "push_scene",
+            "switch_scene",
+        ],
+    ];
+
+    /// Every API global a version holds, sorted
+    fn api_names(version: usize) -> Vec<&'static str> {
+        let mut names: Vec<&str> = ADDED_NAMES[..=version].concat();
+        names.sort_unstable();
+        names
+    }
+
+    fn build_dictionary(names: &[&str]) -> Vec<u8> {
+        let mut source = String::from("return function(a, b, c)\n");
+        for callback in CALLBACK_NAMES {
+            source.push_str(&format!("  {}(a)\n", callback));
+        }
+        for name in STDLIB_NAMES {
+            source.push_str(&format!("  {}(a, b)\n", name));
+        }
+        for name in names {
+            if name.contains('.') {
+                source.push_str(&format!("  local v = {}\n", name));
+            } else {
+                source.push_str(&format!("  {}(a, b, c)\n", name));
+            }
+        }
+        source.push_str("end\n");
+
+        // Bytecode goes last: the most recent dictionary bytes are the cheapest to match
+        let mut dictionary = source.clone().into_bytes();
+        dictionary.extend(compile_script(source.as_bytes(), "dictionary", true).unwrap());
+        dictionary
+    }
+
+    #[test]
+    fn dictionaries_rebuild_from_their_names() {
+        assert_eq!(ADDED_NAMES.len(), DICTIONARIES.len());
+        for (version, dictionary) in DICTIONARIES.iter().enumerate() {
+            assert!(
+                build_dictionary(&api_names(version)) == dictionary.bytes,
+                "v{} doesn't rebuild byte-for-byte from its names",
+                version + 1
+            );
+            assert_eq!(
+                dictionary.id,
+                crc32fast::hash(dictionary.bytes),
+                "v{} id isn't the CRC-32 of its file",
+                version + 1
+            );
+        }
+    }
+
+    #[test]
+    fn current_dictionary_has_every_api_global() {
+        let mut names: Vec<String> = api_module_globals()
+            .unwrap()
+            .into_iter()
+            .flat_map(|(_, names)| names)
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, api_names(DICTIONARIES.len() - 1));
+    }
+
+    /// Writes the file for the newest `ADDED_NAMES` entry and prints its id:
+    /// `cargo test write_new_dictionary -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn write_new_dictionary() {
+        let version = ADDED_NAMES.len() - 1;
+        let path = format!(
+            "{}/src/cartridge/dictionaries/v{}.bin",
+            env!("CARGO_MANIFEST_DIR"),
+            version + 1
+        );
+        assert!(
+            !std::path::Path::new(&path).exists(),
+            "{} is frozen; add a new version instead",
+            path
+        );
+        let dictionary = build_dictionary(&api_names(version));
+        std::fs::write(&path, &dictionary).unwrap();
+        println!("{}: id 0x{:08x}", path, crc32fast::hash(&dictionary));
+    }
+}
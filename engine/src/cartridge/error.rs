@@ -0,0 +1,219 @@
+use super::abi::BytecodeAbi;
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum CartridgeError {
+    Io(std::io::Error),
+    Compile(mlua::Error),
+    Syntax { line: usize, message: String },
+    Qr(qrcode::types::QrError),
+    Image(image::ImageError),
+    QrDecode(rqrr::DeQRError),
+    NoQrCode,
+    InvalidArguments(String),
+    InvalidMagic,
+    InvalidText,
+    UnsupportedFormatVersion(u8),
+    UnsupportedApiVersion { required: u16, supported: u16 },
+    UnsupportedApiModules { modules: Vec<usize> },
+    UnknownFlags(u8),
+    Truncated { expected: usize, actual: usize },
+    ChecksumMismatch { expected: u32, actual: u32 },
+    InvalidMetadata(&'static str),
+    InvalidPrefabs(&'static str),
+    Decompress(std::io::Error),
+    UnknownDictionary(u32),
+    IncompatibleBytecode { producer: Option<BytecodeAbi> },
+    TooLarge { size: usize, capacity: usize },
+    TooManyChunks { chunks: usize, max: usize },
+    InvalidChunk(&'static str),
+    MissingChunks { missing: Vec<usize>, total: usize },
+    InvalidKey { path: PathBuf, reason: String },
+    SignatureMismatch,
+    RobustnessFailed { failures: usize },
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CartridgeError::Io(e) => write!(f, "I/O error: {}", e),
+            CartridgeError::Compile(e) => write!(f, "Failed to compile script: {}", e),
+            CartridgeError::Syntax { line, message } => {
+                write!(f, "Syntax error on line {}: {}", line, message)
+            }
+            CartridgeError::Qr(e) => write!(f, "Failed to encode QR code: {}", e),
+            CartridgeError::Image(e) => write!(f, "Image error: {}", e),
+            CartridgeError::QrDecode(e) => write!(f, "Failed to decode QR code: {}", e),
+            CartridgeError::NoQrCode => write!(f, "No QR code found in image"),
+            CartridgeError::InvalidArguments(msg) => write!(f, "{}", msg),
+            CartridgeError::InvalidMagic => write!(f, "Data is not a QRcade cartridge"),
+            CartridgeError::InvalidText => write!(
+                f,
+                "Text is not an encoded QRcade cartridge \
+                 (expected Base45, Base64url or a qrcade: URI)"
+            ),
+            CartridgeError::UnsupportedFormatVersion(version) => {
+                write!(f, "Unsupported cartridge format version {}", version)
+            }
+            CartridgeError::UnsupportedApiVersion {
+                required,
+                supported,
+            } => write!(
+                f,
+                "Cartridge needs engine API version {} but this engine supports up to {}",
+                required, supported
+            ),
+            CartridgeError::UnsupportedApiModules { modules } => {
+                let numbers: Vec<String> = modules.iter().map(|id| format!("#{}", id)).collect();
+                write!(
+                    f,
+                    "Cartridge needs API module(s) {} that this engine does not provide; \
+                     it was packed for a newer engine",
+                    numbers.join(", ")
+                )
+            }
+            CartridgeError::UnknownFlags(flags) => {
+                write!(f, "Cartridge uses unknown flags {:#010b}", flags)
+            }
+            CartridgeError::Truncated { expected, actual } => write!(
+                f,
+                "Cartridge is truncated: expected {} bytes, got {}",
+                expected, actual
+            ),
+            CartridgeError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Cartridge is corrupted: checksum {:08x} does not match {:08x}",
+                actual, expected
+            ),
+            CartridgeError::InvalidMetadata(reason) => {
+                write!(f, "Invalid cartridge metadata: {}", reason)
+            }
+            CartridgeError::InvalidPrefabs(reason) => {
+                write!(f, "Invalid cartridge prefabs: {}", reason)
+            }
+            CartridgeError::Decompress(e) => {
+                write!(f, "Cartridge payload failed to decompress: {}", e)
+            }
+            CartridgeError::UnknownDictionary(id) => write!(
+                f,
+                "Cartridge was compressed with preset dictionary {:08x}, which this engine \
+                 doesn't have; it was packed by a newer engine",
+                id
+            ),
+            CartridgeError::IncompatibleBytecode { producer } => {
+                match producer {
+                    Some(producer) => write!(
+                        f,
+                        "Cartridge bytecode was compiled for {} but this engine runs {}",
+                        producer,
+                        BytecodeAbi::native()
+                    )?,
+                    None => write!(
+                        f,
+                        "Cartridge bytecode is in a format this engine can't read"
+                    )?,
+                }
+                write!(
+                    f,
+                    "; repack it with --fallback-source to ship its source too"
+                )
+            }
+            CartridgeError::TooLarge { size, capacity } => write!(
+                f,
+                "Cartridge is {} bytes but a single QR code holds at most {} bytes",
+                size, capacity
+            ),
+            CartridgeError::TooManyChunks { chunks, max } => write!(
+                f,
+                "Cartridge needs {} QR codes but at most {} chunks are supported",
+                chunks, max
+            ),
+            CartridgeError::InvalidChunk(reason) => {
+                write!(f, "Invalid cartridge chunk: {}", reason)
+            }
+            CartridgeError::MissingChunks { missing, total } => {
+                let numbers: Vec<String> = missing.iter().map(ToString::to_string).collect();
+                write!(
+                    f,
+                    "Cartridge is incomplete: missing chunk(s) {} of {}",
+                    numbers.join(", "),
+                    total
+                )
+            }
+            CartridgeError::InvalidKey { path, reason } => {
+                write!(f, "Invalid key file {}: {}", path.display(), reason)
+            }
+            CartridgeError::SignatureMismatch => write!(
+                f,
+                "Cartridge signature does not match its contents, it has been tampered with"
+            ),
+            CartridgeError::RobustnessFailed { failures } => write!(
+                f,
+                "{} robustness check(s) failed: a code decoded to the wrong bytes \
+                 or an undistorted code did not decode",
+                failures
+            ),
+        }
+    }
+}
+
+impl CartridgeError {
+    /// Whether the data itself is damaged or unsupported, as opposed to a
+    /// problem reading it or running the packer
+    pub fn is_bad_cartridge(&self) -> bool {
+        matches!(
+            self,
+            CartridgeError::QrDecode(_)
+                | CartridgeError::NoQrCode
+                | CartridgeError::InvalidMagic
+                | CartridgeError::InvalidText
+                | CartridgeError::UnsupportedFormatVersion(_)
+                | CartridgeError::UnsupportedApiVersion { .. }
+                | CartridgeError::UnsupportedApiModules { .. }
+                | CartridgeError::UnknownFlags(_)
+                | CartridgeError::Truncated { .. }
+                | CartridgeError::ChecksumMismatch { .. }
+                | CartridgeError::InvalidMetadata(_)
+                | CartridgeError::InvalidPrefabs(_)
+                | CartridgeError::Decompress(_)
+                | CartridgeError::UnknownDictionary(_)
+                | CartridgeError::IncompatibleBytecode { .. }
+                | CartridgeError::InvalidChunk(_)
+                | CartridgeError::MissingChunks { .. }
+                | CartridgeError::SignatureMismatch
+        )
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+impl From<std::io::Error> for CartridgeError {
+    fn from(e: std::io::Error) -> Self {
+        CartridgeError::Io(e)
+    }
+}
+
+impl From<mlua::Error> for CartridgeError {
+    fn from(e: mlua::Error) -> Self {
+        CartridgeError::Compile(e)
+    }
+}
+
+impl From<qrcode::types::QrError> for CartridgeError {
+    fn from(e: qrcode::types::QrError) -> Self {
+        CartridgeError::Qr(e)
+    }
+}
+
+impl From<image::ImageError> for CartridgeError {
+    fn from(e: image::ImageError) -> Self {
+        CartridgeError::Image(e)
+    }
+}
+
+impl From<rqrr::DeQRError> for CartridgeError {
+    fn from(e: rqrr::DeQRError) -> Self {
+        CartridgeError::QrDecode(e)
+    }
+}
@@ -0,0 +1,265 @@
+use super::CartridgeError;
+
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Multi-character symbols, longest first so the lexer always takes the longest match
+const SYMBOLS: &[&str] = &[
+    "...", "..", "==", "~=", "<=", ">=", "<<", ">>", "//", "::", "+", "-", "*", "/", "%", "^", "#",
+    "&", "~", "|", "<", ">", "=", "(", ")", "{", "}", "[", "]", ";", ":", ",", ".",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Name,
+    Keyword,
+    Number,
+    String,
+    Symbol,
+    Eof,
+}
+
+/// A Lua 5.4 token, keeping its exact source text so it can be written back out unchanged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    /// Line the token starts on, counted from 1
+    pub line: usize,
+    /// Line the token ends on; differs from `line` only for multi-line strings
+    pub end_line: usize,
+    /// Character offsets of the token in the source, end exclusive
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token {
+    pub fn is(&self, kind: TokenKind, text: &str) -> bool {
+        self.kind == kind && self.text == text
+    }
+
+    pub fn is_symbol(&self, text: &str) -> bool {
+        self.is(TokenKind::Symbol, text)
+    }
+
+    pub fn is_keyword(&self, text: &str) -> bool {
+        self.is(TokenKind::Keyword, text)
+    }
+}
+
+pub fn is_keyword(name: &str) -> bool {
+    KEYWORDS.contains(&name)
+}
+
+/// Split Lua source into tokens, dropping whitespace and comments.
+/// The returned list always ends with a single `Eof` token.
+pub fn tokenize(source: &str) -> Result<Vec<Token>, CartridgeError> {
+    Lexer {
+        chars: source.chars().collect(),
+        pos: 0,
+        line: 1,
+    }
+    .run()
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Lexer {
+    fn run(mut self) -> Result<Vec<Token>, CartridgeError> {
+        let mut tokens = Vec::new();
+        loop {
+            self.skip_whitespace_and_comments()?;
+            let line = self.line;
+            let Some(c) = self.peek(0) else {
+                tokens.push(Token {
+                    kind: TokenKind::Eof,
+                    text: String::new(),
+                    line,
+                    end_line: line,
+                    start: self.pos,
+                    end: self.pos,
+                });
+                return Ok(tokens);
+            };
+
+            let start = self.pos;
+            let kind = if c.is_ascii_alphabetic() || c == '_' {
+                while self
+                    .peek(0)
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+                {
+                    self.pos += 1;
+                }
+                if is_keyword(&self.text_from(start)) {
+                    TokenKind::Keyword
+                } else {
+                    TokenKind::Name
+                }
+            } else if c.is_ascii_digit()
+                || (c == '.' && self.peek(1).is_some_and(|c| c.is_ascii_digit()))
+            {
+                self.read_number();
+                TokenKind::Number
+            } else if c == '"' || c == '\'' {
+                self.read_quoted_string(c)?;
+                TokenKind::String
+            } else if c == '[' && self.long_bracket_level().is_some() {
+                self.read_long_bracket()?;
+                TokenKind::String
+            } else {
+                let symbol = SYMBOLS
+                    .iter()
+                    .find(|symbol| self.starts_with(symbol))
+                    .ok_or_else(|| self.error(format!("unexpected character '{}'", c)))?;
+                self.pos += symbol.len();
+                TokenKind::Symbol
+            };
+
+            tokens.push(Token {
+                kind,
+                text: self.text_from(start),
+                line,
+                end_line: self.line,
+                start,
+                end: self.pos,
+            });
+        }
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek(i) == Some(c))
+    }
+
+    fn text_from(&self, start: usize) -> String {
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn error(&self, message: String) -> CartridgeError {
+        CartridgeError::Syntax {
+            line: self.line,
+            message,
+        }
+    }
+
+    /// Consume one line break, treating `\r\n` and `\n\r` as a single break like Lua does
+    fn newline(&mut self) {
+        let first = self.peek(0);
+        self.pos += 1;
+        let second = self.peek(0);
+        if matches!(second, Some('\n') | Some('\r')) && second != first {
+            self.pos += 1;
+        }
+        self.line += 1;
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), CartridgeError> {
+        while let Some(c) = self.peek(0) {
+            match c {
+                '\n' | '\r' => self.newline(),
+                ' ' | '\t' | '\x0b' | '\x0c' => self.pos += 1,
+                '-' if self.peek(1) == Some('-') => {
+                    self.pos += 2;
+                    if self.peek(0) == Some('[') && self.long_bracket_level().is_some() {
+                        self.read_long_bracket()?;
+                    } else {
+                        while self.peek(0).is_some_and(|c| c != '\n' && c != '\r') {
+                            self.pos += 1;
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Same rule as Lua's `read_numeral`: take every alphanumeric and `.`, plus a sign
+    /// right after an exponent marker. Malformed numbers are left for Lua to reject.
+    fn read_number(&mut self) {
+        let hex = self.peek(0) == Some('0') && matches!(self.peek(1), Some('x') | Some('X'));
+        let exponent = if hex { ['p', 'P'] } else { ['e', 'E'] };
+        while let Some(c) = self.peek(0) {
+            if exponent.contains(&c) && matches!(self.peek(1), Some('+') | Some('-')) {
+                self.pos += 2;
+            } else if c.is_ascii_alphanumeric() || c == '_' || c == '.' {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn read_quoted_string(&mut self, quote: char) -> Result<(), CartridgeError> {
+        self.pos += 1;
+        loop {
+            match self.peek(0) {
+                None | Some('\n') | Some('\r') => {
+                    return Err(self.error("unfinished string".to_string()))
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek(0) {
+                        Some('\n') | Some('\r') => self.newline(),
+                        // `\z` skips the following whitespace, line breaks included
+                        Some('z') => {
+                            self.pos += 1;
+                            while let Some(c) = self.peek(0) {
+                                match c {
+                                    '\n' | '\r' => self.newline(),
+                                    c if c.is_whitespace() => self.pos += 1,
+                                    _ => break,
+                                }
+                            }
+                        }
+                        Some(_) => self.pos += 1,
+                        None => return Err(self.error("unfinished string".to_string())),
+                    }
+                }
+                Some(c) => {
+                    self.pos += 1;
+                    if c == quote {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Level of the long bracket `[==[` starting at the current position, if it is one
+    fn long_bracket_level(&self) -> Option<usize> {
+        let mut level = 0;
+        while self.peek(1 + level) == Some('=') {
+            level += 1;
+        }
+        (self.peek(1 + level) == Some('[')).then_some(level)
+    }
+
+    fn read_long_bracket(&mut self) -> Result<(), CartridgeError> {
+        let level = self.long_bracket_level().unwrap_or_default();
+        self.pos += level + 2;
+        let close: String = format!("]{}]", "=".repeat(level));
+        loop {
+            match self.peek(0) {
+                None => return Err(self.error("unfinished long string or comment".to_string())),
+                Some('\n') | Some('\r') => self.newline(),
+                Some(_) if self.starts_with(&close) => {
+                    self.pos += close.len();
+                    return Ok(());
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use super::container::SIGNATURE_BLOCK_SIZE;
+use super::loader::{file_stem, is_image, read_game};
+use super::signing::{self, Verification};
+use super::Cartridge;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Where the launcher looks for games, unless `QRCADE_LIBRARY` points somewhere
+/// else. `pack` writes here by default, so freshly packed games show up in it.
+pub const DEFAULT_LIBRARY_DIR: &str = "cartridges";
+
+/// One game in the library and the file(s) it is read from
+#[derive(Debug)]
+pub struct LibraryEntry {
+    /// File name the game was found under, chunk suffix removed
+    pub name: String,
+    pub paths: Vec<PathBuf>,
+    /// The game as read at scan time, or why it couldn't be read
+    pub game: Result<LibraryGame, String>,
+}
+
+#[derive(Debug)]
+pub struct LibraryGame {
+    pub cartridge: Cartridge,
+    /// Bytes of the packed cartridge, or of the source for a loose script
+    pub size: usize,
+    pub verification: Verification,
+}
+
+impl LibraryEntry {
+    pub fn title(&self) -> &str {
+        self.game
+            .as_ref()
+            .ok()
+            .and_then(|game| game.cartridge.metadata.title.as_deref())
+            .unwrap_or(&self.name)
+    }
+}
+
+/// Find every game in `dir`, sorted by name.
+///
+/// `.lua` scripts, `.qrc` cartridges, `.txt` cartridges shared as text, QR
+/// images and subdirectories of images are all games. Files that `pack` writes
+/// for the same game (`snake.qrc`, `snake.png`, `snake-card.png`, `2048-1of3.png`,
+/// ...) are listed once, read from the packed file when there is one. Unreadable
+/// games are listed with the reason.
+pub fn scan(dir: &Path, trusted_keys_dir: &Path) -> Vec<LibraryEntry> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        warn!(dir = %dir.display(), "Library directory not found");
+        return Vec::new();
+    };
+
+    let mut groups: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.is_dir() {
+            groups.insert(file_stem(&path), vec![path]);
+        } else if is_game_file(&path) {
+            let stem = file_stem(&path);
+            let name = game_name(&stem).to_string();
+            groups.entry(name).or_default().push(path);
+        }
+    }
+
+    let library: Vec<LibraryEntry> = groups
+        .into_iter()
+        .map(|(name, paths)| {
+            let paths = preferred_paths(paths);
+            let game = read_entry(&paths, trusted_keys_dir);
+            if let Err(e) = &game {
+                warn!(game = %name, error = %e, "Skipping unreadable game in library");
+            }
+            LibraryEntry { name, paths, game }
+        })
+        .collect();
+    info!(dir = %dir.display(), games = library.len(), "Scanned game library");
+    library
+}
+
+fn read_entry(paths: &[PathBuf], trusted_keys_dir: &Path) -> Result<LibraryGame, String> {
+    let cartridge = read_game(paths).map_err(|e| e.to_string())?;
+    let size = match paths {
+        [path] if path.extension().is_some_and(|ext| ext == "lua") => cartridge.code.len(),
+        _ => {
+            let signature = cartridge
+                .signature
+                .as_ref()
+                .map_or(0, |_| SIGNATURE_BLOCK_SIZE);
+            cartridge.to_bytes().map_err(|e| e.to_string())?.len() + signature
+        }
+    };
+    let verification = signing::verify(&cartridge, trusted_keys_dir);
+    Ok(LibraryGame {
+        cartridge,
+        size,
+        verification,
+    })
+}
+
+fn is_game_file(path: &Path) -> bool {
+    is_image(path)
+        || path
+            .extension()
+            .is_some_and(|ext| ext == "qrc" || ext == "lua" || ext == "txt")
+}
+
+/// A packed cartridge beats its images: it is exact and quicker to read. All the
+/// images of a multi-code game are kept together.
+fn preferred_paths(mut paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths.sort();
+    for extension in ["qrc", "lua", "txt"] {
+        if let Some(path) = paths
+            .iter()
+            .find(|path| path.extension().is_some_and(|ext| ext == extension))
+        {
+            return vec![path.clone()];
+        }
+    }
+    paths
+}
+
+/// `snake-card` -> `snake`, `2048-1of3` -> `2048`: the game a file written by
+/// `pack` belongs to
+fn game_name(stem: &str) -> &str {
+    stem.strip_suffix("-card")
+        .or_else(|| strip_chunk_suffix(stem))
+        .unwrap_or(stem)
+}
+
+/// `2048-1of3` -> `2048`, the names `pack` gives the codes of a multi-code game
+fn strip_chunk_suffix(stem: &str) -> Option<&str> {
+    let (name, suffix) = stem.rsplit_once('-')?;
+    let (index, total) = suffix.split_once("of")?;
+    let is_number = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+    (is_number(index) && is_number(total)).then_some(name)
+}
@@ -0,0 +1,164 @@
+use super::chunk::{Chunk, Reassembler, CHUNK_MAGIC};
+use super::container::MAGIC;
+use super::{qr, text, Cartridge, CartridgeError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Read a game from disk.
+///
+/// Accepts plain `.lua` source, packed `.qrc` cartridges, `.txt` files of
+/// cartridges shared as text and PNG/JPEG images of a cartridge's QR code
+/// (screenshots, photos or game cards). A cartridge split across several QR
+/// codes can be given as a list of images or a directory holding them, in any
+/// order. Packed data always goes through `Cartridge::from_bytes`, so damaged
+/// scans surface as typed errors.
+pub fn read_game(paths: &[PathBuf]) -> Result<Cartridge, CartridgeError> {
+    let paths = expand_directories(paths)?;
+
+    let cartridge = match paths.as_slice() {
+        [] => {
+            return Err(CartridgeError::InvalidArguments(
+                "No cartridge images found".to_string(),
+            ))
+        }
+        [path] if !is_image(path) => read_file(path)?,
+        _ => read_images(&paths)?,
+    };
+
+    info!(
+        title = cartridge.metadata.title.as_deref().unwrap_or("untitled"),
+        author = cartridge.metadata.author.as_deref().unwrap_or("unknown"),
+        code_size = cartridge.code.len(),
+        "Loaded game"
+    );
+    Ok(cartridge)
+}
+
+/// Read a cartridge pasted as text (Base45, Base64url or a `qrcade:` URI), one
+/// text per chunk for a multi-code game
+pub fn read_text(texts: &[String]) -> Result<Cartridge, CartridgeError> {
+    let payloads = texts
+        .iter()
+        .map(|text| text::decode(text).ok_or(CartridgeError::InvalidText))
+        .collect::<Result<Vec<_>, _>>()?;
+    assemble(payloads)
+}
+
+fn read_file(path: &Path) -> Result<Cartridge, CartridgeError> {
+    if path.extension().is_some_and(|ext| ext == "qrc") {
+        return Cartridge::from_bytes(&fs::read(path)?);
+    }
+    if path.extension().is_some_and(|ext| ext == "txt") {
+        let contents = fs::read_to_string(path)?;
+        let texts: Vec<String> = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect();
+        return read_text(&texts);
+    }
+
+    // Loose scripts have no header, so describe them from the file name
+    let mut cartridge = Cartridge::new(fs::read(path)?);
+    cartridge.metadata.title = Some(file_stem(path));
+    Ok(cartridge)
+}
+
+/// Decode every QR code in the images and rebuild the cartridge they carry
+fn read_images(paths: &[PathBuf]) -> Result<Cartridge, CartridgeError> {
+    let mut all_payloads = Vec::new();
+    let mut last_error = None;
+
+    for path in paths {
+        let payloads = match qr::decode_image(path) {
+            Ok(payloads) => payloads,
+            // One unreadable image shouldn't hide which chunks are actually missing
+            Err(e) if paths.len() > 1 => {
+                warn!(path = %path.display(), error = %e, "Skipping unreadable image");
+                last_error = Some(e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        for payload in payloads {
+            info!(path = %path.display(), size = payload.len(), "Decoded QR code");
+            all_payloads.push(unwrap_text_payload(payload));
+        }
+    }
+
+    if all_payloads.is_empty() {
+        return Err(last_error.unwrap_or(CartridgeError::NoQrCode));
+    }
+    assemble(all_payloads)
+}
+
+/// Codes the packer wrote in alphanumeric mode hold the cartridge as Base45
+/// text; hand back the bytes it stands for. Binary payloads pass through.
+pub fn unwrap_text_payload(payload: Vec<u8>) -> Vec<u8> {
+    if payload.starts_with(MAGIC) || payload.starts_with(CHUNK_MAGIC) {
+        return payload;
+    }
+    std::str::from_utf8(&payload)
+        .ok()
+        .and_then(text::decode)
+        .unwrap_or(payload)
+}
+
+/// Rebuild a cartridge from decoded payloads: a whole cartridge, or chunks in
+/// any order
+fn assemble(payloads: Vec<Vec<u8>>) -> Result<Cartridge, CartridgeError> {
+    let mut reassembler = Reassembler::new();
+    let mut whole = None;
+
+    for payload in payloads {
+        if Chunk::is_chunk(&payload) {
+            reassembler.add(Chunk::from_bytes(&payload)?)?;
+        } else if whole.is_none() {
+            whole = Some(payload);
+        }
+    }
+
+    if let Some(bytes) = whole {
+        return Cartridge::from_bytes(&bytes);
+    }
+    if reassembler.is_empty() {
+        return Err(CartridgeError::NoQrCode);
+    }
+    Cartridge::from_bytes(&reassembler.finish()?)
+}
+
+/// Replace each directory with the images inside it, sorted by name
+fn expand_directories(paths: &[PathBuf]) -> Result<Vec<PathBuf>, CartridgeError> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            let mut images: Vec<PathBuf> = fs::read_dir(path)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| is_image(path))
+                .collect();
+            images.sort();
+            expanded.extend(images);
+        } else {
+            expanded.push(path.clone());
+        }
+    }
+    Ok(expanded)
+}
+
+pub fn is_image(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    matches!(
+        extension.as_deref(),
+        Some("png") | Some("jpg") | Some("jpeg")
+    )
+}
+
+pub fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "cartridge".to_string())
+}
@@ -0,0 +1,98 @@
+use super::lexer::tokenize;
+use super::scope::resolve_names;
+use super::CartridgeError;
+use crate::lua::{api_module_globals, API_MODULES};
+use std::fmt;
+
+/// The engine API modules a cartridge declares it uses, one bit per entry of
+/// [`API_MODULES`]. The loader registers only these into the game's Lua state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ApiManifest {
+    bits: u32,
+}
+
+impl ApiManifest {
+    pub fn from_bits(bits: u32) -> Self {
+        Self { bits }
+    }
+
+    pub fn bits(self) -> u32 {
+        self.bits
+    }
+
+    /// Every module this engine registers
+    pub fn all() -> Self {
+        Self::from_bits(supported_bits())
+    }
+
+    /// Work out the modules a script needs from the globals it reads or writes.
+    /// A global only reached through `_G` or a computed name is not seen.
+    pub fn infer(source: &str) -> Result<Self, CartridgeError> {
+        let tokens = tokenize(source)?;
+        let resolution = resolve_names(&tokens)?;
+        let modules = api_module_globals()?;
+
+        let mut bits = 0;
+        for (id, (_, globals)) in modules.iter().enumerate() {
+            // `timer.set_interval` is registered as the global table `timer`
+            let used = globals.iter().any(|global| {
+                let root = global.split('.').next().unwrap_or(global);
+                resolution.globals.contains(root)
+            });
+            if used {
+                bits |= 1 << id;
+            }
+        }
+        Ok(Self::from_bits(bits))
+    }
+
+    pub fn contains(self, module: &str) -> bool {
+        API_MODULES
+            .iter()
+            .position(|(name, _)| *name == module)
+            .is_some_and(|id| self.bits & (1 << id) != 0)
+    }
+
+    /// Names of the declared modules this engine has, in registration order
+    pub fn module_names(self) -> Vec<&'static str> {
+        API_MODULES
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| self.bits & (1 << id) != 0)
+            .map(|(_, (name, _))| *name)
+            .collect()
+    }
+
+    /// Reject a manifest naming modules from a newer engine
+    pub fn check_supported(self) -> Result<(), CartridgeError> {
+        let unknown = self.bits & !supported_bits();
+        if unknown == 0 {
+            return Ok(());
+        }
+        Err(CartridgeError::UnsupportedApiModules {
+            modules: (0..u32::BITS)
+                .filter(|id| unknown & (1 << id) != 0)
+                .map(|id| id as usize)
+                .collect(),
+        })
+    }
+}
+
+impl fmt::Display for ApiManifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self
+            .module_names()
+            .into_iter()
+            .map(|name| name.trim_end_matches("_api"))
+            .collect();
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join(", "))
+        }
+    }
+}
+
+fn supported_bits() -> u32 {
+    (1 << API_MODULES.len()) - 1
+}
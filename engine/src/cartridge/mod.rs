@@ -0,0 +1,23 @@
+pub mod abi;
+pub mod analyzer;
+pub mod card;
+pub mod chunk;
+pub mod compiler;
+pub mod container;
+pub mod dictionary;
+pub mod error;
+pub mod lexer;
+pub mod library;
+pub mod loader;
+pub mod manifest;
+pub mod minifier;
+pub mod packer;
+pub mod prefabs;
+pub mod qr;
+pub mod robustness;
+pub mod scope;
+pub mod signing;
+pub mod text;
+
+pub use container::Cartridge;
+pub use error::CartridgeError;
@@ -0,0 +1,773 @@
+use super::abi::BytecodeAbi;
+use super::card;
+use super::chunk;
+use super::compiler::compile_script;
+use super::container::{sign_bytes, CartridgeMetadata, Compression, ENGINE_API_VERSION};
+use super::loader::file_stem;
+use super::manifest::ApiManifest;
+use super::minifier;
+use super::prefabs;
+use super::qr::{self, QrMode};
+use super::signing;
+use super::text::{self, TextEncoding};
+use super::{Cartridge, CartridgeError};
+use crate::ecs::components::prefab::Prefab;
+use crate::engine::timestep::MAX_STEP_RATE;
+use qrcode::{EcLevel, QrCode};
+use std::fs;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+pub const PACK_USAGE: &str = "\
+Usage: qr-game-engine pack <script.lua> [options]
+
+Options:
+  --out <dir>         Directory to write the cartridge and QR codes to (default: cartridges)
+  --format <format>   QR image format: png, svg or all (default: all)
+  --ec <level>        QR error-correction level: L, M, Q or H (default: L)
+  --chunk-size <n>    Largest payload per QR code in bytes; bigger cartridges are split
+                      across several codes (default: what one code holds at --ec)
+  --title <title>     Title stored in the cartridge (default: script file name)
+  --author <author>   Author stored in the cartridge
+  --sign <key>        Sign the cartridge with a private key file from `keygen`
+  --text <encoding>   Also write the cartridge as text: base45, base64url or uri
+  --card              Also write a game card: the QR code(s) with the title, author
+                      and a thumbnail of the first frame
+  --instruction-budget <n>
+                      Lua instructions each callback may run before the game is
+                      paused (default: 10000000)
+  --memory-limit <size>
+                      Memory the game's Lua state may use, in bytes or with a K or M
+                      suffix (default: 64M)
+  --step-rate <hz>    Fixed updates per second for physics and on_fixed_update
+                      (default: 60)
+  --bytecode          Ship bytecode when it makes a smaller cartridge. Lua does not
+                      verify bytecode, so it only runs in engines started with --trusted
+  --keep-debug        Keep debug info (line numbers, local names) in the bytecode and
+                      ship the source as written instead of minified
+  --fallback-source   Ship bytecode together with the source, which sandboxed engines
+                      and engines whose Lua was built with other integer/float sizes
+                      or byte order run instead (implies --bytecode)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Png,
+    Svg,
+    All,
+}
+
+impl OutputFormat {
+    fn parse(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "png" => Some(OutputFormat::Png),
+            "svg" => Some(OutputFormat::Svg),
+            "all" => Some(OutputFormat::All),
+            _ => None,
+        }
+    }
+
+    fn includes_png(self) -> bool {
+        matches!(self, OutputFormat::Png | OutputFormat::All)
+    }
+
+    fn includes_svg(self) -> bool {
+        matches!(self, OutputFormat::Svg | OutputFormat::All)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PackOptions {
+    pub script_path: PathBuf,
+    pub out_dir: PathBuf,
+    pub format: OutputFormat,
+    pub ec_level: EcLevel,
+    /// Split the cartridge across several QR codes when it is larger than this
+    pub chunk_size: Option<usize>,
+    pub strip: bool,
+    /// Let bytecode compete with the source, see [`Payload::Bytecode`]
+    pub bytecode: bool,
+    /// Ship the source next to bytecode, see [`Payload::BytecodeWithSource`]
+    pub fallback_source: bool,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// Private key file to sign the cartridge with
+    pub sign_key: Option<PathBuf>,
+    /// Also write the cartridge as text for sharing in chats and links
+    pub text: Option<TextEncoding>,
+    /// Also write a game card PNG with a thumbnail from a headless run of `on_start`
+    pub card: bool,
+    /// Script limits stored in the cartridge; the engine's defaults apply when unset
+    pub instruction_budget: Option<u32>,
+    pub memory_limit: Option<u32>,
+    /// Fixed updates per second stored in the cartridge
+    pub step_rate: Option<u32>,
+}
+
+impl PackOptions {
+    pub fn new(script_path: impl Into<PathBuf>) -> Self {
+        Self {
+            script_path: script_path.into(),
+            out_dir: PathBuf::from("cartridges"),
+            format: OutputFormat::All,
+            ec_level: EcLevel::L,
+            chunk_size: None,
+            strip: true,
+            bytecode: false,
+            fallback_source: false,
+            title: None,
+            author: None,
+            sign_key: None,
+            text: None,
+            card: false,
+            instruction_budget: None,
+            memory_limit: None,
+            step_rate: None,
+        }
+    }
+
+    /// Parse the arguments that follow `pack` on the command line
+    pub fn from_args(args: &[String]) -> Result<Self, CartridgeError> {
+        let mut script_path = None;
+        let mut out_dir = None;
+        let mut format = None;
+        let mut ec_level = None;
+        let mut chunk_size = None;
+        let mut strip = true;
+        let mut bytecode = false;
+        let mut fallback_source = false;
+        let mut title = None;
+        let mut author = None;
+        let mut sign_key = None;
+        let mut text = None;
+        let mut card = false;
+        let mut instruction_budget = None;
+        let mut memory_limit = None;
+        let mut step_rate = None;
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--out" => out_dir = Some(PathBuf::from(expect_value(&mut args, "--out")?)),
+                "--format" => {
+                    let value = expect_value(&mut args, "--format")?;
+                    format = Some(OutputFormat::parse(value).ok_or_else(|| {
+                        CartridgeError::InvalidArguments(format!("Unknown format: {}", value))
+                    })?);
+                }
+                "--ec" => {
+                    let value = expect_value(&mut args, "--ec")?;
+                    ec_level = Some(qr::parse_ec_level(value).ok_or_else(|| {
+                        CartridgeError::InvalidArguments(format!(
+                            "Unknown error-correction level: {}",
+                            value
+                        ))
+                    })?);
+                }
+                "--chunk-size" => {
+                    let value = expect_value(&mut args, "--chunk-size")?;
+                    chunk_size = Some(value.parse().map_err(|_| {
+                        CartridgeError::InvalidArguments(format!("Invalid chunk size: {}", value))
+                    })?);
+                }
+                "--title" => title = Some(expect_value(&mut args, "--title")?.to_string()),
+                "--author" => author = Some(expect_value(&mut args, "--author")?.to_string()),
+                "--sign" => sign_key = Some(PathBuf::from(expect_value(&mut args, "--sign")?)),
+                "--text" => {
+                    let value = expect_value(&mut args, "--text")?;
+                    text = Some(TextEncoding::parse(value).ok_or_else(|| {
+                        CartridgeError::InvalidArguments(format!(
+                            "Unknown text encoding: {}",
+                            value
+                        ))
+                    })?);
+                }
+                "--card" => card = true,
+                "--instruction-budget" => {
+                    let value = expect_value(&mut args, "--instruction-budget")?;
+                    instruction_budget = Some(value.parse().map_err(|_| {
+                        CartridgeError::InvalidArguments(format!(
+                            "Invalid instruction budget: {}",
+                            value
+                        ))
+                    })?);
+                }
+                "--memory-limit" => {
+                    let value = expect_value(&mut args, "--memory-limit")?;
+                    memory_limit = Some(parse_size(value).ok_or_else(|| {
+                        CartridgeError::InvalidArguments(format!("Invalid memory limit: {}", value))
+                    })?);
+                }
+                "--step-rate" => {
+                    let value = expect_value(&mut args, "--step-rate")?;
+                    step_rate = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|rate| (1..=MAX_STEP_RATE).contains(rate))
+                            .ok_or_else(|| {
+                                CartridgeError::InvalidArguments(format!(
+                                    "Invalid step rate: {} (expected 1 to {})",
+                                    value, MAX_STEP_RATE
+                                ))
+                            })?,
+                    );
+                }
+                "--bytecode" => bytecode = true,
+                "--keep-debug" => strip = false,
+                "--fallback-source" => fallback_source = true,
+                other if other.starts_with("--") => {
+                    return Err(CartridgeError::InvalidArguments(format!(
+                        "Unknown option: {}\n\n{}",
+                        other, PACK_USAGE
+                    )));
+                }
+                other => script_path = Some(PathBuf::from(other)),
+            }
+        }
+
+        let script_path = script_path.ok_or_else(|| {
+            CartridgeError::InvalidArguments(format!("Missing script path\n\n{}", PACK_USAGE))
+        })?;
+
+        let mut options = Self::new(script_path);
+        if let Some(out_dir) = out_dir {
+            options.out_dir = out_dir;
+        }
+        if let Some(format) = format {
+            options.format = format;
+        }
+        if let Some(ec_level) = ec_level {
+            options.ec_level = ec_level;
+        }
+        options.chunk_size = chunk_size;
+        options.strip = strip;
+        options.bytecode = bytecode || fallback_source;
+        options.fallback_source = fallback_source;
+        options.title = title;
+        options.author = author;
+        options.sign_key = sign_key;
+        options.text = text;
+        options.card = card;
+        options.instruction_budget = instruction_budget;
+        options.memory_limit = memory_limit;
+        options.step_rate = step_rate;
+        Ok(options)
+    }
+}
+
+/// A byte count such as `65536`, `512K` or `64M`
+fn parse_size(value: &str) -> Option<u32> {
+    let value = value.to_uppercase();
+    let (digits, multiplier) = if let Some(digits) = value.strip_suffix('K') {
+        (digits, 1024)
+    } else if let Some(digits) = value.strip_suffix('M') {
+        (digits, 1024 * 1024)
+    } else {
+        (value.as_str(), 1)
+    };
+    digits.parse::<u32>().ok()?.checked_mul(multiplier)
+}
+
+pub fn expect_value<'a>(
+    args: &mut impl Iterator<Item = &'a String>,
+    option: &str,
+) -> Result<&'a str, CartridgeError> {
+    args.next()
+        .map(String::as_str)
+        .ok_or_else(|| CartridgeError::InvalidArguments(format!("{} expects a value", option)))
+}
+
+/// What the cartridge's Lua chunk is stored as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Payload {
+    /// Only runs in trusted engines: Lua does not verify bytecode, so a
+    /// hand-crafted chunk could get out of the sandbox
+    Bytecode,
+    /// Bytecode plus the (minified) source, which sandboxed engines and engines
+    /// whose Lua can't load the bytecode run instead
+    BytecodeWithSource,
+    MinifiedSource,
+    /// The script as written, when it wasn't minified
+    Source,
+}
+
+impl Payload {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Payload::Bytecode => "bytecode",
+            Payload::BytecodeWithSource => "bytecode with fallback source",
+            Payload::MinifiedSource => "minified source",
+            Payload::Source => "source",
+        }
+    }
+}
+
+/// What a pack run produced, for reporting back to the user
+#[derive(Debug)]
+pub struct PackReport {
+    pub source_size: usize,
+    pub bytecode_size: usize,
+    /// `None` when minifying was skipped or its output could not be trusted
+    pub minified_size: Option<usize>,
+    pub payload: Payload,
+    pub cartridge_size: usize,
+    /// Cartridge size with plain deflate, for comparison with the preset dictionary.
+    /// Both sizes are for the payload that was shipped.
+    pub deflate_size: usize,
+    /// Cartridge size with deflate and the preset dictionary
+    pub dictionary_size: usize,
+    pub compression: Compression,
+    /// Fingerprint of the key the cartridge was signed with
+    pub signed_by: Option<String>,
+    /// API modules the cartridge declares
+    pub api_modules: Option<ApiManifest>,
+    /// Prefabs stored as a prefab table instead of in the script
+    pub prefabs: usize,
+    /// QR version and mode of each code, one entry per chunk
+    pub qr_versions: Vec<(i16, QrMode)>,
+    pub ec_level: EcLevel,
+    /// Why the game card has no thumbnail, when its first frame failed to render
+    pub thumbnail_error: Option<String>,
+    pub outputs: Vec<PathBuf>,
+}
+
+/// A script compiled and serialized into its smallest cartridge
+#[derive(Debug)]
+pub struct Build {
+    pub bytecode_size: usize,
+    /// `None` when minifying was skipped or its output could not be trusted
+    pub minified_size: Option<usize>,
+    pub payload: Payload,
+    pub compression: Compression,
+    /// Prefab definitions moved out of the script into a prefab table
+    pub prefabs: usize,
+    /// The serialized cartridge
+    pub bytes: Vec<u8>,
+    /// The shipped payload serialized with every compression mode
+    encodings: Encodings,
+}
+
+impl Build {
+    pub fn encoded_size(&self, compression: Compression) -> usize {
+        encoded_size(&self.encodings, compression)
+    }
+}
+
+/// Compile (and, when stripping, minify) a script and keep whichever payload and
+/// compression mode make the smallest cartridge. The payload is the source unless
+/// `bytecode` lets bytecode compete with it. Bytecode records the Lua ABI it was
+/// compiled for and, with `fallback_source`, carries the source as well.
+/// Prefab definitions move into a prefab table when that comes out smaller.
+pub fn build(
+    source: &[u8],
+    stem: &str,
+    strip: bool,
+    bytecode: bool,
+    fallback_source: bool,
+    metadata: &CartridgeMetadata,
+) -> Result<Build, CartridgeError> {
+    let mut best = build_script(
+        source,
+        stem,
+        strip,
+        bytecode,
+        fallback_source,
+        metadata,
+        &[],
+    )?;
+    let Some(extracted) = std::str::from_utf8(source).ok().and_then(prefabs::extract) else {
+        return Ok(best);
+    };
+    let source = extracted.source.as_bytes();
+    match build_script(
+        source,
+        stem,
+        strip,
+        bytecode,
+        fallback_source,
+        metadata,
+        &extracted.prefabs,
+    ) {
+        Ok(lifted) if lifted.bytes.len() < best.bytes.len() => best = lifted,
+        Ok(_) => {}
+        Err(e) => {
+            warn!(error = %e, "Script without its prefab definitions does not compile, keeping them");
+        }
+    }
+    Ok(best)
+}
+
+fn build_script(
+    source: &[u8],
+    stem: &str,
+    strip: bool,
+    bytecode: bool,
+    fallback_source: bool,
+    metadata: &CartridgeMetadata,
+    prefabs: &[(String, Prefab)],
+) -> Result<Build, CartridgeError> {
+    // Compiled even when only source ships: it checks the script and the minifier
+    let compiled = compile_script(source, stem, strip)?;
+    let bytecode_size = compiled.len();
+    // Keeping debug info only makes sense for bytecode, so only minify stripped builds
+    let minified = if strip {
+        minified_source(source, stem, &compiled)
+    } else {
+        None
+    };
+    let minified_size = minified.as_ref().map(Vec::len);
+    let (source_payload, source) = match minified {
+        Some(minified) => (Payload::MinifiedSource, minified),
+        None => (Payload::Source, source.to_vec()),
+    };
+
+    let mut candidates = vec![(
+        source_payload,
+        Cartridge::new(source.clone())
+            .with_metadata(metadata.clone())
+            .with_prefabs(prefabs.to_vec()),
+    )];
+    // Source usually compresses better than bytecode, so compare the finished cartridges
+    if bytecode {
+        let bytecode_metadata = CartridgeMetadata {
+            bytecode_abi: BytecodeAbi::of_chunk(&compiled),
+            ..metadata.clone()
+        };
+        let mut cartridge = Cartridge::new(compiled)
+            .with_metadata(bytecode_metadata)
+            .with_prefabs(prefabs.to_vec());
+        let payload = if fallback_source {
+            cartridge = cartridge.with_fallback_source(source);
+            Payload::BytecodeWithSource
+        } else {
+            Payload::Bytecode
+        };
+        candidates.push((payload, cartridge));
+    }
+    let mut best: Option<Build> = None;
+    for (payload, cartridge) in candidates {
+        let encodings = all_encodings(&cartridge)?;
+        let (compression, bytes) = smallest_encoding(&encodings);
+        let smaller = match &best {
+            Some(best) => bytes.len() < best.bytes.len(),
+            None => true,
+        };
+        if smaller {
+            best = Some(Build {
+                bytecode_size,
+                minified_size,
+                payload,
+                compression,
+                prefabs: prefabs.len(),
+                bytes,
+                encodings,
+            });
+        }
+    }
+    Ok(best.expect("the source candidate is always present"))
+}
+
+/// The QR codes a cartridge needs at an error-correction level: one plain code when
+/// it fits in `chunk_size` bytes, otherwise one code per chunk
+pub fn encode_codes(
+    bytes: &[u8],
+    ec_level: EcLevel,
+    chunk_size: Option<usize>,
+) -> Result<Vec<(QrCode, QrMode)>, CartridgeError> {
+    let capacity = qr::byte_capacity(ec_level);
+    let chunk_size = chunk_size.unwrap_or(capacity);
+    if chunk_size > capacity {
+        return Err(CartridgeError::InvalidArguments(format!(
+            "Chunk size {} is larger than the {} bytes a QR code holds at level {:?}",
+            chunk_size, capacity, ec_level
+        )));
+    }
+
+    // Small cartridges stay a single plain code; anything bigger gets chunk headers
+    let payloads = if bytes.len() <= chunk_size {
+        vec![bytes.to_vec()]
+    } else {
+        chunk::split(bytes, chunk_size)?
+            .iter()
+            .map(chunk::Chunk::to_bytes)
+            .collect()
+    };
+    payloads
+        .iter()
+        .map(|payload| qr::encode(payload, ec_level))
+        .collect()
+}
+
+/// Compile a script, wrap it in a cartridge and write the cartridge plus its QR code(s)
+pub fn pack(options: &PackOptions) -> Result<PackReport, CartridgeError> {
+    let source = fs::read(&options.script_path)?;
+    let stem = file_stem(&options.script_path);
+
+    let metadata = CartridgeMetadata {
+        title: Some(options.title.clone().unwrap_or_else(|| stem.clone())),
+        author: options.author.clone(),
+        instruction_budget: options.instruction_budget,
+        memory_limit: options.memory_limit,
+        step_rate: options.step_rate,
+        api_modules: infer_manifest(&source),
+        // `build` records the bytecode ABI when it ships bytecode
+        ..Default::default()
+    };
+    let build = build(
+        &source,
+        &stem,
+        options.strip,
+        options.bytecode,
+        options.fallback_source,
+        &metadata,
+    )?;
+    // Signing comes last: the signature covers the exact bytes that get encoded
+    let (bytes, signed_by) = match &options.sign_key {
+        Some(path) => {
+            let key = signing::read_signing_key(path)?;
+            let fingerprint = signing::fingerprint(key.verifying_key().as_bytes());
+            (sign_bytes(&build.bytes, &key)?, Some(fingerprint))
+        }
+        None => (build.bytes.clone(), None),
+    };
+    let codes = encode_codes(&bytes, options.ec_level, options.chunk_size)?;
+
+    fs::create_dir_all(&options.out_dir)?;
+    let mut outputs = Vec::new();
+
+    let cartridge_path = options.out_dir.join(format!("{}.qrc", stem));
+    fs::write(&cartridge_path, &bytes)?;
+    outputs.push(cartridge_path);
+
+    if let Some(encoding) = options.text {
+        let path = options.out_dir.join(format!("{}.txt", stem));
+        fs::write(&path, format!("{}\n", text::encode(&bytes, encoding)))?;
+        outputs.push(path);
+    }
+
+    for (index, (code, _)) in codes.iter().enumerate() {
+        let name = if codes.len() == 1 {
+            stem.clone()
+        } else {
+            format!("{}-{}of{}", stem, index + 1, codes.len())
+        };
+        if options.format.includes_png() {
+            let path = options.out_dir.join(format!("{}.png", name));
+            qr::write_png(code, &path)?;
+            outputs.push(path);
+        }
+        if options.format.includes_svg() {
+            let path = options.out_dir.join(format!("{}.svg", name));
+            qr::write_svg(code, &path)?;
+            outputs.push(path);
+        }
+    }
+
+    let mut thumbnail_error = None;
+    if options.card {
+        let qr_codes: Vec<QrCode> = codes.iter().map(|(code, _)| code.clone()).collect();
+        // Render from the packed bytes, so the thumbnail shows what players will run
+        let first_frame = match card::render_first_frame(&Cartridge::from_bytes(&bytes)?) {
+            Ok(frame) => Some(frame),
+            Err(e) => {
+                warn!(error = %e, "Game card has no thumbnail: the first frame failed to render");
+                thumbnail_error = Some(e.to_string());
+                None
+            }
+        };
+        let path = options.out_dir.join(format!("{}-card.png", stem));
+        card::write_card(&qr_codes, &metadata, first_frame.as_ref(), &path)?;
+        outputs.push(path);
+    }
+
+    info!(
+        script = %options.script_path.display(),
+        cartridge_size = bytes.len(),
+        chunks = codes.len(),
+        "Packed cartridge"
+    );
+
+    Ok(PackReport {
+        source_size: source.len(),
+        bytecode_size: build.bytecode_size,
+        minified_size: build.minified_size,
+        payload: build.payload,
+        cartridge_size: bytes.len(),
+        deflate_size: build.encoded_size(Compression::Deflate),
+        dictionary_size: build.encoded_size(Compression::Dictionary),
+        compression: build.compression,
+        signed_by,
+        api_modules: metadata.api_modules,
+        prefabs: build.prefabs,
+        qr_versions: codes
+            .iter()
+            .map(|(code, mode)| (qr::version_number(code.version()), *mode))
+            .collect(),
+        ec_level: options.ec_level,
+        thumbnail_error,
+        outputs,
+    })
+}
+
+/// The API modules the script uses. If the script can't be scanned the cartridge
+/// declares nothing and the loader registers every module.
+fn infer_manifest(source: &[u8]) -> Option<ApiManifest> {
+    let source = std::str::from_utf8(source).ok()?;
+    match ApiManifest::infer(source) {
+        Ok(manifest) => Some(manifest),
+        Err(e) => {
+            warn!(error = %e, "Could not work out which API modules the script uses");
+            None
+        }
+    }
+}
+
+/// Minify the script and keep the result only if it compiles to exactly the same
+/// stripped bytecode, which is what guarantees the game behaves the same
+fn minified_source(source: &[u8], stem: &str, bytecode: &[u8]) -> Option<Vec<u8>> {
+    let source = std::str::from_utf8(source).ok()?;
+    let minified = match minifier::minify(source) {
+        Ok(minified) => minified,
+        Err(e) => {
+            warn!(error = %e, "Could not minify script, keeping it as written");
+            return None;
+        }
+    };
+
+    match compile_script(minified.as_bytes(), stem, true) {
+        Ok(compiled) if compiled == bytecode => Some(minified.into_bytes()),
+        Ok(_) => {
+            warn!("Minified script compiles differently from the original, keeping it as written");
+            None
+        }
+        Err(e) => {
+            warn!(error = %e, "Minified script does not compile, keeping it as written");
+            None
+        }
+    }
+}
+
+/// A cartridge serialized once per compression mode
+type Encodings = Vec<(Compression, Vec<u8>)>;
+
+/// Serialize the cartridge with every compression mode, in the order they are preferred on a tie
+fn all_encodings(cartridge: &Cartridge) -> Result<Encodings, CartridgeError> {
+    [
+        Compression::None,
+        Compression::Deflate,
+        Compression::Dictionary,
+    ]
+    .into_iter()
+    .map(|compression| {
+        let bytes = cartridge.clone().with_compression(compression).to_bytes()?;
+        Ok((compression, bytes))
+    })
+    .collect()
+}
+
+/// Keep whichever encoding is smallest.
+/// Tiny scripts can grow under deflate, so compression is never assumed.
+fn smallest_encoding(encodings: &[(Compression, Vec<u8>)]) -> (Compression, Vec<u8>) {
+    let mut smallest = &encodings[0];
+    for encoding in &encodings[1..] {
+        if encoding.1.len() < smallest.1.len() {
+            smallest = encoding;
+        }
+    }
+    smallest.clone()
+}
+
+fn encoded_size(encodings: &[(Compression, Vec<u8>)], compression: Compression) -> usize {
+    encodings
+        .iter()
+        .find(|(c, _)| *c == compression)
+        .map(|(_, bytes)| bytes.len())
+        .unwrap_or_default()
+}
+
+/// Entry point for the `pack` subcommand
+pub fn run(args: &[String]) -> Result<(), CartridgeError> {
+    let options = PackOptions::from_args(args)?;
+    let report = pack(&options)?;
+
+    println!("Packed {}", options.script_path.display());
+    println!("  source:    {} bytes", report.source_size);
+    println!("  bytecode:  {} bytes", report.bytecode_size);
+    match report.minified_size {
+        Some(size) => println!("  minified:  {} bytes", size),
+        None => println!("  minified:  skipped"),
+    }
+    println!(
+        "  cartridge: {} bytes ({}, {:?} compression, EC level {:?})",
+        report.cartridge_size,
+        report.payload.describe(),
+        report.compression,
+        report.ec_level
+    );
+    if matches!(
+        report.payload,
+        Payload::Bytecode | Payload::BytecodeWithSource
+    ) {
+        println!("  abi:       {}", BytecodeAbi::native());
+    }
+    if report.payload == Payload::Bytecode {
+        println!("  note:      bytecode only runs in engines started with --trusted");
+    }
+    match report.api_modules {
+        Some(manifest) => println!(
+            "  requires:  engine API v{}, modules: {}",
+            ENGINE_API_VERSION, manifest
+        ),
+        None => println!(
+            "  requires:  engine API v{}, all modules",
+            ENGINE_API_VERSION
+        ),
+    }
+    if report.prefabs > 0 {
+        println!("  prefabs:   {} stored as a prefab table", report.prefabs);
+    }
+    if let Some(fingerprint) = &report.signed_by {
+        println!("  signed:    key {}", fingerprint);
+    }
+    if let Some(budget) = options.instruction_budget {
+        println!("  budget:    {} instructions per callback", budget);
+    }
+    if let Some(limit) = options.memory_limit {
+        println!("  memory:    {} KiB", limit / 1024);
+    }
+    if let Some(rate) = options.step_rate {
+        println!("  step rate: {} fixed updates per second", rate);
+    }
+    println!(
+        "  deflate:   {} bytes plain, {} bytes with the API dictionary",
+        report.deflate_size, report.dictionary_size
+    );
+    let versions: Vec<String> = report
+        .qr_versions
+        .iter()
+        .map(|(version, mode)| match mode {
+            QrMode::Byte => version.to_string(),
+            QrMode::Alphanumeric => format!("{} (alphanumeric)", version),
+        })
+        .collect();
+    if report.qr_versions.len() == 1 {
+        println!("  QR code:   version {}", versions[0]);
+    } else {
+        println!(
+            "  QR codes:  {} chunks, versions {}",
+            report.qr_versions.len(),
+            versions.join(", ")
+        );
+    }
+    if let Some(e) = &report.thumbnail_error {
+        let first_line = e.lines().next().unwrap_or_default();
+        println!(
+            "  card:      no thumbnail, the first frame failed: {}",
+            first_line
+        );
+    }
+    for output in &report.outputs {
+        println!("  wrote {}", output.display());
+    }
+    Ok(())
+}
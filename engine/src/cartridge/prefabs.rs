@@ -0,0 +1,413 @@
+use super::container::ByteReader;
+use super::lexer::{tokenize, Token, TokenKind};
+use super::scope::{resolve_names, GlobalCall};
+use super::CartridgeError;
+use crate::ecs::components::gravity::GravityType;
+use crate::ecs::components::prefab::{GravitySpec, Prefab, ShapeSpec, TransformSpec, VelocitySpec};
+use crate::ecs::components::sprite::SHAPE_PARAMS;
+use crate::lua::api::prefab_api::prefab_from_table;
+use mlua::{Lua, Table};
+use std::collections::HashSet;
+
+const HAS_TRANSFORM: u8 = 1 << 0;
+const HAS_SHAPE: u8 = 1 << 1;
+const HAS_VELOCITY: u8 = 1 << 2;
+const HAS_GRAVITY: u8 = 1 << 3;
+const DRAGGABLE: u8 = 1 << 4;
+const NOT_DRAGGABLE: u8 = 1 << 5;
+const HAS_TAGS: u8 = 1 << 6;
+
+const SHAPE_NAME: u8 = 1 << 0;
+const SHAPE_COLOR: u8 = 1 << 1;
+
+/// Varint that stands for "a raw f32 follows"; whole numbers are always even
+const FLOAT_MARKER: u64 = 1;
+
+/// A script with its `define_prefab` statements lifted out, and the prefabs they defined
+#[derive(Debug)]
+pub struct Extracted {
+    pub source: String,
+    pub prefabs: Vec<(String, Prefab)>,
+}
+
+/// Serialize prefabs for the cartridge payload.
+///
+/// Each prefab is its name, a byte saying which parts it has, then those parts in
+/// order. Strings and counts are LEB128 varints; every group of optional numbers
+/// starts with a varint bitmask of the ones present. Numbers are whole numbers as
+/// zigzag varints shifted left one bit, so `400` takes two bytes, or
+/// `FLOAT_MARKER` followed by the f32.
+pub fn encode(prefabs: &[(String, Prefab)]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    write_varint(&mut bytes, prefabs.len() as u64);
+    for (name, prefab) in prefabs {
+        write_str(&mut bytes, name);
+        let mut parts = 0;
+        if prefab.transform.is_some() {
+            parts |= HAS_TRANSFORM;
+        }
+        if prefab.shape.is_some() {
+            parts |= HAS_SHAPE;
+        }
+        if prefab.velocity.is_some() {
+            parts |= HAS_VELOCITY;
+        }
+        if prefab.gravity.is_some() {
+            parts |= HAS_GRAVITY;
+        }
+        match prefab.draggable {
+            Some(true) => parts |= DRAGGABLE,
+            Some(false) => parts |= NOT_DRAGGABLE,
+            None => {}
+        }
+        if prefab.tags.is_some() {
+            parts |= HAS_TAGS;
+        }
+        bytes.push(parts);
+
+        if let Some(transform) = &prefab.transform {
+            write_numbers(&mut bytes, &[transform.x, transform.y, transform.rotation]);
+        }
+        if let Some(shape) = &prefab.shape {
+            let mut fields = 0;
+            if shape.name.is_some() {
+                fields |= SHAPE_NAME;
+            }
+            if shape.color.is_some() {
+                fields |= SHAPE_COLOR;
+            }
+            bytes.push(fields);
+            if let Some(name) = &shape.name {
+                write_str(&mut bytes, name);
+            }
+            if let Some((r, g, b)) = shape.color {
+                bytes.extend_from_slice(&[r, g, b]);
+            }
+            let sizes: Vec<Option<f32>> = SHAPE_PARAMS
+                .iter()
+                .map(|param| shape.params.get(*param).copied())
+                .collect();
+            write_numbers(&mut bytes, &sizes);
+        }
+        if let Some(velocity) = &prefab.velocity {
+            write_numbers(&mut bytes, &[velocity.dx, velocity.dy, velocity.angular]);
+        }
+        if let Some(gravity) = &prefab.gravity {
+            bytes.push(match gravity.gravity_type {
+                None => 0,
+                Some(GravityType::Downward) => 1,
+                Some(GravityType::Attractive) => 2,
+                Some(GravityType::Repulsive) => 3,
+            });
+            write_numbers(&mut bytes, &[gravity.force, gravity.terminal_velocity]);
+        }
+        if let Some(tags) = &prefab.tags {
+            write_varint(&mut bytes, tags.len() as u64);
+            for tag in tags {
+                write_str(&mut bytes, tag);
+            }
+        }
+    }
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Vec<(String, Prefab)>, CartridgeError> {
+    let mut reader = ByteReader::new(bytes);
+    let count = read_varint(&mut reader)?;
+    let mut prefabs = Vec::new();
+    for _ in 0..count {
+        let name = read_str(&mut reader)?;
+        let parts = reader.u8()?;
+        let mut prefab = Prefab::default();
+
+        if parts & HAS_TRANSFORM != 0 {
+            let [x, y, rotation] = read_numbers(&mut reader)?;
+            prefab.transform = Some(TransformSpec { x, y, rotation });
+        }
+        if parts & HAS_SHAPE != 0 {
+            let fields = reader.u8()?;
+            let mut shape = ShapeSpec::default();
+            if fields & SHAPE_NAME != 0 {
+                shape.name = Some(read_str(&mut reader)?);
+            }
+            if fields & SHAPE_COLOR != 0 {
+                let [r, g, b] = reader.array::<3>()?;
+                shape.color = Some((r, g, b));
+            }
+            let sizes: [Option<f32>; SHAPE_PARAMS.len()] = read_numbers(&mut reader)?;
+            for (param, size) in SHAPE_PARAMS.iter().zip(sizes) {
+                if let Some(size) = size {
+                    shape.params.insert(param.to_string(), size);
+                }
+            }
+            prefab.shape = Some(shape);
+        }
+        if parts & HAS_VELOCITY != 0 {
+            let [dx, dy, angular] = read_numbers(&mut reader)?;
+            prefab.velocity = Some(VelocitySpec { dx, dy, angular });
+        }
+        if parts & HAS_GRAVITY != 0 {
+            let gravity_type = match reader.u8()? {
+                0 => None,
+                1 => Some(GravityType::Downward),
+                2 => Some(GravityType::Attractive),
+                3 => Some(GravityType::Repulsive),
+                _ => return Err(CartridgeError::InvalidPrefabs("unknown gravity type")),
+            };
+            let [force, terminal_velocity] = read_numbers(&mut reader)?;
+            prefab.gravity = Some(GravitySpec {
+                gravity_type,
+                force,
+                terminal_velocity,
+            });
+        }
+        if parts & DRAGGABLE != 0 {
+            prefab.draggable = Some(true);
+        } else if parts & NOT_DRAGGABLE != 0 {
+            prefab.draggable = Some(false);
+        }
+        if parts & HAS_TAGS != 0 {
+            let count = read_varint(&mut reader)?;
+            let tags = (0..count)
+                .map(|_| read_str(&mut reader))
+                .collect::<Result<_, _>>()?;
+            prefab.tags = Some(tags);
+        }
+        prefabs.push((name, prefab));
+    }
+    if !reader.is_empty() {
+        return Err(CartridgeError::InvalidPrefabs(
+            "trailing bytes after the table",
+        ));
+    }
+    Ok(prefabs)
+}
+
+/// Lift the script's prefab definitions out so they can ship as a prefab table.
+///
+/// Only works when every `define_prefab` call is a statement in the main chunk,
+/// outside any block or function, whose arguments are a string and a table of
+/// constants, with no name defined twice. Otherwise the script is left alone:
+/// `None`. Each lifted statement leaves its line breaks behind, so line numbers
+/// in errors still match the original script.
+pub fn extract(source: &str) -> Option<Extracted> {
+    let tokens = tokenize(source).ok()?;
+    let resolution = resolve_names(&tokens).ok()?;
+    let calls: Vec<&GlobalCall> = resolution
+        .global_calls
+        .iter()
+        .filter(|call| call.name == "define_prefab")
+        .collect();
+    if calls.is_empty() {
+        return None;
+    }
+
+    let depths = block_depths(&tokens);
+    let chars: Vec<char> = source.chars().collect();
+    // Only constants ever reach this state, so it needs no sandbox
+    let lua = Lua::new();
+    let mut names = HashSet::new();
+    let mut prefabs = Vec::new();
+    let mut lifted = Vec::new();
+
+    for call in calls {
+        let (first, last) = (call.tokens.start, call.tokens.end - 1);
+        let shape_ok = call.statement
+            && depths[first] == 0
+            && tokens[first + 1].is_symbol("(")
+            && tokens[first + 2].kind == TokenKind::String
+            && tokens[first + 3].is_symbol(",")
+            && tokens[first + 4].is_symbol("{")
+            && tokens[last].is_symbol(")")
+            && is_constant_table(&tokens[first + 4..last]);
+        if !shape_ok {
+            return None;
+        }
+
+        let arguments: String = chars[tokens[first + 2].start..tokens[last - 1].end]
+            .iter()
+            .collect();
+        let (name, table) = lua
+            .load(format!("return {}", arguments))
+            .eval::<(String, Table)>()
+            .ok()?;
+        // A definition the engine would reject stays in the script, to fail there
+        let prefab = prefab_from_table(&lua, table).ok()?;
+        if !names.insert(name.clone()) {
+            return None;
+        }
+
+        // Take a trailing `;` along; a `(` right after would turn the statement
+        // before into a call once this one is gone
+        let mut end = last + 1;
+        if tokens[end].is_symbol(";") {
+            end += 1;
+        }
+        if tokens[end].is_symbol("(") {
+            return None;
+        }
+        prefabs.push((name, prefab));
+        lifted.push(tokens[first].start..tokens[end - 1].end);
+    }
+
+    // Whatever the encoding can't reproduce exactly stays in the script
+    if decode(&encode(&prefabs)).ok()? != prefabs {
+        return None;
+    }
+
+    let mut stripped = String::with_capacity(source.len());
+    let mut pos = 0;
+    for range in lifted {
+        stripped.extend(&chars[pos..range.start]);
+        let line_breaks = chars[range.clone()].iter().filter(|c| **c == '\n').count();
+        match line_breaks {
+            0 => stripped.push(' '),
+            n => stripped.push_str(&"\n".repeat(n)),
+        }
+        pos = range.end;
+    }
+    stripped.extend(&chars[pos..]);
+    Some(Extracted {
+        source: stripped,
+        prefabs,
+    })
+}
+
+/// How many blocks deep each token sits. Every block opens with `function`,
+/// `if`, `do` (also for `while` and `for`) or `repeat`, and closes with `end`
+/// or `until`.
+fn block_depths(tokens: &[Token]) -> Vec<usize> {
+    let mut depths = Vec::with_capacity(tokens.len());
+    let mut depth: usize = 0;
+    for token in tokens {
+        if token.is_keyword("end") || token.is_keyword("until") {
+            depth = depth.saturating_sub(1);
+        }
+        depths.push(depth);
+        if ["function", "if", "do", "repeat"]
+            .iter()
+            .any(|keyword| token.is_keyword(keyword))
+        {
+            depth += 1;
+        }
+    }
+    depths
+}
+
+/// A table constructor made only of constants: numbers (optionally negated),
+/// strings, booleans, nested tables, and names used as keys
+fn is_constant_table(tokens: &[Token]) -> bool {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1);
+        let constant = match token.kind {
+            TokenKind::Number | TokenKind::String => true,
+            TokenKind::Keyword => ["true", "false", "nil"].contains(&token.text.as_str()),
+            TokenKind::Name => next.is_some_and(|next| next.is_symbol("=")),
+            TokenKind::Symbol => match token.text.as_str() {
+                "{" => {
+                    depth += 1;
+                    true
+                }
+                "}" => {
+                    depth -= 1;
+                    // The outer table has to end on the last token
+                    depth > 0 || i == tokens.len() - 1
+                }
+                "-" => next.is_some_and(|next| next.kind == TokenKind::Number),
+                "," | ";" | "=" | "[" | "]" => true,
+                _ => false,
+            },
+            TokenKind::Eof => false,
+        };
+        if !constant {
+            return false;
+        }
+    }
+    depth == 0
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(reader: &mut ByteReader) -> Result<u64, CartridgeError> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = reader.u8()?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(CartridgeError::InvalidPrefabs("varint is too long"))
+}
+
+fn write_str(bytes: &mut Vec<u8>, text: &str) {
+    write_varint(bytes, text.len() as u64);
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+fn read_str(reader: &mut ByteReader) -> Result<String, CartridgeError> {
+    let len = read_varint(reader)? as usize;
+    String::from_utf8(reader.take(len)?.to_vec())
+        .map_err(|_| CartridgeError::InvalidPrefabs("text is not valid UTF-8"))
+}
+
+/// A bitmask of the numbers present, then each of them
+fn write_numbers(bytes: &mut Vec<u8>, numbers: &[Option<f32>]) {
+    let present = numbers
+        .iter()
+        .enumerate()
+        .filter(|(_, number)| number.is_some())
+        .fold(0, |mask, (i, _)| mask | 1 << i);
+    write_varint(bytes, present);
+    for number in numbers.iter().flatten() {
+        write_number(bytes, *number);
+    }
+}
+
+fn read_numbers<const N: usize>(
+    reader: &mut ByteReader,
+) -> Result<[Option<f32>; N], CartridgeError> {
+    let present = read_varint(reader)?;
+    if present >> N != 0 {
+        return Err(CartridgeError::InvalidPrefabs("unknown field"));
+    }
+    let mut numbers = [None; N];
+    for (i, number) in numbers.iter_mut().enumerate() {
+        if present & 1 << i != 0 {
+            *number = Some(read_number(reader)?);
+        }
+    }
+    Ok(numbers)
+}
+
+fn write_number(bytes: &mut Vec<u8>, number: f32) {
+    let whole = number as i32;
+    // -0.0 would come back as 0.0
+    if whole as f32 == number && !(number == 0.0 && number.is_sign_negative()) {
+        let zigzag = ((whole << 1) ^ (whole >> 31)) as u32;
+        write_varint(bytes, u64::from(zigzag) << 1);
+    } else {
+        write_varint(bytes, FLOAT_MARKER);
+        bytes.extend_from_slice(&number.to_le_bytes());
+    }
+}
+
+fn read_number(reader: &mut ByteReader) -> Result<f32, CartridgeError> {
+    let value = read_varint(reader)?;
+    if value == FLOAT_MARKER {
+        return Ok(f32::from_le_bytes(reader.array::<4>()?));
+    }
+    if value & 1 != 0 || value >> 33 != 0 {
+        return Err(CartridgeError::InvalidPrefabs("invalid number"));
+    }
+    let zigzag = (value >> 1) as u32;
+    let whole = (zigzag >> 1) as i32 ^ -((zigzag & 1) as i32);
+    Ok(whole as f32)
+}
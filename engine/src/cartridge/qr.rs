@@ -0,0 +1,184 @@
+use super::text::base45_encode;
+use super::CartridgeError;
+use image::{GrayImage, Luma};
+use qrcode::render::svg;
+use qrcode::types::Version;
+use qrcode::{EcLevel, QrCode};
+use std::fs;
+use std::path::Path;
+use tracing::debug;
+
+/// Pixels per QR module in rendered PNGs, big enough for a phone camera at arm's length
+pub const MODULE_SIZE: u32 = 8;
+
+/// Largest byte-mode payload a version 40 symbol can hold at each error-correction level
+pub fn byte_capacity(ec_level: EcLevel) -> usize {
+    match ec_level {
+        EcLevel::L => 2953,
+        EcLevel::M => 2331,
+        EcLevel::Q => 1663,
+        EcLevel::H => 1273,
+    }
+}
+
+pub fn parse_ec_level(level: &str) -> Option<EcLevel> {
+    match level.to_uppercase().as_str() {
+        "L" => Some(EcLevel::L),
+        "M" => Some(EcLevel::M),
+        "Q" => Some(EcLevel::Q),
+        "H" => Some(EcLevel::H),
+        _ => None,
+    }
+}
+
+pub fn version_number(version: Version) -> i16 {
+    match version {
+        Version::Normal(v) | Version::Micro(v) => v,
+    }
+}
+
+/// How a QR symbol stores its payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QrMode {
+    /// The cartridge bytes as they are
+    Byte,
+    /// The cartridge as Base45 text, which phone scanners show as text
+    Alphanumeric,
+}
+
+/// Encode bytes into the smallest QR symbol that fits them at the given level.
+///
+/// Byte mode stores 8 bits per byte; Base45 in alphanumeric mode about 8.25, but
+/// with a shorter length field. Both are tried and the smaller symbol is kept,
+/// byte mode on a tie.
+pub fn encode(data: &[u8], ec_level: EcLevel) -> Result<(QrCode, QrMode), CartridgeError> {
+    let capacity = byte_capacity(ec_level);
+    if data.len() > capacity {
+        return Err(CartridgeError::TooLarge {
+            size: data.len(),
+            capacity,
+        });
+    }
+    let bytes = QrCode::with_error_correction_level(data, ec_level)?;
+    // Base45 uses only alphanumeric characters, so the encoder picks that mode for it
+    match QrCode::with_error_correction_level(base45_encode(data), ec_level) {
+        Ok(text) if text.width() < bytes.width() => Ok((text, QrMode::Alphanumeric)),
+        _ => Ok((bytes, QrMode::Byte)),
+    }
+}
+
+/// Render a code with its quiet zone at `module_size` pixels per module
+pub fn render(code: &QrCode, module_size: u32) -> GrayImage {
+    code.render::<Luma<u8>>()
+        .module_dimensions(module_size, module_size)
+        .build()
+}
+
+pub fn write_png(code: &QrCode, path: &Path) -> Result<(), CartridgeError> {
+    render(code, MODULE_SIZE).save(path)?;
+    Ok(())
+}
+
+pub fn write_svg(code: &QrCode, path: &Path) -> Result<(), CartridgeError> {
+    let document = code
+        .render::<svg::Color>()
+        .module_dimensions(MODULE_SIZE, MODULE_SIZE)
+        .build();
+    fs::write(path, document)?;
+    Ok(())
+}
+
+/// Find every QR code in a PNG or JPEG image and return their raw payloads.
+/// A single photo may hold all the chunks of a multi-code cartridge.
+pub fn decode_image(path: &Path) -> Result<Vec<Vec<u8>>, CartridgeError> {
+    let image = image::open(path)?.to_luma8();
+    debug!(path = %path.display(), "Scanning image for QR codes");
+    decode(image)
+}
+
+/// Find every QR code in a grayscale image and return their raw payloads
+pub fn decode(image: GrayImage) -> Result<Vec<Vec<u8>>, CartridgeError> {
+    match decode_grids(image.clone()) {
+        Ok(payloads) => Ok(payloads),
+        // Blur and sensor noise can hide the finder patterns from the detector, and a
+        // hard black-and-white copy often shows them again. It loses the soft edges
+        // that help with rotated codes, so it is only the fallback.
+        Err(first) => match decode_grids(binarize(&image)) {
+            Ok(payloads) => {
+                debug!("Decoded QR code after thresholding the image");
+                Ok(payloads)
+            }
+            Err(CartridgeError::NoQrCode) => Err(first),
+            Err(e) => Err(e),
+        },
+    }
+}
+
+fn decode_grids(image: GrayImage) -> Result<Vec<Vec<u8>>, CartridgeError> {
+    let mut prepared = rqrr::PreparedImage::prepare(image);
+    let grids = prepared.detect_grids();
+    debug!(grids = grids.len(), "Scanned image for QR codes");
+
+    // Photos can contain false positives, so keep only the grids that actually decode
+    let mut payloads = Vec::new();
+    let mut last_error = None;
+    for grid in &grids {
+        let mut payload = Vec::new();
+        match grid.decode_to(&mut payload) {
+            Ok(_) => payloads.push(payload),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    if !payloads.is_empty() {
+        return Ok(payloads);
+    }
+    match last_error {
+        Some(e) => Err(e.into()),
+        None => Err(CartridgeError::NoQrCode),
+    }
+}
+
+/// Black and white at the gray level that best separates the image's dark and
+/// light pixels (Otsu's method)
+fn binarize(image: &GrayImage) -> GrayImage {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+    let total = image.pixels().len() as f64;
+    let sum: f64 = histogram
+        .iter()
+        .enumerate()
+        .map(|(level, count)| level as f64 * *count as f64)
+        .sum();
+
+    let (mut dark_count, mut dark_sum) = (0.0, 0.0);
+    let (mut best_variance, mut threshold) = (0.0, 128);
+    for (level, count) in histogram.iter().enumerate() {
+        dark_count += *count as f64;
+        dark_sum += level as f64 * *count as f64;
+        let light_count = total - dark_count;
+        if dark_count == 0.0 || light_count == 0.0 {
+            continue;
+        }
+        let dark_mean = dark_sum / dark_count;
+        let light_mean = (sum - dark_sum) / light_count;
+        // The threshold that maximizes the variance between the two classes
+        let variance = dark_count * light_count * (dark_mean - light_mean).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            threshold = level;
+        }
+    }
+
+    let mut binary = image.clone();
+    for pixel in binary.pixels_mut() {
+        pixel[0] = if pixel[0] as usize > threshold {
+            255
+        } else {
+            0
+        };
+    }
+    binary
+}
@@ -0,0 +1,536 @@
+use super::chunk::{Chunk, Reassembler};
+use super::container::CartridgeMetadata;
+use super::loader::{file_stem, unwrap_text_payload};
+use super::manifest::ApiManifest;
+use super::packer::{self, expect_value};
+use super::{qr, CartridgeError};
+use image::imageops;
+use image::{GrayImage, Luma};
+use qrcode::EcLevel;
+use std::f32::consts::PI;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use tracing::{debug, info};
+
+pub const ROBUSTNESS_USAGE: &str = "\
+Usage: qr-game-engine robustness [options]
+
+Packs every script in a directory at each error-correction level, distorts the
+QR codes the way printing and photographing does, and checks that they still
+decode to the exact cartridge bytes.
+
+Options:
+  --scripts <dir>     Directory of .lua scripts to pack (default: resources/lua_scripts)
+  --ec <levels>       Error-correction levels to test, e.g. LH or L,M (default: LMQH)
+  --module-size <px>  Pixels per QR module in the rendered codes (default: 8, like pack)
+  --verbose           List every script a distortion breaks, and why";
+
+const DEFAULT_SCRIPTS_DIR: &str = "resources/lua_scripts";
+const EC_LEVELS: [EcLevel; 4] = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H];
+/// Width of the white border a rendered code has on each side, in modules
+const QUIET_ZONE: u32 = 4;
+/// Noise is pseudo-random but the same on every run, so reports can be compared
+const NOISE_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+const WHITE: u8 = 255;
+const BLACK: u8 = 0;
+
+/// Damage done to a rendered QR code before it is decoded again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Distortion {
+    None,
+    /// Turned by this many degrees
+    Rotate(f32),
+    /// Gaussian blur with this standard deviation, in modules
+    Blur(f32),
+    /// Gaussian noise with this standard deviation, in gray levels
+    Noise(f32),
+    /// Photographed at an angle: the top edge narrower by this fraction of the width
+    Perspective(f32),
+    /// This fraction of the symbol hidden under a black square in its middle
+    Occlude(f32),
+}
+
+/// Every distortion tried, mildest first within each kind
+pub const DISTORTIONS: &[Distortion] = &[
+    Distortion::None,
+    Distortion::Rotate(10.0),
+    Distortion::Rotate(30.0),
+    Distortion::Rotate(45.0),
+    Distortion::Blur(0.25),
+    Distortion::Blur(0.5),
+    Distortion::Blur(0.75),
+    Distortion::Noise(32.0),
+    Distortion::Noise(64.0),
+    Distortion::Noise(96.0),
+    Distortion::Perspective(0.1),
+    Distortion::Perspective(0.25),
+    Distortion::Perspective(0.4),
+    Distortion::Occlude(0.02),
+    Distortion::Occlude(0.05),
+    Distortion::Occlude(0.1),
+    Distortion::Occlude(0.2),
+];
+
+impl fmt::Display for Distortion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Distortion::None => write!(f, "none"),
+            Distortion::Rotate(degrees) => write!(f, "rotate {}°", degrees),
+            Distortion::Blur(modules) => write!(f, "blur {} modules", modules),
+            Distortion::Noise(stddev) => write!(f, "noise ±{}", stddev),
+            Distortion::Perspective(amount) => write!(f, "perspective {}%", amount * 100.0),
+            Distortion::Occlude(fraction) => write!(f, "occlude {}%", fraction * 100.0),
+        }
+    }
+}
+
+impl Distortion {
+    pub fn apply(self, image: &GrayImage, module_size: u32) -> GrayImage {
+        match self {
+            Distortion::None => image.clone(),
+            Distortion::Rotate(degrees) => rotate(image, degrees.to_radians()),
+            Distortion::Blur(modules) => imageops::blur(image, modules * module_size as f32),
+            Distortion::Noise(stddev) => add_noise(image, stddev),
+            Distortion::Perspective(amount) => perspective(image, amount),
+            Distortion::Occlude(fraction) => occlude(image, fraction, module_size),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RobustnessOptions {
+    pub scripts_dir: PathBuf,
+    pub ec_levels: Vec<EcLevel>,
+    pub module_size: u32,
+    pub verbose: bool,
+}
+
+impl Default for RobustnessOptions {
+    fn default() -> Self {
+        Self {
+            scripts_dir: PathBuf::from(DEFAULT_SCRIPTS_DIR),
+            ec_levels: EC_LEVELS.to_vec(),
+            module_size: qr::MODULE_SIZE,
+            verbose: false,
+        }
+    }
+}
+
+impl RobustnessOptions {
+    /// Parse the arguments that follow `robustness` on the command line
+    pub fn from_args(args: &[String]) -> Result<Self, CartridgeError> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--scripts" => {
+                    options.scripts_dir = PathBuf::from(expect_value(&mut args, "--scripts")?)
+                }
+                "--ec" => {
+                    let value = expect_value(&mut args, "--ec")?;
+                    options.ec_levels = value
+                        .chars()
+                        .filter(|c| *c != ',')
+                        .map(|c| {
+                            qr::parse_ec_level(&c.to_string()).ok_or_else(|| {
+                                CartridgeError::InvalidArguments(format!(
+                                    "Unknown error-correction level: {}",
+                                    c
+                                ))
+                            })
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                "--module-size" => {
+                    let value = expect_value(&mut args, "--module-size")?;
+                    options.module_size =
+                        value.parse().ok().filter(|size| *size > 0).ok_or_else(|| {
+                            CartridgeError::InvalidArguments(format!(
+                                "Invalid module size: {}",
+                                value
+                            ))
+                        })?;
+                }
+                "--verbose" => options.verbose = true,
+                other => {
+                    return Err(CartridgeError::InvalidArguments(format!(
+                        "Unknown option: {}\n\n{}",
+                        other, ROBUSTNESS_USAGE
+                    )));
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// What decoding a distorted cartridge gave
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every code decoded and the cartridge bytes match exactly
+    Exact,
+    /// A code no longer decodes: the damage is more than its error correction covers
+    Unreadable(String),
+    /// Decoding produced bytes that differ from what was packed. Must never happen.
+    Corrupted(String),
+}
+
+#[derive(Debug)]
+pub struct Trial {
+    pub script: String,
+    pub ec_level: EcLevel,
+    pub distortion: Distortion,
+    pub outcome: Outcome,
+}
+
+impl Trial {
+    /// Corrupted bytes always fail the suite; so does an undamaged code that doesn't read
+    pub fn is_failure(&self) -> bool {
+        match self.outcome {
+            Outcome::Exact => false,
+            Outcome::Unreadable(_) => self.distortion == Distortion::None,
+            Outcome::Corrupted(_) => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RobustnessReport {
+    pub scripts: Vec<String>,
+    pub ec_levels: Vec<EcLevel>,
+    pub module_size: u32,
+    pub trials: Vec<Trial>,
+}
+
+impl RobustnessReport {
+    pub fn failures(&self) -> impl Iterator<Item = &Trial> {
+        self.trials.iter().filter(|trial| trial.is_failure())
+    }
+
+    /// Scripts whose cartridge survived a distortion at a level
+    fn survivors(&self, distortion: Distortion, ec_level: EcLevel) -> usize {
+        self.trials
+            .iter()
+            .filter(|trial| {
+                trial.distortion == distortion
+                    && trial.ec_level == ec_level
+                    && trial.outcome == Outcome::Exact
+            })
+            .count()
+    }
+}
+
+/// Pack, render, distort and decode every script at every requested level
+pub fn check(options: &RobustnessOptions) -> Result<RobustnessReport, CartridgeError> {
+    let mut scripts: Vec<PathBuf> = fs::read_dir(&options.scripts_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lua"))
+        .collect();
+    scripts.sort();
+
+    let mut report = RobustnessReport {
+        scripts: scripts.iter().map(|path| file_stem(path)).collect(),
+        ec_levels: options.ec_levels.clone(),
+        module_size: options.module_size,
+        trials: Vec::new(),
+    };
+
+    for path in &scripts {
+        let source = fs::read_to_string(path)?;
+        let stem = file_stem(path);
+        let metadata = CartridgeMetadata {
+            title: Some(stem.clone()),
+            api_modules: ApiManifest::infer(&source).ok(),
+            ..Default::default()
+        };
+        let bytes = packer::build(source.as_bytes(), &stem, true, false, false, &metadata)?.bytes;
+
+        for &ec_level in &options.ec_levels {
+            let codes: Vec<GrayImage> = packer::encode_codes(&bytes, ec_level, None)?
+                .iter()
+                .map(|(code, _)| qr::render(code, options.module_size))
+                .collect();
+            for &distortion in DISTORTIONS {
+                let outcome = decode_distorted(&bytes, &codes, distortion, options.module_size);
+                debug!(script = %stem, ?ec_level, %distortion, ?outcome, "Decoded distorted cartridge");
+                report.trials.push(Trial {
+                    script: stem.clone(),
+                    ec_level,
+                    distortion,
+                    outcome,
+                });
+            }
+        }
+        info!(script = %stem, "Checked robustness");
+    }
+    Ok(report)
+}
+
+/// Distort every code of a cartridge, decode them and compare with the packed bytes
+fn decode_distorted(
+    bytes: &[u8],
+    codes: &[GrayImage],
+    distortion: Distortion,
+    module_size: u32,
+) -> Outcome {
+    let mut reassembler = Reassembler::new();
+    let mut whole = None;
+
+    for code in codes {
+        let payloads = match qr::decode(distortion.apply(code, module_size)) {
+            Ok(payloads) => payloads,
+            Err(e) => return Outcome::Unreadable(e.to_string()),
+        };
+        for payload in payloads.into_iter().map(unwrap_text_payload) {
+            if !Chunk::is_chunk(&payload) {
+                whole = Some(payload);
+                continue;
+            }
+            if let Err(e) = Chunk::from_bytes(&payload).and_then(|chunk| reassembler.add(chunk)) {
+                return Outcome::Corrupted(e.to_string());
+            }
+        }
+    }
+
+    let decoded = match whole {
+        Some(payload) => Ok(payload),
+        None => reassembler.finish(),
+    };
+    match decoded {
+        Ok(decoded) if decoded == bytes => Outcome::Exact,
+        Ok(decoded) => Outcome::Corrupted(format!(
+            "decoded {} bytes that differ from the {} packed",
+            decoded.len(),
+            bytes.len()
+        )),
+        Err(e @ CartridgeError::MissingChunks { .. }) => Outcome::Unreadable(e.to_string()),
+        Err(e) => Outcome::Corrupted(e.to_string()),
+    }
+}
+
+/// Bilinear sample with white outside the image, like paper around a printed code
+fn sample(image: &GrayImage, x: f32, y: f32) -> u8 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let pixel = |x: f32, y: f32| {
+        if x < 0.0 || y < 0.0 || x >= image.width() as f32 || y >= image.height() as f32 {
+            WHITE as f32
+        } else {
+            image.get_pixel(x as u32, y as u32)[0] as f32
+        }
+    };
+    let top = pixel(x0, y0) * (1.0 - fx) + pixel(x0 + 1.0, y0) * fx;
+    let bottom = pixel(x0, y0 + 1.0) * (1.0 - fx) + pixel(x0 + 1.0, y0 + 1.0) * fx;
+    (top * (1.0 - fy) + bottom * fy).round() as u8
+}
+
+/// Turn the image about its center, growing the canvas so no corner is cut off
+fn rotate(image: &GrayImage, angle: f32) -> GrayImage {
+    let (sin, cos) = angle.sin_cos();
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let out_width = (width * cos.abs() + height * sin.abs()).ceil();
+    let out_height = (width * sin.abs() + height * cos.abs()).ceil();
+
+    GrayImage::from_fn(out_width as u32, out_height as u32, |x, y| {
+        // Map each output pixel back into the source
+        let dx = x as f32 - out_width / 2.0;
+        let dy = y as f32 - out_height / 2.0;
+        let source_x = dx * cos + dy * sin + width / 2.0;
+        let source_y = -dx * sin + dy * cos + height / 2.0;
+        Luma([sample(image, source_x, source_y)])
+    })
+}
+
+/// Project the image onto a trapezoid, as a camera tilted back from the page sees it
+fn perspective(image: &GrayImage, amount: f32) -> GrayImage {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let inset = amount * width / 2.0;
+    let corners = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)];
+    let projected = [
+        (inset, 0.0),
+        (width - inset, 0.0),
+        (width, height),
+        (0.0, height),
+    ];
+    // Map output pixels back to the source, so solve for projected -> source
+    let Some(h) = homography(&projected, &corners) else {
+        return image.clone();
+    };
+
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let (x, y) = (x as f32, y as f32);
+        let w = h[6] * x + h[7] * y + 1.0;
+        let source_x = (h[0] * x + h[1] * y + h[2]) / w;
+        let source_y = (h[3] * x + h[4] * y + h[5]) / w;
+        Luma([sample(image, source_x, source_y)])
+    })
+}
+
+/// The projective transform taking each `from` corner to its `to` corner, as
+/// the first eight entries of its 3x3 matrix (the last is 1)
+fn homography(from: &[(f32, f32); 4], to: &[(f32, f32); 4]) -> Option<[f32; 8]> {
+    // Two equations per corner pair, solved by Gaussian elimination
+    let mut rows = [[0.0f64; 9]; 8];
+    for (i, (&(x, y), &(u, v))) in from.iter().zip(to).enumerate() {
+        let (x, y, u, v) = (x as f64, y as f64, u as f64, v as f64);
+        rows[2 * i] = [x, y, 1.0, 0.0, 0.0, 0.0, -x * u, -y * u, u];
+        rows[2 * i + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -x * v, -y * v, v];
+    }
+
+    for column in 0..8 {
+        let pivot =
+            (column..8).max_by(|&a, &b| rows[a][column].abs().total_cmp(&rows[b][column].abs()))?;
+        if rows[pivot][column].abs() < 1e-9 {
+            return None;
+        }
+        rows.swap(column, pivot);
+        let pivot_row = rows[column];
+        for (index, row) in rows.iter_mut().enumerate() {
+            if index != column {
+                let factor = row[column] / pivot_row[column];
+                for (value, pivot_value) in row.iter_mut().zip(pivot_row).skip(column) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+
+    let mut h = [0.0f32; 8];
+    for (i, entry) in h.iter_mut().enumerate() {
+        *entry = (rows[i][8] / rows[i][i]) as f32;
+    }
+    Some(h)
+}
+
+fn add_noise(image: &GrayImage, stddev: f32) -> GrayImage {
+    let mut rng = Rng(NOISE_SEED);
+    let mut noisy = image.clone();
+    for pixel in noisy.pixels_mut() {
+        let value = pixel[0] as f32 + rng.gaussian() * stddev;
+        pixel[0] = value.clamp(BLACK as f32, WHITE as f32) as u8;
+    }
+    noisy
+}
+
+/// Cover the middle of the symbol (not counting its quiet zone) with a black square
+fn occlude(image: &GrayImage, fraction: f32, module_size: u32) -> GrayImage {
+    let symbol = image.width().saturating_sub(2 * QUIET_ZONE * module_size);
+    let side = (fraction.sqrt() * symbol as f32).round() as u32;
+    let left = (image.width() - side) / 2;
+    let top = (image.height() - side) / 2;
+
+    let mut occluded = image.clone();
+    for y in top..top + side {
+        for x in left..left + side {
+            occluded.put_pixel(x, y, Luma([BLACK]));
+        }
+    }
+    occluded
+}
+
+/// xorshift64: reproducible noise without pulling in a random number crate
+struct Rng(u64);
+
+impl Rng {
+    /// Uniform in [0, 1)
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Standard normal, by the Box-Muller transform
+    fn gaussian(&mut self) -> f32 {
+        let u1 = self.next().max(f32::MIN_POSITIVE);
+        let u2 = self.next();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+}
+
+/// Entry point for the `robustness` subcommand
+pub fn run(args: &[String]) -> Result<(), CartridgeError> {
+    let options = RobustnessOptions::from_args(args)?;
+    let report = check(&options)?;
+
+    println!(
+        "Robustness of {} scripts in {}, {}px modules",
+        report.scripts.len(),
+        options.scripts_dir.display(),
+        report.module_size
+    );
+    println!("Scripts whose cartridge still decodes exactly after each distortion:");
+    println!();
+    print!("  {:<20}", "distortion");
+    for ec_level in &report.ec_levels {
+        print!(" {:>7}", format!("{:?}", ec_level));
+    }
+    println!();
+    for &distortion in DISTORTIONS {
+        print!("  {:<20}", distortion.to_string());
+        for &ec_level in &report.ec_levels {
+            let cell = format!(
+                "{}/{}",
+                report.survivors(distortion, ec_level),
+                report.scripts.len()
+            );
+            print!(" {:>7}", cell);
+        }
+        println!();
+    }
+
+    if options.verbose {
+        println!();
+        for trial in &report.trials {
+            if let Outcome::Unreadable(reason) = &trial.outcome {
+                println!(
+                    "  broken: {} at {:?} by {}: {}",
+                    trial.script, trial.ec_level, trial.distortion, reason
+                );
+            }
+        }
+    }
+
+    let failures: Vec<&Trial> = report.failures().collect();
+    if failures.is_empty() {
+        println!();
+        println!("Every decoded cartridge matched its packed bytes exactly.");
+        return Ok(());
+    }
+    println!();
+    for trial in &failures {
+        let reason = match &trial.outcome {
+            Outcome::Unreadable(reason) | Outcome::Corrupted(reason) => reason.as_str(),
+            Outcome::Exact => "",
+        };
+        println!(
+            "  FAILED: {} at {:?} by {}: {}",
+            trial.script, trial.ec_level, trial.distortion, reason
+        );
+    }
+    Err(CartridgeError::RobustnessFailed {
+        failures: failures.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every example script at every level; slow in debug builds, so run it with
+    /// `cargo test --release robustness -- --ignored`
+    #[test]
+    #[ignore]
+    fn example_scripts_never_decode_to_wrong_bytes() {
+        let options = RobustnessOptions {
+            scripts_dir: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(DEFAULT_SCRIPTS_DIR),
+            ..Default::default()
+        };
+        let report = check(&options).unwrap();
+        assert!(!report.scripts.is_empty());
+        let failures: Vec<&Trial> = report.failures().collect();
+        assert!(failures.is_empty(), "{:#?}", failures);
+    }
+}
@@ -0,0 +1,646 @@
+use super::lexer::{Token, TokenKind};
+use super::CartridgeError;
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+/// Locals that must keep their name: `self` is declared implicitly by method
+/// definitions, and a local `_ENV` changes how every global below it resolves.
+const FIXED_NAMES: &[&str] = &["self", "_ENV"];
+
+/// A local variable (or parameter, loop variable, upvalue) and every token that names it
+#[derive(Debug, Clone)]
+pub struct LocalVar {
+    pub name: String,
+    /// Token index of the declaration
+    pub declared_at: usize,
+    /// Token index of the last reference, or the declaration if it is never used
+    pub last_used: usize,
+    /// Token indices of the declaration and every reference
+    pub occurrences: Vec<usize>,
+    /// The name has to stay as written, see `FIXED_NAMES`
+    pub fixed: bool,
+}
+
+/// A named function defined in the main chunk rather than inside another function
+#[derive(Debug, Clone)]
+pub struct FunctionDef {
+    /// As written: `update`, `Snake.move`, `Board:draw`
+    pub name: String,
+    pub line: usize,
+    /// Token range of the body, between the parameter list and `end`
+    pub body: Range<usize>,
+}
+
+/// A call whose callee is a global, such as `add_shape(...)` or `timer.set_interval(...)`
+#[derive(Debug, Clone)]
+pub struct GlobalCall {
+    /// Callee as written, including any `.field` path
+    pub name: String,
+    /// Token range of the whole call, arguments included
+    pub tokens: Range<usize>,
+    /// The call is a statement on its own rather than part of an expression
+    pub statement: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Resolution {
+    pub locals: Vec<LocalVar>,
+    /// Every name that resolves to a global, read or written
+    pub globals: BTreeSet<String>,
+    pub functions: Vec<FunctionDef>,
+    pub global_calls: Vec<GlobalCall>,
+}
+
+/// Walk a token stream with Lua 5.4's scoping rules and work out which names are
+/// locals and which are globals. Only scoping is tracked, no syntax tree is built.
+pub fn resolve_names(tokens: &[Token]) -> Result<Resolution, CartridgeError> {
+    let mut resolver = Resolver {
+        tokens,
+        pos: 0,
+        scopes: vec![Vec::new()],
+        function_depth: 0,
+        pending_function_name: None,
+        resolution: Resolution::default(),
+    };
+    resolver.block()?;
+    resolver.expect_kind(TokenKind::Eof)?;
+    Ok(resolver.resolution)
+}
+
+struct Resolver<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    /// Local ids visible in each open block, innermost last
+    scopes: Vec<Vec<usize>>,
+    /// How many function bodies enclose the current position
+    function_depth: usize,
+    /// Name for the function body about to be parsed, set by definitions like `x = function`
+    pending_function_name: Option<String>,
+    resolution: Resolution,
+}
+
+impl Resolver<'_> {
+    fn current(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn peek_next(&self) -> &Token {
+        &self.tokens[(self.pos + 1).min(self.tokens.len() - 1)]
+    }
+
+    fn advance(&mut self) {
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> CartridgeError {
+        CartridgeError::Syntax {
+            line: self.current().line,
+            message: message.into(),
+        }
+    }
+
+    fn check_symbol(&mut self, symbol: &str) -> bool {
+        if self.current().is_symbol(symbol) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn check_keyword(&mut self, keyword: &str) -> bool {
+        if self.current().is_keyword(keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), CartridgeError> {
+        if self.check_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(format!(
+                "'{}' expected near '{}'",
+                symbol,
+                self.current().text
+            )))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), CartridgeError> {
+        if self.check_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!(
+                "'{}' expected near '{}'",
+                keyword,
+                self.current().text
+            )))
+        }
+    }
+
+    fn expect_kind(&mut self, kind: TokenKind) -> Result<usize, CartridgeError> {
+        if self.current().kind == kind {
+            let index = self.pos;
+            self.advance();
+            Ok(index)
+        } else {
+            Err(self.error(format!(
+                "{:?} expected near '{}'",
+                kind,
+                self.current().text
+            )))
+        }
+    }
+
+    // ------------------------------------------------------------
+    // Scopes
+    // ------------------------------------------------------------
+
+    fn open_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn close_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, token_index: usize) {
+        let name = self.tokens[token_index].text.clone();
+        self.declare_named(name, token_index, vec![token_index]);
+    }
+
+    fn declare_named(&mut self, name: String, declared_at: usize, occurrences: Vec<usize>) {
+        let id = self.resolution.locals.len();
+        self.resolution.locals.push(LocalVar {
+            fixed: FIXED_NAMES.contains(&name.as_str()),
+            name,
+            declared_at,
+            last_used: declared_at,
+            occurrences,
+        });
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(id);
+        }
+    }
+
+    /// Record a use of the name at `token_index` as a local or a global.
+    /// Returns whether it is a global.
+    fn reference(&mut self, token_index: usize) -> bool {
+        let name = &self.tokens[token_index].text;
+        let local = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .copied()
+            .find(|&id| self.resolution.locals[id].name == *name);
+
+        match local {
+            Some(id) => {
+                let local = &mut self.resolution.locals[id];
+                local.last_used = token_index;
+                local.occurrences.push(token_index);
+                false
+            }
+            None => {
+                self.resolution.globals.insert(name.clone());
+                true
+            }
+        }
+    }
+
+    fn scoped_block(&mut self) -> Result<(), CartridgeError> {
+        self.open_scope();
+        self.block()?;
+        self.close_scope();
+        Ok(())
+    }
+
+    // ------------------------------------------------------------
+    // Statements
+    // ------------------------------------------------------------
+
+    fn block_follows(&self) -> bool {
+        let token = self.current();
+        token.kind == TokenKind::Eof
+            || ["else", "elseif", "end", "until"]
+                .iter()
+                .any(|keyword| token.is_keyword(keyword))
+    }
+
+    fn block(&mut self) -> Result<(), CartridgeError> {
+        while !self.block_follows() {
+            if self.check_keyword("return") {
+                if !self.block_follows() && !self.current().is_symbol(";") {
+                    self.expression_list()?;
+                }
+                self.check_symbol(";");
+                return Ok(());
+            }
+            self.statement()?;
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), CartridgeError> {
+        let token = self.current().clone();
+        match (token.kind, token.text.as_str()) {
+            (TokenKind::Symbol, ";") => self.advance(),
+            (TokenKind::Symbol, "::") => {
+                // Labels live in their own namespace
+                self.advance();
+                self.expect_kind(TokenKind::Name)?;
+                self.expect_symbol("::")?;
+            }
+            (TokenKind::Keyword, "break") => self.advance(),
+            (TokenKind::Keyword, "goto") => {
+                self.advance();
+                self.expect_kind(TokenKind::Name)?;
+            }
+            (TokenKind::Keyword, "do") => {
+                self.advance();
+                self.scoped_block()?;
+                self.expect_keyword("end")?;
+            }
+            (TokenKind::Keyword, "while") => {
+                self.advance();
+                self.expression()?;
+                self.expect_keyword("do")?;
+                self.scoped_block()?;
+                self.expect_keyword("end")?;
+            }
+            (TokenKind::Keyword, "repeat") => {
+                // The `until` condition can see the loop body's locals
+                self.advance();
+                self.open_scope();
+                self.block()?;
+                self.expect_keyword("until")?;
+                self.expression()?;
+                self.close_scope();
+            }
+            (TokenKind::Keyword, "if") => {
+                self.advance();
+                self.expression()?;
+                self.expect_keyword("then")?;
+                self.scoped_block()?;
+                while self.check_keyword("elseif") {
+                    self.expression()?;
+                    self.expect_keyword("then")?;
+                    self.scoped_block()?;
+                }
+                if self.check_keyword("else") {
+                    self.scoped_block()?;
+                }
+                self.expect_keyword("end")?;
+            }
+            (TokenKind::Keyword, "for") => self.for_statement()?,
+            (TokenKind::Keyword, "function") => {
+                self.advance();
+                let name = self.expect_kind(TokenKind::Name)?;
+                self.reference(name);
+                let mut full_name = self.tokens[name].text.clone();
+                let mut method = false;
+                loop {
+                    let separator = self.current().text.clone();
+                    if self.check_symbol(".") || self.check_symbol(":") {
+                        let field = self.expect_kind(TokenKind::Name)?;
+                        full_name.push_str(&separator);
+                        full_name.push_str(&self.tokens[field].text);
+                        if separator == ":" {
+                            method = true;
+                            break;
+                        }
+                    } else {
+                        break;
+                    }
+                }
+                self.pending_function_name = Some(full_name);
+                self.function_body(method)?;
+            }
+            (TokenKind::Keyword, "local") => {
+                self.advance();
+                if self.check_keyword("function") {
+                    // Declared before the body so the function can call itself
+                    let name = self.expect_kind(TokenKind::Name)?;
+                    self.declare(name);
+                    self.pending_function_name = Some(self.tokens[name].text.clone());
+                    self.function_body(false)?;
+                } else {
+                    let mut names = vec![self.expect_kind(TokenKind::Name)?];
+                    self.attribute()?;
+                    while self.check_symbol(",") {
+                        names.push(self.expect_kind(TokenKind::Name)?);
+                        self.attribute()?;
+                    }
+                    if self.check_symbol("=") {
+                        if names.len() == 1 && self.current().is_keyword("function") {
+                            self.pending_function_name = Some(self.tokens[names[0]].text.clone());
+                        }
+                        self.expression_list()?;
+                    }
+                    // New locals only come into scope after their initializers
+                    for name in names {
+                        self.declare(name);
+                    }
+                }
+            }
+            _ => self.expression_statement()?,
+        }
+        Ok(())
+    }
+
+    /// `<const>` / `<close>` after a local name
+    fn attribute(&mut self) -> Result<(), CartridgeError> {
+        if self.check_symbol("<") {
+            self.expect_kind(TokenKind::Name)?;
+            self.expect_symbol(">")?;
+        }
+        Ok(())
+    }
+
+    fn for_statement(&mut self) -> Result<(), CartridgeError> {
+        self.advance();
+        let mut names = vec![self.expect_kind(TokenKind::Name)?];
+        if self.check_symbol("=") {
+            self.expression()?;
+            self.expect_symbol(",")?;
+            self.expression()?;
+            if self.check_symbol(",") {
+                self.expression()?;
+            }
+        } else {
+            while self.check_symbol(",") {
+                names.push(self.expect_kind(TokenKind::Name)?);
+            }
+            self.expect_keyword("in")?;
+            self.expression_list()?;
+        }
+        self.expect_keyword("do")?;
+        self.open_scope();
+        for name in names {
+            self.declare(name);
+        }
+        self.block()?;
+        self.close_scope();
+        self.expect_keyword("end")
+    }
+
+    fn expression_statement(&mut self) -> Result<(), CartridgeError> {
+        let start = self.pos;
+        self.suffixed_expression()?;
+        if self.current().is_symbol("=") || self.current().is_symbol(",") {
+            let target = start..self.pos;
+            let single_target = self.current().is_symbol("=");
+            while self.check_symbol(",") {
+                self.suffixed_expression()?;
+            }
+            self.expect_symbol("=")?;
+            if single_target && self.current().is_keyword("function") {
+                self.pending_function_name = self.dotted_name(target);
+            }
+            self.expression_list()?;
+        } else {
+            let end = self.pos;
+            if let Some(call) = self
+                .resolution
+                .global_calls
+                .iter_mut()
+                .rev()
+                .find(|call| call.tokens == (start..end))
+            {
+                call.statement = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// `a.b.c` written out, if the tokens are nothing but names separated by dots
+    fn dotted_name(&self, tokens: Range<usize>) -> Option<String> {
+        let tokens = &self.tokens[tokens];
+        let well_formed = tokens.iter().enumerate().all(|(i, token)| {
+            if i % 2 == 0 {
+                token.kind == TokenKind::Name
+            } else {
+                token.is_symbol(".")
+            }
+        });
+        (well_formed && tokens.len() % 2 == 1)
+            .then(|| tokens.iter().map(|token| token.text.as_str()).collect())
+    }
+
+    fn function_body(&mut self, method: bool) -> Result<(), CartridgeError> {
+        let name = self.pending_function_name.take();
+        let line = self.current().line;
+        self.expect_symbol("(")?;
+        self.open_scope();
+        if method {
+            self.declare_named("self".to_string(), self.pos, Vec::new());
+        }
+        if !self.current().is_symbol(")") {
+            loop {
+                if self.check_symbol("...") {
+                    break;
+                }
+                let name = self.expect_kind(TokenKind::Name)?;
+                self.declare(name);
+                if !self.check_symbol(",") {
+                    break;
+                }
+            }
+        }
+        self.expect_symbol(")")?;
+        let body_start = self.pos;
+        self.function_depth += 1;
+        self.block()?;
+        self.function_depth -= 1;
+        self.close_scope();
+
+        if let (Some(name), 0) = (name, self.function_depth) {
+            self.resolution.functions.push(FunctionDef {
+                name,
+                line,
+                body: body_start..self.pos,
+            });
+        }
+        self.expect_keyword("end")
+    }
+
+    // ------------------------------------------------------------
+    // Expressions
+    // ------------------------------------------------------------
+
+    fn expression_list(&mut self) -> Result<(), CartridgeError> {
+        self.expression()?;
+        while self.check_symbol(",") {
+            self.expression()?;
+        }
+        Ok(())
+    }
+
+    /// Operator precedence doesn't change which names are in scope, so binary
+    /// expressions are read as a flat list of operands
+    fn expression(&mut self) -> Result<(), CartridgeError> {
+        self.operand()?;
+        while self.is_binary_operator() {
+            self.advance();
+            self.operand()?;
+        }
+        Ok(())
+    }
+
+    fn is_binary_operator(&self) -> bool {
+        let token = self.current();
+        match token.kind {
+            TokenKind::Symbol => matches!(
+                token.text.as_str(),
+                "+" | "-"
+                    | "*"
+                    | "/"
+                    | "//"
+                    | "%"
+                    | "^"
+                    | ".."
+                    | "=="
+                    | "~="
+                    | "<"
+                    | "<="
+                    | ">"
+                    | ">="
+                    | "&"
+                    | "|"
+                    | "~"
+                    | "<<"
+                    | ">>"
+            ),
+            TokenKind::Keyword => token.text == "and" || token.text == "or",
+            _ => false,
+        }
+    }
+
+    fn operand(&mut self) -> Result<(), CartridgeError> {
+        while self.current().is_keyword("not")
+            || ["-", "#", "~"]
+                .iter()
+                .any(|op| self.current().is_symbol(op))
+        {
+            self.advance();
+        }
+
+        let token = self.current();
+        match token.kind {
+            TokenKind::Number | TokenKind::String => self.advance(),
+            TokenKind::Keyword if matches!(token.text.as_str(), "nil" | "true" | "false") => {
+                self.advance()
+            }
+            TokenKind::Keyword if token.text == "function" => {
+                self.advance();
+                self.function_body(false)?;
+            }
+            TokenKind::Symbol if token.text == "..." => self.advance(),
+            TokenKind::Symbol if token.text == "{" => self.table_constructor()?,
+            _ => self.suffixed_expression()?,
+        }
+        Ok(())
+    }
+
+    /// Returns the name if the expression is a global variable
+    fn primary_expression(&mut self) -> Result<Option<String>, CartridgeError> {
+        if self.current().kind == TokenKind::Name {
+            let name = self.pos;
+            self.advance();
+            let global = self.reference(name);
+            Ok(global.then(|| self.tokens[name].text.clone()))
+        } else if self.check_symbol("(") {
+            self.expression()?;
+            self.expect_symbol(")")?;
+            Ok(None)
+        } else {
+            Err(self.error(format!("unexpected symbol near '{}'", self.current().text)))
+        }
+    }
+
+    fn suffixed_expression(&mut self) -> Result<(), CartridgeError> {
+        let start = self.pos;
+        // Callee path while the expression is still a global followed by `.field`s
+        let mut global_path = self.primary_expression()?;
+        loop {
+            let token = self.current();
+            if token.is_symbol(".") {
+                self.advance();
+                let field = self.expect_kind(TokenKind::Name)?;
+                if let Some(path) = global_path.as_mut() {
+                    path.push('.');
+                    path.push_str(&self.tokens[field].text);
+                }
+            } else if token.is_symbol("[") {
+                self.advance();
+                self.expression()?;
+                self.expect_symbol("]")?;
+                global_path = None;
+            } else if token.is_symbol(":") {
+                self.advance();
+                self.expect_kind(TokenKind::Name)?;
+                self.call_arguments()?;
+                global_path = None;
+            } else if token.is_symbol("(")
+                || token.is_symbol("{")
+                || token.kind == TokenKind::String
+            {
+                self.call_arguments()?;
+                if let Some(name) = global_path.take() {
+                    self.resolution.global_calls.push(GlobalCall {
+                        name,
+                        tokens: start..self.pos,
+                        statement: false,
+                    });
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn call_arguments(&mut self) -> Result<(), CartridgeError> {
+        let token = self.current();
+        if token.kind == TokenKind::String {
+            self.advance();
+            Ok(())
+        } else if token.is_symbol("{") {
+            self.table_constructor()
+        } else {
+            self.expect_symbol("(")?;
+            if !self.current().is_symbol(")") {
+                self.expression_list()?;
+            }
+            self.expect_symbol(")")
+        }
+    }
+
+    fn table_constructor(&mut self) -> Result<(), CartridgeError> {
+        self.expect_symbol("{")?;
+        while !self.current().is_symbol("}") {
+            if self.check_symbol("[") {
+                self.expression()?;
+                self.expect_symbol("]")?;
+                self.expect_symbol("=")?;
+                self.expression()?;
+            } else if self.current().kind == TokenKind::Name && self.peek_next().is_symbol("=") {
+                // `name = value` keys are fields, not variables
+                self.advance();
+                self.advance();
+                self.expression()?;
+            } else {
+                self.expression()?;
+            }
+            if !self.check_symbol(",") && !self.check_symbol(";") {
+                break;
+            }
+        }
+        self.expect_symbol("}")
+    }
+}
@@ -0,0 +1,212 @@
+use super::loader::file_stem;
+use super::{Cartridge, CartridgeError};
+use ed25519_dalek::{SigningKey, PUBLIC_KEY_LENGTH, SECRET_KEY_LENGTH};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+pub const KEYGEN_USAGE: &str = "\
+Usage: qr-game-engine keygen <name> [options]
+
+Writes <name>.key (keep it private, pass it to pack --sign) and <name>.pub
+(share it; players drop it in their trusted keys directory).
+
+Options:
+  --out <dir>         Directory to write the key files to (default: keys)";
+
+/// Where the loader looks for trusted publishers' `.pub` files, unless
+/// `QRCADE_TRUSTED_KEYS` points somewhere else
+pub const DEFAULT_TRUSTED_KEYS_DIR: &str = "trusted_keys";
+
+/// Bytes of the public key shown to identify an unknown signer
+const FINGERPRINT_LENGTH: usize = 8;
+
+/// What the loader can say about who made a cartridge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    Unsigned,
+    /// Signed with a key from the trusted keys directory, named after its file
+    Verified {
+        publisher: String,
+    },
+    /// Correctly signed, but with a key nobody trusted
+    UnknownSigner {
+        fingerprint: String,
+    },
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verification::Unsigned => write!(f, "Unsigned cartridge"),
+            Verification::Verified { publisher } => {
+                write!(f, "Verified publisher: {}", publisher)
+            }
+            Verification::UnknownSigner { fingerprint } => {
+                write!(f, "Unknown signer: key {} is not trusted", fingerprint)
+            }
+        }
+    }
+}
+
+/// Look up who signed a cartridge among the `.pub` files in `trusted_dir`.
+/// Tampered cartridges never get here: `Cartridge::from_bytes` rejects them.
+pub fn verify(cartridge: &Cartridge, trusted_dir: &Path) -> Verification {
+    let Some(signature) = &cartridge.signature else {
+        return Verification::Unsigned;
+    };
+
+    let publisher = trusted_keys(trusted_dir)
+        .into_iter()
+        .find(|(_, key)| *key == signature.public_key)
+        .map(|(publisher, _)| publisher);
+    match publisher {
+        Some(publisher) => Verification::Verified { publisher },
+        None => Verification::UnknownSigner {
+            fingerprint: fingerprint(&signature.public_key),
+        },
+    }
+}
+
+/// Every readable public key in the directory, named after its file.
+/// A missing directory just means nobody is trusted yet.
+fn trusted_keys(dir: &Path) -> Vec<(String, [u8; PUBLIC_KEY_LENGTH])> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut keys = Vec::new();
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.extension().is_none_or(|ext| ext != "pub") {
+            continue;
+        }
+        match read_public_key(&path) {
+            Ok(key) => keys.push((file_stem(&path), key)),
+            Err(e) => warn!(error = %e, "Skipping trusted key"),
+        }
+    }
+    keys
+}
+
+/// Short hex id of a public key, enough to tell signers apart
+pub fn fingerprint(public_key: &[u8; PUBLIC_KEY_LENGTH]) -> String {
+    to_hex(&public_key[..FINGERPRINT_LENGTH])
+}
+
+/// Read a private key written by `keygen`
+pub fn read_signing_key(path: &Path) -> Result<SigningKey, CartridgeError> {
+    Ok(SigningKey::from_bytes(&read_key_file(path)?))
+}
+
+/// Read a public key written by `keygen`
+pub fn read_public_key(path: &Path) -> Result<[u8; PUBLIC_KEY_LENGTH], CartridgeError> {
+    read_key_file(path)
+}
+
+/// Key files hold the key as a single line of hex
+fn read_key_file<const N: usize>(path: &Path) -> Result<[u8; N], CartridgeError> {
+    let invalid = |reason: &str| CartridgeError::InvalidKey {
+        path: path.to_path_buf(),
+        reason: reason.to_string(),
+    };
+
+    let text = fs::read_to_string(path)?;
+    let bytes = from_hex(text.trim()).ok_or_else(|| invalid("not a hex-encoded key"))?;
+    bytes
+        .try_into()
+        .map_err(|_| invalid(&format!("expected a {} byte key", N)))
+}
+
+/// Generate a key pair and write `<name>.key` and `<name>.pub` into `out_dir`
+pub fn generate(name: &str, out_dir: &Path) -> Result<(PathBuf, PathBuf), CartridgeError> {
+    let key_path = out_dir.join(format!("{}.key", name));
+    let pub_path = out_dir.join(format!("{}.pub", name));
+    if key_path.exists() {
+        return Err(CartridgeError::InvalidArguments(format!(
+            "{} already exists, refusing to overwrite a key",
+            key_path.display()
+        )));
+    }
+
+    let mut secret = [0; SECRET_KEY_LENGTH];
+    getrandom::getrandom(&mut secret).map_err(|e| io::Error::other(e.to_string()))?;
+    let key = SigningKey::from_bytes(&secret);
+
+    fs::create_dir_all(out_dir)?;
+    write_private(&key_path, &format!("{}\n", to_hex(&secret)))?;
+    fs::write(
+        &pub_path,
+        format!("{}\n", to_hex(key.verifying_key().as_bytes())),
+    )?;
+
+    info!(key = %key_path.display(), "Generated signing key");
+    Ok((key_path, pub_path))
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &str) -> io::Result<()> {
+    fs::write(path, contents)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&text[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Entry point for the `keygen` subcommand
+pub fn run(args: &[String]) -> Result<(), CartridgeError> {
+    let mut name = None;
+    let mut out_dir = PathBuf::from("keys");
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => {
+                out_dir = PathBuf::from(args.next().ok_or_else(|| {
+                    CartridgeError::InvalidArguments("--out expects a value".to_string())
+                })?)
+            }
+            other if other.starts_with("--") => {
+                return Err(CartridgeError::InvalidArguments(format!(
+                    "Unknown option: {}\n\n{}",
+                    other, KEYGEN_USAGE
+                )));
+            }
+            other => name = Some(other.to_string()),
+        }
+    }
+    let name = name.ok_or_else(|| {
+        CartridgeError::InvalidArguments(format!("Missing key name\n\n{}", KEYGEN_USAGE))
+    })?;
+
+    let (key_path, pub_path) = generate(&name, &out_dir)?;
+    let public_key = read_public_key(&pub_path)?;
+    println!("Generated key {}", fingerprint(&public_key));
+    println!("  private key: {} (keep this secret)", key_path.display());
+    println!("  public key:  {}", pub_path.display());
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use super::chunk::CHUNK_MAGIC;
+use super::container::MAGIC;
+
+/// Prefix of a cartridge shared as a link
+pub const URI_SCHEME: &str = "qrcade:";
+
+/// Characters of QR alphanumeric mode, in Base45 digit order (RFC 9285)
+const BASE45_ALPHABET: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+const BASE64URL_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Ways to write cartridge bytes as text that survives chat apps and URLs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    /// What QR alphanumeric mode stores most densely; contains spaces, so quote it
+    Base45,
+    /// URL- and filename-safe Base64 without padding
+    Base64Url,
+    /// `qrcade:` followed by Base64url, for links
+    Uri,
+}
+
+impl TextEncoding {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "base45" => Some(TextEncoding::Base45),
+            "base64url" => Some(TextEncoding::Base64Url),
+            "uri" => Some(TextEncoding::Uri),
+            _ => None,
+        }
+    }
+}
+
+pub fn encode(bytes: &[u8], encoding: TextEncoding) -> String {
+    match encoding {
+        TextEncoding::Base45 => base45_encode(bytes),
+        TextEncoding::Base64Url => base64url_encode(bytes),
+        TextEncoding::Uri => format!("{}{}", URI_SCHEME, base64url_encode(bytes)),
+    }
+}
+
+/// Recover cartridge (or chunk) bytes from any of the text encodings.
+///
+/// The encoding isn't marked, so every decoding that works is checked for the
+/// cartridge or chunk magic bytes; anything else is not a cartridge.
+pub fn decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    let text = match text.get(..URI_SCHEME.len()) {
+        Some(scheme) if scheme.eq_ignore_ascii_case(URI_SCHEME) => &text[URI_SCHEME.len()..],
+        _ => text,
+    };
+
+    [base64url_decode(text), base45_decode(text)]
+        .into_iter()
+        .flatten()
+        .find(|bytes| bytes.starts_with(MAGIC) || bytes.starts_with(CHUNK_MAGIC))
+}
+
+/// Base45 as in RFC 9285: every two bytes become three characters
+pub fn base45_encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(2) * 3);
+    for pair in bytes.chunks(2) {
+        let (mut value, digits) = match pair {
+            [high, low] => ((*high as usize) << 8 | *low as usize, 3),
+            [byte] => (*byte as usize, 2),
+            _ => unreachable!(),
+        };
+        for _ in 0..digits {
+            text.push(BASE45_ALPHABET[value % 45] as char);
+            value /= 45;
+        }
+    }
+    text
+}
+
+pub fn base45_decode(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<usize> = text
+        .bytes()
+        .map(|c| BASE45_ALPHABET.iter().position(|&a| a == c))
+        .collect::<Option<_>>()?;
+    if digits.len() % 3 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(digits.len() / 3 * 2 + 1);
+    for group in digits.chunks(3) {
+        let value = group
+            .iter()
+            .rev()
+            .fold(0, |value, digit| value * 45 + digit);
+        if group.len() == 3 {
+            let pair = u16::try_from(value).ok()?;
+            bytes.extend_from_slice(&pair.to_be_bytes());
+        } else {
+            bytes.push(u8::try_from(value).ok()?);
+        }
+    }
+    Some(bytes)
+}
+
+pub fn base64url_encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for group in bytes.chunks(3) {
+        let mut value = 0u32;
+        for (i, byte) in group.iter().enumerate() {
+            value |= (*byte as u32) << (16 - 8 * i);
+        }
+        // 3 bytes -> 4 characters, and one less for each missing byte
+        for i in 0..=group.len() {
+            text.push(BASE64URL_ALPHABET[(value >> (18 - 6 * i)) as usize & 0x3f] as char);
+        }
+    }
+    text
+}
+
+/// Padding is accepted but not required
+pub fn base64url_decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let digits: Vec<u32> = text
+        .bytes()
+        .map(|c| {
+            BASE64URL_ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .map(|d| d as u32)
+        })
+        .collect::<Option<_>>()?;
+    if digits.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(digits.len() / 4 * 3 + 2);
+    for group in digits.chunks(4) {
+        let mut value = 0u32;
+        for (i, digit) in group.iter().enumerate() {
+            value |= digit << (18 - 6 * i);
+        }
+        for i in 0..group.len() - 1 {
+            bytes.push((value >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The examples from RFC 9285, section 4.3
+    const RFC_9285_VECTORS: &[(&str, &str)] = &[
+        ("AB", "BB8"),
+        ("Hello!!", "%69 VD92EX0"),
+        ("base-45", "UJCLQE7W581"),
+        ("ietf!", "QED8WEX0"),
+    ];
+
+    #[test]
+    fn base45_matches_rfc_9285() {
+        for (bytes, text) in RFC_9285_VECTORS {
+            assert_eq!(base45_encode(bytes.as_bytes()), *text);
+            assert_eq!(base45_decode(text).as_deref(), Some(bytes.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn base45_rejects_invalid_text() {
+        // 65536 doesn't fit in two bytes (RFC 9285 section 6)
+        assert_eq!(base45_decode("GGW"), None);
+        // 2024 doesn't fit in the one byte a trailing pair stands for
+        assert_eq!(base45_decode("::"), None);
+        // A lone trailing digit can't encode anything
+        assert_eq!(base45_decode("BB8B"), None);
+        // Lowercase isn't in the alphabet
+        assert_eq!(base45_decode("bb8"), None);
+    }
+}
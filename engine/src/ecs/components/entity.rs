@@ -0,0 +1,84 @@
+/// An entity handle as scripts see it: the slot index in the low 32 bits and the
+/// slot's generation in the high 32 bits. A destroyed entity's slot is reused with
+/// the next generation, so a stale handle never refers to the entity that took
+/// its place. First-generation handles are plain slot indices: 1, 2, 3, ...
+pub type Entity = u64;
+
+pub fn index(entity: Entity) -> u32 {
+    entity as u32
+}
+
+pub fn generation(entity: Entity) -> u32 {
+    (entity >> 32) as u32
+}
+
+fn handle(index: u32, generation: u32) -> Entity {
+    (generation as Entity) << 32 | index as Entity
+}
+
+/// Hands out entity handles and recycles the slots of destroyed entities
+#[derive(Clone, Debug)]
+pub struct Entities {
+    /// Current generation of every slot. Slot 0 is never handed out, so no
+    /// entity is `0`.
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    /// Slots of destroyed entities, reused before new slots are added
+    free: Vec<u32>,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self {
+            generations: vec![0],
+            alive: vec![false],
+            free: Vec::new(),
+        }
+    }
+
+    pub fn create(&mut self) -> Entity {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.generations.push(0);
+                self.alive.push(false);
+                (self.generations.len() - 1) as u32
+            }
+        };
+        self.alive[index as usize] = true;
+        handle(index, self.generations[index as usize])
+    }
+
+    /// Free an entity's slot; `false` when it was already dead
+    pub fn destroy(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let slot = index(entity) as usize;
+        self.alive[slot] = false;
+        self.generations[slot] = self.generations[slot].wrapping_add(1);
+        self.free.push(slot as u32);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let slot = index(entity) as usize;
+        self.alive.get(slot).copied().unwrap_or(false)
+            && self.generations[slot] == generation(entity)
+    }
+
+    /// Live entities in slot order
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.alive
+            .iter()
+            .enumerate()
+            .filter(|(_, alive)| **alive)
+            .map(|(slot, _)| handle(slot as u32, self.generations[slot]))
+    }
+}
+
+impl Default for Entities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
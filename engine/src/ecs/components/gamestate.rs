@@ -1,29 +1,110 @@
 use super::{
     draggable::Draggable,
+    entity::{index, Entities, Entity},
     gravity::Gravity,
+    hierarchy::Parent,
+    prefab::Prefab,
+    scene::{ActiveScene, Scene, SceneInstance},
     sprite::Sprite,
+    storage::SparseSet,
+    tag::Tags,
     text::Text,
     tilemap::Tilemap,
     timer::{Timer, TimerId},
     transform::Transform,
     velocity::Velocity,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
-pub type Entity = u32;
+/// A per-entity component store, as seen by entity destruction and queries
+pub trait ComponentStore {
+    fn remove_entity(&mut self, entity: Entity);
+    fn contains_entity(&self, entity: Entity) -> bool;
+    /// Entities with a component in this store
+    fn entities(&self) -> Vec<Entity>;
+    fn count(&self) -> usize;
+}
+
+impl<T> ComponentStore for SparseSet<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(&entity);
+    }
+
+    fn contains_entity(&self, entity: Entity) -> bool {
+        self.contains_key(&entity)
+    }
+
+    fn entities(&self) -> Vec<Entity> {
+        self.keys().copied().collect()
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Every per-entity component store in `GameState`, under the name scripts query
+/// it by. `destroy_entity` clears an entity out of each of them, so a new component
+/// type is cleaned up and queryable as soon as its field is listed here.
+macro_rules! component_registry {
+    ($($name:ident: $store:ident),* $(,)?) => {
+        impl GameState {
+            /// Name and store of every registered component type
+            pub fn component_stores(&self) -> Vec<(&'static str, &dyn ComponentStore)> {
+                vec![$((stringify!($name), &self.$store as &dyn ComponentStore)),*]
+            }
+
+            fn component_stores_mut(&mut self) -> Vec<&mut dyn ComponentStore> {
+                vec![$(&mut self.$store as &mut dyn ComponentStore),*]
+            }
+        }
+    };
+}
 
+component_registry!(
+    transform: transforms,
+    velocity: velocities,
+    sprite: sprites,
+    tilemap: tilemaps,
+    gravity: gravities,
+    draggable: draggables,
+    text: texts,
+    tags: tags,
+    parent: parents,
+    scene: scene_owners,
+);
+
+/// Per-entity components must also be listed in `component_registry!`
 #[derive(Clone, Debug)]
 pub struct GameState {
-    pub entities: Vec<Entity>,
-    pub transforms: HashMap<Entity, Transform>,
-    pub velocities: HashMap<Entity, Velocity>,
-    pub sprites: HashMap<Entity, Sprite>,
-    pub tilemaps: HashMap<u32, Tilemap>,
-    pub gravities: HashMap<Entity, Gravity>,
-    pub draggables: HashMap<Entity, Draggable>,
-    pub texts: HashMap<Entity, Text>,
+    pub entities: Entities,
+    pub transforms: SparseSet<Transform>,
+    pub velocities: SparseSet<Velocity>,
+    pub sprites: SparseSet<Sprite>,
+    pub tilemaps: SparseSet<Tilemap>,
+    pub gravities: SparseSet<Gravity>,
+    pub draggables: SparseSet<Draggable>,
+    pub texts: SparseSet<Text>,
+    pub tags: SparseSet<Tags>,
+    pub parents: SparseSet<Parent>,
+    /// Each parent's children in ID order, kept in step with `parents` by
+    /// `attach`, `detach` and `destroy_entity`
+    pub children: HashMap<Entity, Vec<Entity>>,
+    /// World transforms from before the last fixed step, which rendering
+    /// interpolates from. Not a component: entities that jumped since are left out.
+    pub previous_transforms: SparseSet<Transform>,
+    /// The scene instance each entity was created in
+    pub scene_owners: SparseSet<SceneInstance>,
+    /// Templates for `spawn`, by name
+    pub prefabs: HashMap<String, Prefab>,
     pub timers: HashMap<TimerId, Timer>,
     pub next_timer_id: u32,
+    /// Scenes from `define_scene`, by name
+    pub scenes: HashMap<String, Rc<Scene>>,
+    /// Pushed scenes, the one being updated last
+    pub scene_stack: Vec<ActiveScene>,
+    pub next_scene_instance: u32,
 }
 
 // ------------------------
@@ -32,16 +113,25 @@ pub struct GameState {
 impl GameState {
     pub fn new() -> Self {
         Self {
-            entities: Vec::new(),
-            transforms: HashMap::new(),
-            velocities: HashMap::new(),
-            sprites: HashMap::new(),
-            tilemaps: HashMap::new(),
-            gravities: HashMap::new(),
-            draggables: HashMap::new(),
-            texts: HashMap::new(),
+            entities: Entities::new(),
+            transforms: SparseSet::new(),
+            velocities: SparseSet::new(),
+            sprites: SparseSet::new(),
+            tilemaps: SparseSet::new(),
+            gravities: SparseSet::new(),
+            draggables: SparseSet::new(),
+            texts: SparseSet::new(),
+            tags: SparseSet::new(),
+            parents: SparseSet::new(),
+            children: HashMap::new(),
+            previous_transforms: SparseSet::new(),
+            scene_owners: SparseSet::new(),
+            prefabs: HashMap::new(),
             timers: HashMap::new(),
             next_timer_id: 0,
+            scenes: HashMap::new(),
+            scene_stack: Vec::new(),
+            next_scene_instance: 0,
         }
     }
 
@@ -66,12 +156,28 @@ impl GameState {
         self.draggables.remove(&entity);
     }
 
-    /// Create a new entity and return its include its ID
+    /// Create a new entity and return its include its ID. While a scene is on the
+    /// stack, the entity belongs to the top one and is destroyed when it is popped.
     pub fn create_entity(&mut self) -> Entity {
-        // Simple approach: entity ID is just (length + 1)
-        let new_id = self.entities.len() as u32 + 1;
-        self.entities.push(new_id);
-        new_id
+        let entity = self.entities.create();
+        if let Some(scene) = self.scene_stack.last() {
+            self.scene_owners.insert(entity, scene.instance);
+        }
+        entity
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.is_alive(entity)
+    }
+
+    /// The error managers report when a script passes an entity that was never
+    /// created or has been destroyed
+    pub fn check_alive(&self, entity: Entity) -> Result<(), &'static str> {
+        if self.is_alive(entity) {
+            Ok(())
+        } else {
+            Err("Entity does not exist or has been destroyed")
+        }
     }
 
     /// Attach a Velocity component to an entity
@@ -89,22 +195,343 @@ impl GameState {
         self.sprites.insert(entity, sprite);
     }
 
-    pub fn _get_velocity(&self, entity_id: u32) -> Option<&Velocity> {
+    pub fn _get_velocity(&self, entity_id: Entity) -> Option<&Velocity> {
         self.velocities.get(&entity_id)
     }
 
-    /// Destroy an entity
-    pub fn destroy_entity(&mut self, entity: Entity) {
-        // Remove from entities vector
-        if let Some(pos) = self.entities.iter().position(|&e| e == entity) {
-            self.entities.remove(pos);
+    /// Destroy an entity and its descendants; `false` when it was already dead
+    pub fn destroy_entity(&mut self, entity: Entity) -> bool {
+        let descendants = self.descendants_of(entity);
+        // Its slot is reused with the next generation, so stale handles stay dead
+        if !self.entities.destroy(entity) {
+            return false;
         }
 
-        // Remove all components associated with this entity
-        self.transforms.remove(&entity);
-        self.velocities.remove(&entity);
-        self.sprites.remove(&entity);
-        self.draggables.remove(&entity);
+        self.unlink(entity);
+        for dead in std::iter::once(entity).chain(descendants) {
+            self.entities.destroy(dead);
+            self.children.remove(&dead);
+            for store in self.component_stores_mut() {
+                store.remove_entity(dead);
+            }
+        }
+        true
+    }
+
+    /// Components whose entity is dead, by store name, and children whose parent
+    /// is. Always empty unless a component was attached without going through the
+    /// managers.
+    pub fn orphaned_components(&self) -> Vec<(&'static str, Entity)> {
+        self.component_stores()
+            .into_iter()
+            .flat_map(|(name, store)| {
+                store
+                    .entities()
+                    .into_iter()
+                    .filter(|entity| !self.is_alive(*entity))
+                    .map(move |entity| (name, entity))
+            })
+            .chain(
+                self.parents
+                    .iter()
+                    .filter(|(_, parent)| !self.is_alive(parent.entity))
+                    .map(|(&child, _)| ("parent", child)),
+            )
+            .chain(
+                self.children
+                    .keys()
+                    .filter(|parent| !self.is_alive(**parent))
+                    .map(|&parent| ("children", parent)),
+            )
+            .collect()
+    }
+
+    // ------------------------
+    // Scenes
+    // ------------------------
+
+    /// Push a new instance of a defined scene
+    pub fn push_scene(&mut self, name: &str) -> Option<SceneInstance> {
+        let scene = Rc::clone(self.scenes.get(name)?);
+        let instance = SceneInstance(self.next_scene_instance);
+        self.next_scene_instance += 1;
+        self.scene_stack.push(ActiveScene {
+            name: name.to_string(),
+            instance,
+            scene,
+        });
+        Some(instance)
+    }
+
+    /// Take a scene instance off the stack, wherever it is, and return the
+    /// entities it owned that are still alive, in ID order
+    pub fn remove_scene(&mut self, instance: SceneInstance) -> Vec<Entity> {
+        self.scene_stack.retain(|scene| scene.instance != instance);
+        let mut owned: Vec<Entity> = self
+            .scene_owners
+            .iter()
+            .filter(|(_, owner)| **owner == instance)
+            .map(|(&entity, _)| entity)
+            .collect();
+        owned.sort_unstable();
+        owned
+    }
+
+    /// The scene an entity belongs to, if another scene has been pushed over it
+    fn paused_scene(&self, entity: Entity) -> Option<&ActiveScene> {
+        let owner = self.scene_owners.get(&entity)?;
+        let (top, below) = self.scene_stack.split_last()?;
+        if top.instance == *owner {
+            return None;
+        }
+        below.iter().find(|scene| scene.instance == *owner)
+    }
+
+    /// Whether an entity belongs to a scene that has another pushed over it.
+    /// Paused entities don't move, fall or get dragged.
+    pub fn is_paused(&self, entity: Entity) -> bool {
+        self.paused_scene(entity).is_some()
+    }
+
+    /// Every paused entity, for systems that can't ask `is_paused` while they hold
+    /// a store mutably
+    pub fn paused_entities(&self) -> HashSet<Entity> {
+        if self.scene_stack.len() < 2 {
+            return HashSet::new();
+        }
+        self.scene_owners
+            .keys()
+            .copied()
+            .filter(|&entity| self.is_paused(entity))
+            .collect()
+    }
+
+    /// Whether an entity is paused and its scene isn't drawn while paused
+    pub fn is_hidden(&self, entity: Entity) -> bool {
+        self.paused_scene(entity)
+            .is_some_and(|active| !active.scene.draw_when_paused)
+    }
+
+    // ------------------------
+    // Hierarchy
+    // ------------------------
+
+    pub fn parent_of(&self, entity: Entity) -> Option<Entity> {
+        self.parents.get(&entity).map(|parent| parent.entity)
+    }
+
+    /// Direct children, in ID order
+    pub fn children_of(&self, entity: Entity) -> Vec<Entity> {
+        self.children.get(&entity).cloned().unwrap_or_default()
+    }
+
+    /// Children, grandchildren and so on, each after its parent
+    pub fn descendants_of(&self, entity: Entity) -> Vec<Entity> {
+        let mut descendants = Vec::new();
+        if self.children.is_empty() {
+            return descendants;
+        }
+        let mut next = 0;
+        let mut current = entity;
+        loop {
+            descendants.extend(self.children_of(current));
+            match descendants.get(next) {
+                Some(&descendant) => current = descendant,
+                None => return descendants,
+            }
+            next += 1;
+        }
+    }
+
+    /// Make `child` follow `parent`, keeping where it is in the world. `child`
+    /// must not be `parent` or one of its ancestors.
+    pub fn attach(&mut self, child: Entity, parent: Entity) -> Result<(), &'static str> {
+        let mut ancestor = Some(parent);
+        while let Some(entity) = ancestor {
+            if entity == child {
+                return Err("Cannot attach an entity to itself or one of its descendants");
+            }
+            ancestor = self.parent_of(entity);
+        }
+
+        let parent_world = self.world_transform(parent).unwrap_or_default();
+        // A child without a transform starts on its parent
+        let world = self.world_transform(child).unwrap_or(parent_world);
+        self.unlink(child);
+        self.parents
+            .insert(child, Parent::new(parent, parent_world.to_local(&world)));
+        let siblings = self.children.entry(parent).or_default();
+        if let Err(position) = siblings.binary_search(&child) {
+            siblings.insert(position, child);
+        }
+        self.transforms.insert(child, world);
+        Ok(())
+    }
+
+    /// Unlink `child` from its parent, leaving it where it is in the world;
+    /// `false` when it had no parent
+    pub fn detach(&mut self, child: Entity) -> bool {
+        self.unlink(child).is_some()
+    }
+
+    /// Remove `child`'s parent link and its place among its parent's children
+    fn unlink(&mut self, child: Entity) -> Option<Parent> {
+        let link = self.parents.remove(&child)?;
+        if let Some(siblings) = self.children.get_mut(&link.entity) {
+            siblings.retain(|&sibling| sibling != child);
+            if siblings.is_empty() {
+                self.children.remove(&link.entity);
+            }
+        }
+        Some(link)
+    }
+
+    /// Where an entity is in the world, worked out from its ancestors now rather
+    /// than read from the last `resolve_world_transforms`
+    pub fn world_transform(&self, entity: Entity) -> Option<Transform> {
+        match self.parents.get(&entity) {
+            Some(parent) => {
+                let parent_world = self.world_transform(parent.entity).unwrap_or_default();
+                Some(parent_world.to_world(&parent.local))
+            }
+            None => self.transforms.get(&entity).copied(),
+        }
+    }
+
+    /// An entity's transform relative to its parent, or in the world if it has none
+    pub fn local_transform(&self, entity: Entity) -> Option<Transform> {
+        match self.parents.get(&entity) {
+            Some(parent) => Some(parent.local),
+            None => self.transforms.get(&entity).copied(),
+        }
+    }
+
+    /// Place an entity relative to its parent, or in the world if it has none,
+    /// and move its descendants with it
+    pub fn set_local_transform(&mut self, entity: Entity, transform: Transform) {
+        match self.parents.get_mut(&entity) {
+            Some(parent) => parent.local = transform,
+            None => {
+                self.transforms.insert(entity, transform);
+            }
+        }
+        self.skip_interpolation(entity);
+        self.refresh_world_transforms(entity);
+    }
+
+    /// After an entity's world transform was moved directly, as dragging does,
+    /// update its place relative to its parent and move its descendants with it
+    pub fn sync_local_transform(&mut self, entity: Entity) {
+        if let (Some(parent), Some(world)) = (self.parent_of(entity), self.transforms.get(&entity))
+        {
+            let parent_world = self.world_transform(parent).unwrap_or_default();
+            let local = parent_world.to_local(world);
+            if let Some(link) = self.parents.get_mut(&entity) {
+                link.local = local;
+            }
+        }
+        self.skip_interpolation(entity);
+        self.refresh_world_transforms(entity);
+    }
+
+    /// Remember where everything is before a fixed step, to draw frames between
+    /// this state and the next
+    pub fn snapshot_transforms(&mut self) {
+        self.previous_transforms.clone_from(&self.transforms);
+    }
+
+    /// Draw an entity and its descendants where they are now rather than sliding
+    /// there from before the last step, after a script or dragging moved them
+    fn skip_interpolation(&mut self, entity: Entity) {
+        if self.previous_transforms.is_empty() {
+            return;
+        }
+        self.previous_transforms.remove(&entity);
+        for descendant in self.descendants_of(entity) {
+            self.previous_transforms.remove(&descendant);
+        }
+    }
+
+    /// Where to draw an entity currently at `current`, `alpha` of the way from
+    /// its transform before the last fixed step
+    pub fn interpolated_transform(
+        &self,
+        entity: Entity,
+        current: &Transform,
+        alpha: f32,
+    ) -> Transform {
+        match self.previous_transforms.get(&entity) {
+            Some(previous) if alpha < 1.0 => previous.lerp(current, alpha),
+            _ => *current,
+        }
+    }
+
+    /// Recompute the world transforms of an entity and its descendants, so a
+    /// change shows up before the next frame resolves everything
+    fn refresh_world_transforms(&mut self, entity: Entity) {
+        if self.parents.is_empty() {
+            return;
+        }
+        for moved in std::iter::once(entity).chain(self.descendants_of(entity)) {
+            if self.parents.contains_key(&moved) {
+                if let Some(world) = self.world_transform(moved) {
+                    self.transforms.insert(moved, world);
+                }
+            }
+        }
+    }
+
+    /// Recompute every child's world transform from its parent's
+    pub fn resolve_world_transforms(&mut self) {
+        let resolved: Vec<(Entity, Transform)> = self
+            .parents
+            .keys()
+            .filter_map(|&child| Some((child, self.world_transform(child)?)))
+            .collect();
+        for (child, world) in resolved {
+            self.transforms.insert(child, world);
+        }
+    }
+
+    /// Live entities that have every one of `components` and every one of `tags`,
+    /// by slot index, so an entity in a reused slot keeps that slot's place. Walks
+    /// the smallest of the stores asked for.
+    pub fn query(&self, components: &[String], tags: &[String]) -> Result<Vec<Entity>, String> {
+        let stores = self.component_stores();
+        let mut wanted = Vec::with_capacity(components.len());
+        for component in components {
+            match stores.iter().find(|(name, _)| name == component) {
+                Some((_, store)) => wanted.push(*store),
+                None => {
+                    let known: Vec<&str> = stores.iter().map(|(name, _)| *name).collect();
+                    return Err(format!(
+                        "Unknown component: {} (expected one of {})",
+                        component,
+                        known.join(", ")
+                    ));
+                }
+            }
+        }
+
+        let candidates = match wanted.iter().min_by_key(|store| store.count()) {
+            Some(smallest) => smallest.entities(),
+            None if !tags.is_empty() => self.tags.keys().copied().collect(),
+            None => self.entities.iter().collect(),
+        };
+        let mut matches: Vec<Entity> = candidates
+            .into_iter()
+            .filter(|&entity| wanted.iter().all(|store| store.contains_entity(entity)))
+            .filter(|entity| {
+                tags.is_empty()
+                    || self
+                        .tags
+                        .get(entity)
+                        .is_some_and(|names| tags.iter().all(|tag| names.contains(tag)))
+            })
+            .collect();
+        // Handles compare by generation first, which would put reused slots last
+        matches.sort_unstable_by_key(|&entity| index(entity));
+        Ok(matches)
     }
 
     /// Add Text
@@ -128,9 +555,75 @@ impl GameState {
     }
 
     /// Set the value of the text
-    pub fn set_text_value(&mut self, entity_id: u32, value: String) {
+    pub fn set_text_value(&mut self, entity_id: Entity, value: String) {
         if let Some(text) = self.texts.get_mut(&entity_id) {
             text.value = Some(value);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destroying_a_parent_leaves_no_orphans() {
+        let mut state = GameState::new();
+        let parent = state.create_entity();
+        let child = state.create_entity();
+        let grandchild = state.create_entity();
+        for entity in [parent, child, grandchild] {
+            state.add_transform(entity, Transform::default());
+            state.add_velocity(entity, Velocity::zero());
+        }
+        state.attach(child, parent).unwrap();
+        state.attach(grandchild, child).unwrap();
+
+        assert!(state.destroy_entity(parent));
+        assert!(!state.is_alive(child));
+        assert!(!state.is_alive(grandchild));
+        assert!(state.orphaned_components().is_empty());
+        assert!(state.children.is_empty());
+    }
+
+    #[test]
+    fn children_follow_attach_and_detach() {
+        let mut state = GameState::new();
+        let first = state.create_entity();
+        let second = state.create_entity();
+        let children: Vec<Entity> = (0..3).map(|_| state.create_entity()).collect();
+        for &child in children.iter().rev() {
+            state.attach(child, first).unwrap();
+        }
+        assert_eq!(state.children_of(first), children);
+
+        state.attach(children[1], second).unwrap();
+        assert_eq!(state.children_of(first), [children[0], children[2]]);
+        assert_eq!(state.children_of(second), [children[1]]);
+        assert_eq!(state.descendants_of(second), [children[1]]);
+
+        assert!(state.detach(children[1]));
+        assert!(!state.detach(children[1]));
+        assert!(state.children_of(second).is_empty());
+
+        assert!(state.destroy_entity(children[0]));
+        assert_eq!(state.children_of(first), [children[2]]);
+    }
+
+    #[test]
+    fn query_lists_reused_slots_in_place() {
+        let mut state = GameState::new();
+        let first = state.create_entity();
+        let second = state.create_entity();
+        state.destroy_entity(first);
+        let reused = state.create_entity();
+        assert_eq!(index(reused), index(first));
+        assert!(reused > second);
+
+        for entity in [second, reused] {
+            state.add_transform(entity, Transform::default());
+        }
+        let transform = ["transform".to_string()];
+        assert_eq!(state.query(&transform, &[]).unwrap(), [reused, second]);
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum GravityType {
     Downward,   // Traditional downward gravity
     Attractive, // Like a black hole
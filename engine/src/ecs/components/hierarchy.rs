@@ -0,0 +1,17 @@
+use super::entity::Entity;
+use super::transform::Transform;
+
+/// Links a child entity to its parent. The child's `Transform` in `GameState` is
+/// its world transform, worked out from the parent's each frame; `local` is
+/// where it sits relative to the parent, and what scripts set and read.
+#[derive(Clone, Copy, Debug)]
+pub struct Parent {
+    pub entity: Entity,
+    pub local: Transform,
+}
+
+impl Parent {
+    pub fn new(entity: Entity, local: Transform) -> Self {
+        Self { entity, local }
+    }
+}
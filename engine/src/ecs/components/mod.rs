@@ -2,7 +2,12 @@ pub mod draggable;
 pub mod entity;
 pub mod gamestate;
 pub mod gravity;
+pub mod hierarchy;
+pub mod prefab;
+pub mod scene;
 pub mod sprite;
+pub mod storage;
+pub mod tag;
 pub mod text;
 pub mod tilemap;
 pub mod timer;
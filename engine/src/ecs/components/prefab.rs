@@ -0,0 +1,103 @@
+use super::gravity::GravityType;
+use super::sprite::ShapeParams;
+
+/// A named entity template from `define_prefab`. Every part is optional: `spawn`
+/// adds only the components the prefab, or the overrides it was spawned with,
+/// mention. Overrides are a `Prefab` too, merged in with [`Prefab::merged`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Prefab {
+    pub transform: Option<TransformSpec>,
+    pub shape: Option<ShapeSpec>,
+    pub velocity: Option<VelocitySpec>,
+    pub gravity: Option<GravitySpec>,
+    pub draggable: Option<bool>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// Unset fields spawn as 0
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TransformSpec {
+    pub x: Option<f32>,
+    pub y: Option<f32>,
+    pub rotation: Option<f32>,
+}
+
+/// Arguments for `add_shape`. Spawning needs a name; the color defaults to white
+/// and unset sizes to the shape asset's own.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShapeSpec {
+    pub name: Option<String>,
+    pub color: Option<(u8, u8, u8)>,
+    pub params: ShapeParams,
+}
+
+/// Unset fields spawn as 0
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VelocitySpec {
+    pub dx: Option<f32>,
+    pub dy: Option<f32>,
+    pub angular: Option<f32>,
+}
+
+/// Spawning needs a force and a terminal velocity; the type defaults to downward
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GravitySpec {
+    pub gravity_type: Option<GravityType>,
+    pub force: Option<f32>,
+    pub terminal_velocity: Option<f32>,
+}
+
+impl Prefab {
+    /// This prefab with `overrides` laid over it field by field. Tags are added to
+    /// the prefab's rather than replacing them.
+    pub fn merged(&self, overrides: &Prefab) -> Prefab {
+        Prefab {
+            transform: merge(&self.transform, &overrides.transform, |base, over| {
+                TransformSpec {
+                    x: over.x.or(base.x),
+                    y: over.y.or(base.y),
+                    rotation: over.rotation.or(base.rotation),
+                }
+            }),
+            shape: merge(&self.shape, &overrides.shape, |base, over| {
+                let mut params = base.params.clone();
+                params.extend(over.params.clone());
+                ShapeSpec {
+                    name: over.name.clone().or_else(|| base.name.clone()),
+                    color: over.color.or(base.color),
+                    params,
+                }
+            }),
+            velocity: merge(&self.velocity, &overrides.velocity, |base, over| {
+                VelocitySpec {
+                    dx: over.dx.or(base.dx),
+                    dy: over.dy.or(base.dy),
+                    angular: over.angular.or(base.angular),
+                }
+            }),
+            gravity: merge(&self.gravity, &overrides.gravity, |base, over| {
+                GravitySpec {
+                    gravity_type: over
+                        .gravity_type
+                        .clone()
+                        .or_else(|| base.gravity_type.clone()),
+                    force: over.force.or(base.force),
+                    terminal_velocity: over.terminal_velocity.or(base.terminal_velocity),
+                }
+            }),
+            draggable: overrides.draggable.or(self.draggable),
+            tags: merge(&self.tags, &overrides.tags, |base, over| {
+                let mut tags = base.clone();
+                tags.extend(over.iter().filter(|tag| !base.contains(tag)).cloned());
+                tags
+            }),
+        }
+    }
+}
+
+fn merge<T: Clone>(base: &Option<T>, over: &Option<T>, both: impl Fn(&T, &T) -> T) -> Option<T> {
+    match (base, over) {
+        (Some(base), Some(over)) => Some(both(base, over)),
+        (base, over) => over.clone().or_else(|| base.clone()),
+    }
+}
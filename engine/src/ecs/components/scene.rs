@@ -0,0 +1,27 @@
+use mlua::Function;
+use std::rc::Rc;
+
+/// A scene from `define_scene`: the callbacks it runs while it is on the scene stack
+#[derive(Debug, Clone, Default)]
+pub struct Scene {
+    pub on_enter: Option<Function>,
+    pub on_frame: Option<Function>,
+    pub on_fixed_update: Option<Function>,
+    pub on_exit: Option<Function>,
+    /// Keep drawing the scene's entities while another scene is pushed over it
+    pub draw_when_paused: bool,
+}
+
+/// One push of a scene. Pushing the same scene twice makes two instances, each
+/// owning the entities created while it was on top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SceneInstance(pub u32);
+
+/// An entry on the scene stack. It keeps the scene it was pushed with, so
+/// defining the name again doesn't change a scene already on the stack.
+#[derive(Debug, Clone)]
+pub struct ActiveScene {
+    pub name: String,
+    pub instance: SceneInstance,
+    pub scene: Rc<Scene>,
+}
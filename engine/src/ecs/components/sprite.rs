@@ -1,8 +1,18 @@
+use std::collections::BTreeMap;
+
 // If I want to do batch rendering or manage a zillion sprites
 // at once, that's typically done in a rendering system that
 // loops over all entities with a Sprite component and a
 // Transform component, draws them in the correct order, etc.
 
+/// Every size `add_shape` reads from its params table, whichever shape it is
+pub const SHAPE_PARAMS: &[&str] = &[
+    "width", "height", "radius", "x1", "y1", "x2", "y2", "x3", "y3",
+];
+
+/// Sizes that replace a shape asset's defaults, keyed by the names in `SHAPE_PARAMS`
+pub type ShapeParams = BTreeMap<String, f32>;
+
 #[derive(Clone, Debug)]
 pub enum SpriteShapeData {
     Rectangle { width: f32, height: f32 },
@@ -0,0 +1,166 @@
+use super::entity::{index, Entity};
+
+/// Marks an entity slot with no component in a `SparseSet`
+const EMPTY: u32 = u32::MAX;
+
+/// Component storage for one component type. Components are packed in a dense
+/// array, so iterating them walks contiguous memory, and each entity's slot index
+/// leads straight to its component without hashing. The API mirrors the
+/// `HashMap<Entity, T>` it replaces.
+#[derive(Debug)]
+pub struct SparseSet<T> {
+    /// Position in `dense` of each entity slot's component, or `EMPTY`
+    sparse: Vec<u32>,
+    /// Owner of each component in `components`, in the same order
+    dense: Vec<Entity>,
+    components: Vec<T>,
+}
+
+// By hand so `clone_from` reuses the arrays, as the per-step transform snapshot does
+impl<T: Clone> Clone for SparseSet<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sparse: self.sparse.clone(),
+            dense: self.dense.clone(),
+            components: self.components.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.sparse.clone_from(&source.sparse);
+        self.dense.clone_from(&source.dense);
+        self.components.clone_from(&source.components);
+    }
+}
+
+impl<T> SparseSet<T> {
+    pub fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            dense: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+
+    /// Position of `entity`'s component in the dense arrays. The slot may have
+    /// been reused, so the stored owner must match the whole handle.
+    fn position(&self, entity: Entity) -> Option<usize> {
+        let position = *self.sparse.get(index(entity) as usize)?;
+        (position != EMPTY && self.dense[position as usize] == entity).then_some(position as usize)
+    }
+
+    /// Attach a component, returning the one it replaces
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if let Some(position) = self.position(entity) {
+            return Some(std::mem::replace(&mut self.components[position], component));
+        }
+        let slot = index(entity) as usize;
+        if slot >= self.sparse.len() {
+            self.sparse.resize(slot + 1, EMPTY);
+        }
+        self.sparse[slot] = self.dense.len() as u32;
+        self.dense.push(entity);
+        self.components.push(component);
+        None
+    }
+
+    /// Detach a component. The last component moves into its place, so removal
+    /// doesn't shift the rest but changes iteration order.
+    pub fn remove(&mut self, entity: &Entity) -> Option<T> {
+        let position = self.position(*entity)?;
+        self.sparse[index(*entity) as usize] = EMPTY;
+        self.dense.swap_remove(position);
+        let component = self.components.swap_remove(position);
+        if let Some(&moved) = self.dense.get(position) {
+            self.sparse[index(moved) as usize] = position as u32;
+        }
+        Some(component)
+    }
+
+    pub fn get(&self, entity: &Entity) -> Option<&T> {
+        self.position(*entity)
+            .map(|position| &self.components[position])
+    }
+
+    pub fn get_mut(&mut self, entity: &Entity) -> Option<&mut T> {
+        self.position(*entity)
+            .map(|position| &mut self.components[position])
+    }
+
+    pub fn contains_key(&self, entity: &Entity) -> bool {
+        self.position(*entity).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    /// Entities with this component, in storage order
+    pub fn keys(&self) -> std::slice::Iter<'_, Entity> {
+        self.dense.iter()
+    }
+
+    pub fn values(&self) -> std::slice::Iter<'_, T> {
+        self.components.iter()
+    }
+
+    pub fn iter(&self) -> std::iter::Zip<std::slice::Iter<'_, Entity>, std::slice::Iter<'_, T>> {
+        self.dense.iter().zip(self.components.iter())
+    }
+
+    pub fn iter_mut(
+        &mut self,
+    ) -> std::iter::Zip<std::slice::Iter<'_, Entity>, std::slice::IterMut<'_, T>> {
+        self.dense.iter().zip(self.components.iter_mut())
+    }
+}
+
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SparseSet<T> {
+    type Item = (&'a Entity, &'a T);
+    type IntoIter = std::iter::Zip<std::slice::Iter<'a, Entity>, std::slice::Iter<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut SparseSet<T> {
+    type Item = (&'a Entity, &'a mut T);
+    type IntoIter = std::iter::Zip<std::slice::Iter<'a, Entity>, std::slice::IterMut<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Entities that have both components, with both of them. Walks `first` and
+/// looks each entity up in `second`, so pass the smaller store first.
+pub fn join<'a, A, B>(
+    first: &'a SparseSet<A>,
+    second: &'a SparseSet<B>,
+) -> impl Iterator<Item = (Entity, &'a A, &'a B)> {
+    first
+        .iter()
+        .filter_map(move |(&entity, a)| second.get(&entity).map(|b| (entity, a, b)))
+}
+
+/// Like `join`, with the first component mutable: e.g. moving every transform by
+/// its velocity
+pub fn join_mut<'a, A, B>(
+    first: &'a mut SparseSet<A>,
+    second: &'a SparseSet<B>,
+) -> impl Iterator<Item = (Entity, &'a mut A, &'a B)> {
+    first
+        .iter_mut()
+        .filter_map(move |(&entity, a)| second.get(&entity).map(|b| (entity, a, b)))
+}
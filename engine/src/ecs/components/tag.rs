@@ -0,0 +1,17 @@
+use std::collections::BTreeSet;
+
+/// Names a script gives an entity to find it again with `query`, like "enemy"
+#[derive(Clone, Debug, Default)]
+pub struct Tags {
+    pub names: BTreeSet<String>,
+}
+
+impl Tags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(name)
+    }
+}
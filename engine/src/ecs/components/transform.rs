@@ -1,4 +1,4 @@
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub struct Transform {
     pub x: f32,
     pub y: f32,
@@ -15,6 +15,44 @@ impl Transform {
         self.y += dy;
     }
 
+    /// Where a child placed at `local` relative to this transform ends up
+    pub fn to_world(self, local: &Transform) -> Transform {
+        let (sin, cos) = self.rotation.sin_cos();
+        let mut world = Transform::new(
+            self.x + local.x * cos - local.y * sin,
+            self.y + local.x * sin + local.y * cos,
+            self.rotation,
+        );
+        world.rotate(local.rotation);
+        world
+    }
+
+    /// The inverse of `to_world`: `world` relative to this transform
+    pub fn to_local(self, world: &Transform) -> Transform {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (dx, dy) = (world.x - self.x, world.y - self.y);
+        let mut local = Transform::new(dx * cos + dy * sin, dy * cos - dx * sin, world.rotation);
+        local.rotate(-self.rotation);
+        local
+    }
+
+    /// `t` of the way from this transform to `to`, turning the short way round
+    pub fn lerp(self, to: &Transform, t: f32) -> Transform {
+        let mut turn = (to.rotation - self.rotation) % std::f32::consts::TAU;
+        if turn > std::f32::consts::PI {
+            turn -= std::f32::consts::TAU;
+        } else if turn < -std::f32::consts::PI {
+            turn += std::f32::consts::TAU;
+        }
+        let mut between = Transform::new(
+            self.x + (to.x - self.x) * t,
+            self.y + (to.y - self.y) * t,
+            self.rotation,
+        );
+        between.rotate(turn * t);
+        between
+    }
+
     pub fn rotate(&mut self, angle: f32) {
         // Add the new angle to the current rotation
         self.rotation += angle;
@@ -1,5 +1,6 @@
 use crate::assets::asset_manager::AssetManager;
 use crate::assets::asset_manager::{BuiltInAsset, PrimitiveShape};
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::gamestate::GameState;
 use crate::ecs::components::transform::Transform;
 
@@ -255,8 +256,8 @@ impl CollisionSystem {
     pub fn are_entities_colliding(
         state: &GameState,
         asset_manager: &AssetManager,
-        entity1: u32,
-        entity2: u32,
+        entity1: Entity,
+        entity2: Entity,
     ) -> Result<bool, &'static str> {
         let transform1 = state
             .transforms
@@ -291,8 +292,8 @@ impl CollisionSystem {
     pub fn check_entity_tilemap_collision(
         state: &GameState,
         asset_manager: &AssetManager,
-        entity_id: u32,
-        tilemap_entity: u32,
+        entity_id: Entity,
+        tilemap_entity: Entity,
         x: f32,
         y: f32,
     ) -> Result<bool, &'static str> {
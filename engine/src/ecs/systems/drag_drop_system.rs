@@ -1,9 +1,10 @@
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::gamestate::GameState;
 use crate::ecs::systems::input_system::InputSystem;
 use sdl2::mouse::MouseButton;
 
 pub struct DragDropSystem {
-    dragged_entity: Option<u32>,
+    dragged_entity: Option<Entity>,
     drag_offset_x: f32,
     drag_offset_y: f32,
 }
@@ -20,7 +21,7 @@ impl DragDropSystem {
     // Explicitly start a drag operation
     pub fn start_drag(
         &mut self,
-        entity: u32,
+        entity: Entity,
         mouse_x: f32,
         mouse_y: f32,
         transform_x: f32,
@@ -69,6 +70,7 @@ impl DragDropSystem {
                     transform.x = new_x;
                     transform.y = new_y;
                 }
+                state.sync_local_transform(entity);
 
                 if !input.is_mouse_button_pressed(MouseButton::Left) {
                     self.end_drag();
@@ -84,9 +86,13 @@ impl DragDropSystem {
         state: &GameState,
         mouse_x: f32,
         mouse_y: f32,
-    ) -> Option<u32> {
+    ) -> Option<Entity> {
         // simple point-in-rectangle collision check
         for (&entity_id, transform) in &state.transforms {
+            if state.is_paused(entity_id) {
+                continue;
+            }
+
             // you'll need to get the entity's size from somewhere -
             // perhaps add a Size component to your ecs
             let half_width = 16.0; // Example size
@@ -1,3 +1,4 @@
+use crate::ecs::components::entity::Entity;
 use crate::engine::managers::state_manager::StateManager;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -16,7 +17,12 @@ impl MovementSystem {
     pub fn update(&self, delta_time: f32) {
         // Process movement commands
         if let Ok(mut state) = self.state_manager.borrow().state.try_borrow_mut() {
+            let paused = state.paused_entities();
             for (&entity_id, velocity) in state.velocities.iter_mut() {
+                if paused.contains(&entity_id) {
+                    continue;
+                }
+
                 // Apply any direct velocity changes from commands
                 if let Some(command) = self.get_pending_movement_command(entity_id) {
                     match command {
@@ -52,14 +58,14 @@ impl MovementSystem {
     }
 
     /// Retrieves any pending movement command for an entity
-    fn get_pending_movement_command(&self, _entity_id: u32) -> Option<MovementCommand> {
+    fn get_pending_movement_command(&self, _entity_id: Entity) -> Option<MovementCommand> {
         // This would interface with your command queue or input system
         // For now, we'll return None as a placeholder
         None
     }
 
     /// Gets the friction coefficient for an entity if it has one
-    fn get_friction(&self, _entity_id: u32) -> Option<f32> {
+    fn get_friction(&self, _entity_id: Entity) -> Option<f32> {
         // This could be expanded to look up friction from a component
         // For now we'll return a default friction
         Some(0.98)
@@ -1,10 +1,12 @@
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::gravity::GravityType;
+use crate::ecs::components::storage::{join, join_mut};
 use crate::GameState;
 use std::collections::HashMap;
 
 pub struct PhysicsSystem {
     // Cache for storing intermediate force calculations
-    force_accumulator: HashMap<u32, (f32, f32)>,
+    force_accumulator: HashMap<Entity, (f32, f32)>,
 }
 
 impl PhysicsSystem {
@@ -29,55 +31,53 @@ impl PhysicsSystem {
         self.force_accumulator.clear();
 
         // Accumulate gravity forces
-        for (&entity_id, gravity) in &state.gravities {
-            if !gravity.enabled {
+        for (entity_id, gravity, transform) in join(&state.gravities, &state.transforms) {
+            if !gravity.enabled || state.is_paused(entity_id) {
                 continue;
             }
 
-            if let Some(transform) = state.transforms.get(&entity_id) {
-                let force = match gravity.gravity_type {
-                    GravityType::Downward => {
-                        // Simple downward force
-                        (0.0, gravity.force)
-                    }
-                    GravityType::Attractive | GravityType::Repulsive => {
-                        let mut total_fx = 0.0;
-                        let mut total_fy = 0.0;
-
-                        // Calculate gravitational influence from all other entities
-                        for (&other_id, other_transform) in &state.transforms {
-                            if other_id != entity_id {
-                                let dx = other_transform.x - transform.x;
-                                let dy = other_transform.y - transform.y;
-                                let distance_squared = dx * dx + dy * dy;
-
-                                if distance_squared > 0.0001 {
-                                    let distance = distance_squared.sqrt();
-                                    let force_magnitude = gravity.force / distance_squared;
-
-                                    // Direction vector
-                                    let dir_x = dx / distance;
-                                    let dir_y = dy / distance;
-
-                                    // Attractive pulls toward, Repulsive pushes away
-                                    let multiplier = match gravity.gravity_type {
-                                        GravityType::Attractive => 1.0,
-                                        GravityType::Repulsive => -1.0,
-                                        _ => unreachable!(),
-                                    };
-
-                                    total_fx += dir_x * force_magnitude * multiplier;
-                                    total_fy += dir_y * force_magnitude * multiplier;
-                                }
+            let force = match gravity.gravity_type {
+                GravityType::Downward => {
+                    // Simple downward force
+                    (0.0, gravity.force)
+                }
+                GravityType::Attractive | GravityType::Repulsive => {
+                    let mut total_fx = 0.0;
+                    let mut total_fy = 0.0;
+
+                    // Calculate gravitational influence from all other entities
+                    for (&other_id, other_transform) in &state.transforms {
+                        if other_id != entity_id {
+                            let dx = other_transform.x - transform.x;
+                            let dy = other_transform.y - transform.y;
+                            let distance_squared = dx * dx + dy * dy;
+
+                            if distance_squared > 0.0001 {
+                                let distance = distance_squared.sqrt();
+                                let force_magnitude = gravity.force / distance_squared;
+
+                                // Direction vector
+                                let dir_x = dx / distance;
+                                let dir_y = dy / distance;
+
+                                // Attractive pulls toward, Repulsive pushes away
+                                let multiplier = match gravity.gravity_type {
+                                    GravityType::Attractive => 1.0,
+                                    GravityType::Repulsive => -1.0,
+                                    _ => unreachable!(),
+                                };
+
+                                total_fx += dir_x * force_magnitude * multiplier;
+                                total_fy += dir_y * force_magnitude * multiplier;
                             }
                         }
-                        (total_fx, total_fy)
                     }
-                };
+                    (total_fx, total_fy)
+                }
+            };
 
-                // Store the accumulated force
-                self.force_accumulator.insert(entity_id, force);
-            }
+            // Store the accumulated force
+            self.force_accumulator.insert(entity_id, force);
         }
     }
 
@@ -108,15 +108,32 @@ impl PhysicsSystem {
     }
 
     fn integrate_positions(&self, state: &mut GameState, delta_time: f32) {
+        // Entities of a scene with another pushed over it stay where they are
+        let paused = state.paused_entities();
+
         // Update positions based on final velocities
-        for (&entity_id, velocity) in &state.velocities {
-            if let Some(transform) = state.transforms.get_mut(&entity_id) {
-                // Linear motion
-                transform.translate(velocity.dx * delta_time, velocity.dy * delta_time);
+        for (entity_id, transform, velocity) in join_mut(&mut state.transforms, &state.velocities) {
+            if paused.contains(&entity_id) {
+                continue;
+            }
+
+            // Linear motion
+            transform.translate(velocity.dx * delta_time, velocity.dy * delta_time);
 
-                // Angular motion
-                transform.rotate(velocity.angular * delta_time);
+            // Angular motion
+            transform.rotate(velocity.angular * delta_time);
+        }
+
+        // Children move relative to their parent; their world transforms are
+        // resolved from it afterwards
+        for (entity_id, parent, velocity) in join_mut(&mut state.parents, &state.velocities) {
+            if paused.contains(&entity_id) {
+                continue;
             }
+            parent
+                .local
+                .translate(velocity.dx * delta_time, velocity.dy * delta_time);
+            parent.local.rotate(velocity.angular * delta_time);
         }
     }
 }
@@ -1,20 +1,28 @@
+use crate::ecs::components::storage::join;
 use crate::engine::managers::state_manager::StateManager;
-use crate::engine::rendering::{Renderer, Sdl2Renderer};
+use crate::engine::rendering::Renderer;
 use sdl2::pixels::Color;
 use std::cell::RefCell;
 use std::rc::Rc;
 use tracing::warn;
 
+/// Draw the game. Moving entities are drawn `alpha` of the way from where they were
+/// before the last fixed step to where they are now; 1 draws the current state.
 pub fn render_system(
     state_manager: Rc<RefCell<StateManager>>,
-    renderer: &mut Sdl2Renderer,
+    renderer: &mut impl Renderer,
     scale: f32,
+    alpha: f32,
     debug: bool,
 ) {
     // Try to borrow the state for reading
     if let Ok(state) = state_manager.borrow().state.try_borrow() {
         // First render tilemaps (they should be in the background)
-        for (&_, tilemap) in &state.tilemaps {
+        for (&entity, tilemap) in &state.tilemaps {
+            if state.is_hidden(entity) {
+                continue;
+            }
+
             // Get tilemap dimensions
             let tile_size = tilemap.tile_size;
 
@@ -51,57 +59,65 @@ pub fn render_system(
         }
 
         // Then render sprites (they should be on top of tilemaps)
-        for (&entity, transform) in &state.transforms {
-            if let Some(sprite) = state.sprites.get(&entity) {
-                // Get the asset definition from the state manager
-                if let Some(asset) = state_manager.borrow().get_asset(&sprite.asset_name) {
-                    // Let the asset system handle the rendering based on the shape type
-                    state_manager.borrow().render_asset(
-                        asset,
-                        sprite.shape_data.as_ref(),
-                        transform.x as i32,
-                        transform.y as i32,
-                        sprite.color,
-                        renderer,
-                        scale,
-                        debug,
-                    );
-                }
-            } else if let Some(text) = state.texts.get(&entity) {
-                // Only show warning if entity has neither sprite nor text
-                if !text.visible {
-                    continue;
-                }
+        let manager = state_manager.borrow();
+        for (entity, sprite, transform) in join(&state.sprites, &state.transforms) {
+            if state.is_hidden(entity) {
+                continue;
+            }
+            let transform = state.interpolated_transform(entity, transform, alpha);
 
-                let color = Color::RGB(text.color.0, text.color.1, text.color.2);
-                let content = text.get_string();
+            // Get the asset definition from the state manager
+            if let Some(asset) = manager.get_asset(&sprite.asset_name) {
+                // Let the asset system handle the rendering based on the shape type
+                manager.render_asset(
+                    asset,
+                    sprite.shape_data.as_ref(),
+                    transform.x as i32,
+                    transform.y as i32,
+                    sprite.color,
+                    renderer,
+                    scale,
+                    debug,
+                );
+            }
+        }
 
-                renderer.draw_text(
-                    content,
+        // Text on top of the sprites; an entity with a sprite shows only the sprite
+        for (entity, text, transform) in join(&state.texts, &state.transforms) {
+            if !text.visible || state.sprites.contains_key(&entity) || state.is_hidden(entity) {
+                continue;
+            }
+            let transform = state.interpolated_transform(entity, transform, alpha);
+
+            let color = Color::RGB(text.color.0, text.color.1, text.color.2);
+            let content = text.get_string();
+
+            renderer.draw_text(
+                content,
+                transform.x as i32,
+                transform.y as i32,
+                color,
+                text.scale,
+            );
+
+            if debug {
+                // draw debug info for text entities
+                renderer.draw_bounding_box(
                     transform.x as i32,
                     transform.y as i32,
-                    color,
-                    text.scale,
+                    20, // TODO: Placeholder size, calculate actual text bounds later
+                    20,
+                    Color::RGB(255, 255, 255),
                 );
+            }
+        }
 
-                if debug {
-                    // draw debug info for text entities
-                    renderer.draw_bounding_box(
-                        transform.x as i32,
-                        transform.y as i32,
-                        20, // TODO: Placeholder size, calculate actual text bounds later
-                        20,
-                        Color::RGB(255, 255, 255),
-                    );
-                }
-            } else {
-                // Only show warning if entity has neither sprite nor text
-                if !state.texts.contains_key(&entity) {
-                    warn!(
-                        "Warning: Entity {} has transform but no sprite or text",
-                        entity
-                    );
-                }
+        for &entity in state.transforms.keys() {
+            if !state.sprites.contains_key(&entity) && !state.texts.contains_key(&entity) {
+                warn!(
+                    "Warning: Entity {} has transform but no sprite or text",
+                    entity
+                );
             }
         }
     }
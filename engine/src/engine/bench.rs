@@ -0,0 +1,170 @@
+use crate::ecs::components::sprite::{Sprite, SpriteShapeData};
+use crate::ecs::components::transform::Transform;
+use crate::ecs::components::velocity::Velocity;
+use crate::ecs::{render_system, GameState, InputSystem, MovementSystem, PhysicsSystem};
+use crate::engine::managers::state_manager::StateManager;
+use crate::engine::rendering::{Renderer, SoftwareRenderer, WINDOW_HEIGHT, WINDOW_WIDTH};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+pub const BENCH_USAGE: &str = "\
+Usage: qr-game-engine bench [options]
+
+Times the engine's per-frame work on a scene of moving entities: the movement and
+physics systems, then render_system drawing into an off-screen image.
+
+Options:
+  --entities <n>  Moving entities in the scene (default: 10000)
+  --frames <n>    Frames to time (default: 300)";
+
+/// A fixed frame time, so every run does the same work
+const DELTA_TIME: f32 = 1.0 / 60.0;
+
+pub struct BenchOptions {
+    pub entities: u32,
+    pub frames: u32,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            entities: 10_000,
+            frames: 300,
+        }
+    }
+}
+
+impl BenchOptions {
+    /// Parse the arguments that follow `bench` on the command line
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let target = match arg.as_str() {
+                "--entities" => &mut options.entities,
+                "--frames" => &mut options.frames,
+                other => return Err(format!("Unknown option: {}\n\n{}", other, BENCH_USAGE)),
+            };
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} expects a value", arg))?;
+            *target = value
+                .parse()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| format!("Invalid value for {}: {}", arg, value))?;
+        }
+        Ok(options)
+    }
+}
+
+/// Time spent in each part of the frames
+pub struct BenchReport {
+    pub update: Vec<Duration>,
+    pub render: Vec<Duration>,
+}
+
+impl BenchReport {
+    fn frame(&self, index: usize) -> Duration {
+        self.update[index] + self.render[index]
+    }
+
+    fn mean(times: impl Iterator<Item = Duration>, count: usize) -> f64 {
+        times.sum::<Duration>().as_secs_f64() * 1000.0 / count as f64
+    }
+}
+
+/// A scene of `count` small shapes scattered over the window, each moving in its
+/// own direction
+fn populate(state: &mut GameState, count: u32) {
+    // A fixed linear congruential sequence keeps the scene the same between runs
+    let mut seed: u32 = 0x2545_f491;
+    let mut next = move || {
+        seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        (seed >> 8) as f32 / (1 << 24) as f32
+    };
+    for _ in 0..count {
+        let entity = state.create_entity();
+        state.add_transform(
+            entity,
+            Transform::new(
+                next() * WINDOW_WIDTH as f32,
+                next() * WINDOW_HEIGHT as f32,
+                0.0,
+            ),
+        );
+        state.add_velocity(
+            entity,
+            Velocity::new(next() * 120.0 - 60.0, next() * 120.0 - 60.0),
+        );
+        state.add_sprite(
+            entity,
+            Sprite::new(
+                "circle",
+                (next() * 255.0) as u8,
+                (next() * 255.0) as u8,
+                (next() * 255.0) as u8,
+                Some(SpriteShapeData::Circle { radius: 3.0 }),
+            ),
+        );
+    }
+}
+
+pub fn bench(options: &BenchOptions) -> BenchReport {
+    let state = Rc::new(RefCell::new(GameState::new()));
+    populate(&mut state.borrow_mut(), options.entities);
+    let state_manager = Rc::new(RefCell::new(StateManager::new(
+        Rc::clone(&state),
+        Rc::new(RefCell::new(InputSystem::new())),
+    )));
+    let movement_system = MovementSystem::new(Rc::clone(&state_manager));
+    let mut physics_system = PhysicsSystem::new();
+    let mut renderer = SoftwareRenderer::new(WINDOW_WIDTH, WINDOW_HEIGHT);
+
+    let mut report = BenchReport {
+        update: Vec::new(),
+        render: Vec::new(),
+    };
+    for _ in 0..options.frames {
+        let start = Instant::now();
+        movement_system.update(DELTA_TIME);
+        physics_system.update(&mut state.borrow_mut(), DELTA_TIME);
+        state.borrow_mut().resolve_world_transforms();
+        report.update.push(start.elapsed());
+
+        let start = Instant::now();
+        renderer.clear();
+        render_system(Rc::clone(&state_manager), &mut renderer, 1.0, 1.0, false);
+        report.render.push(start.elapsed());
+    }
+    report
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = BenchOptions::from_args(args)?;
+    let report = bench(&options);
+
+    let frames = options.frames as usize;
+    let mut sorted: Vec<Duration> = (0..frames).map(|i| report.frame(i)).collect();
+    sorted.sort();
+    println!(
+        "{} moving entities, {} frames",
+        options.entities, options.frames
+    );
+    println!(
+        "  frame:  {:>8.3} ms mean, {:.3} ms median, {:.3} ms worst",
+        BenchReport::mean(sorted.iter().copied(), frames),
+        sorted[frames / 2].as_secs_f64() * 1000.0,
+        sorted[frames - 1].as_secs_f64() * 1000.0
+    );
+    println!(
+        "  update: {:>8.3} ms mean",
+        BenchReport::mean(report.update.iter().copied(), frames)
+    );
+    println!(
+        "  render: {:>8.3} ms mean",
+        BenchReport::mean(report.render.iter().copied(), frames)
+    );
+    Ok(())
+}
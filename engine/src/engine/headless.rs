@@ -0,0 +1,113 @@
+use crate::engine::input::INPUT_SCRIPT_HELP;
+use crate::engine::rendering::DrawCall;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub const HEADLESS_USAGE: &str = "\
+Usage: qr-game-engine headless <game>... [options]
+
+Runs a game without a window, for tests and CI. The game goes through the same
+loop as in the engine, with its input read from a script and its frames drawn
+off-screen or not at all. Fails if the game errors, goes over its script limits,
+or reaches the frame limit before --until is true.
+
+Options:
+  --frames <n>        Frames to run at most (default: 600)
+  --until <expr>      Stop once this Lua expression is true after a frame
+  --input <file>      Input script to play (default: no input)
+  --fps <n>           Frame rate the game's delta_time is based on (default: 60)
+  --screenshot <png>  Save the last frame as an image
+  --record <file>     Write the draw calls of every frame to a text file
+  --trusted           Give the game the full Lua standard library";
+
+pub struct HeadlessOptions {
+    /// A script, a packed cartridge, or the QR code image(s) of a cartridge
+    pub paths: Vec<PathBuf>,
+    pub frames: u64,
+    pub until: Option<String>,
+    pub input: Option<PathBuf>,
+    pub fps: u32,
+    pub screenshot: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub trusted: bool,
+}
+
+impl Default for HeadlessOptions {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            frames: 600,
+            until: None,
+            input: None,
+            fps: 60,
+            screenshot: None,
+            record: None,
+            trusted: false,
+        }
+    }
+}
+
+impl HeadlessOptions {
+    /// Parse the arguments that follow `headless` on the command line
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                options.paths.push(PathBuf::from(arg));
+                continue;
+            }
+            if arg == "--trusted" {
+                options.trusted = true;
+                continue;
+            }
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} expects a value", arg))?;
+            let invalid = || format!("Invalid value for {}: {}", arg, value);
+            match arg.as_str() {
+                "--frames" => {
+                    options.frames = value.parse().ok().filter(|n| *n > 0).ok_or_else(invalid)?
+                }
+                "--fps" => {
+                    options.fps = value.parse().ok().filter(|n| *n > 0).ok_or_else(invalid)?
+                }
+                "--until" => options.until = Some(value.clone()),
+                "--input" => options.input = Some(PathBuf::from(value)),
+                "--screenshot" => options.screenshot = Some(PathBuf::from(value)),
+                "--record" => options.record = Some(PathBuf::from(value)),
+                other => return Err(format!("Unknown option: {}\n\n{}", other, usage())),
+            }
+        }
+        if options.paths.is_empty() {
+            return Err(format!("No game given\n\n{}", usage()));
+        }
+        if options.screenshot.is_some() && options.record.is_some() {
+            return Err("--screenshot and --record can't be used together".to_string());
+        }
+        Ok(options)
+    }
+
+    /// The time every frame advances the game by
+    pub fn frame_time(&self) -> f32 {
+        1.0 / self.fps as f32
+    }
+}
+
+fn usage() -> String {
+    format!("{}\n\n{}", HEADLESS_USAGE, INPUT_SCRIPT_HELP)
+}
+
+/// Write the frames of a `RecordingRenderer`, each under a `frame <n>` line with
+/// one draw call per line after it
+pub fn write_recording(path: &Path, frames: &[Vec<DrawCall>]) -> io::Result<()> {
+    let mut out = io::BufWriter::new(fs::File::create(path)?);
+    for (index, calls) in frames.iter().enumerate() {
+        writeln!(out, "frame {}", index)?;
+        for call in calls {
+            writeln!(out, "  {}", call)?;
+        }
+    }
+    out.flush()
+}
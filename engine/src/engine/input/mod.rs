@@ -0,0 +1,176 @@
+use crate::lua::api::input_api::{string_to_keycode, string_to_mousebutton};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::EventPump;
+
+/// Something the game loop reacts to, from the window or from an input script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent {
+    /// The window was closed, which quits the engine
+    Quit,
+    /// Leave the game, as Escape does
+    Exit,
+    KeyDown(Keycode),
+    KeyUp(Keycode),
+    MouseMotion {
+        x: i32,
+        y: i32,
+    },
+    MouseButtonDown(MouseButton),
+    MouseButtonUp(MouseButton),
+}
+
+/// Where a game's loop gets its input each frame
+pub trait InputSource {
+    /// The events that arrived since the last frame
+    fn poll(&mut self) -> Vec<InputEvent>;
+}
+
+impl InputSource for EventPump {
+    fn poll(&mut self) -> Vec<InputEvent> {
+        self.poll_iter()
+            .filter_map(|event| match event {
+                Event::Quit { .. } => Some(InputEvent::Quit),
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => Some(InputEvent::Exit),
+                Event::KeyDown {
+                    keycode: Some(code),
+                    ..
+                } => Some(InputEvent::KeyDown(code)),
+                Event::KeyUp {
+                    keycode: Some(code),
+                    ..
+                } => Some(InputEvent::KeyUp(code)),
+                Event::MouseMotion { x, y, .. } => Some(InputEvent::MouseMotion { x, y }),
+                Event::MouseButtonDown { mouse_btn, .. } => {
+                    Some(InputEvent::MouseButtonDown(mouse_btn))
+                }
+                Event::MouseButtonUp { mouse_btn, .. } => {
+                    Some(InputEvent::MouseButtonUp(mouse_btn))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+pub const INPUT_SCRIPT_HELP: &str = "\
+An input script has one event per line, `<frame> <event> [arguments]`, with
+frames counted from 0 and `#` starting a comment:
+
+  0   key_down RIGHT
+  30  key_up RIGHT
+  45  mouse_move 400 300
+  45  mouse_down LEFT
+  50  mouse_up LEFT
+  120 exit
+
+Keys and mouse buttons have the names is_key_pressed and is_mouse_pressed take.";
+
+/// Input read from a script instead of the keyboard and mouse, for runs without a
+/// window. Each event is delivered at the start of its frame.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    /// Events by frame, in the order they were written
+    events: Vec<(u64, InputEvent)>,
+    frame: u64,
+}
+
+impl ScriptedInput {
+    /// No input at all
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse an input script, see [`INPUT_SCRIPT_HELP`]
+    pub fn parse(script: &str) -> Result<Self, String> {
+        let mut events = Vec::new();
+        for (index, line) in script.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let event =
+                parse_line(line).map_err(|e| format!("Input script line {}: {}", index + 1, e))?;
+            events.push(event);
+        }
+        // Stable, so events of the same frame keep their order
+        events.sort_by_key(|(frame, _)| *frame);
+        Ok(Self { events, frame: 0 })
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn poll(&mut self) -> Vec<InputEvent> {
+        let frame = self.frame;
+        self.frame += 1;
+        self.events
+            .iter()
+            .filter(|(at, _)| *at == frame)
+            .map(|(_, event)| *event)
+            .collect()
+    }
+}
+
+fn parse_line(line: &str) -> Result<(u64, InputEvent), String> {
+    let mut words = line.split_whitespace();
+    let frame = words.next().unwrap_or_default();
+    let frame = frame
+        .parse()
+        .map_err(|_| format!("expected a frame number, got '{}'", frame))?;
+    let name = words.next().ok_or("expected an event after the frame")?;
+    let mut argument = |what: &str| {
+        words
+            .next()
+            .ok_or_else(|| format!("{} needs a {}", name, what))
+    };
+
+    let event = match name {
+        "key_down" | "key_up" => {
+            let key = argument("key")?;
+            let code = string_to_keycode(key).ok_or_else(|| format!("unknown key '{}'", key))?;
+            if name == "key_down" {
+                InputEvent::KeyDown(code)
+            } else {
+                InputEvent::KeyUp(code)
+            }
+        }
+        "mouse_down" | "mouse_up" => {
+            let button = argument("button")?;
+            let button = string_to_mousebutton(button)
+                .ok_or_else(|| format!("unknown mouse button '{}'", button))?;
+            if name == "mouse_down" {
+                InputEvent::MouseButtonDown(button)
+            } else {
+                InputEvent::MouseButtonUp(button)
+            }
+        }
+        "mouse_move" => {
+            let mut coordinate = |axis: &str| {
+                let value = argument(axis)?;
+                value
+                    .parse()
+                    .map_err(|_| format!("invalid {} coordinate '{}'", axis, value))
+            };
+            let x = coordinate("x")?;
+            let y = coordinate("y")?;
+            InputEvent::MouseMotion { x, y }
+        }
+        "exit" => InputEvent::Exit,
+        "quit" => InputEvent::Quit,
+        other => {
+            return Err(format!(
+                "unknown event '{}', expected key_down, key_up, mouse_move, mouse_down, \
+                 mouse_up, exit or quit",
+                other
+            ))
+        }
+    };
+    if let Some(extra) = words.next() {
+        return Err(format!("unexpected '{}' after {}", extra, name));
+    }
+    Ok((frame, event))
+}
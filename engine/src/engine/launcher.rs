@@ -0,0 +1,177 @@
+use crate::cartridge::library::{self, LibraryEntry};
+use crate::engine::rendering::Renderer;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use std::path::PathBuf;
+
+/// Rows of the game list shown at once
+const VISIBLE_ROWS: usize = 9;
+const ROW_HEIGHT: i32 = 40;
+const LIST_TOP: i32 = 140;
+
+const TEXT_COLOR: Color = Color::RGB(230, 230, 230);
+const DIM_COLOR: Color = Color::RGB(150, 150, 150);
+const HIGHLIGHT_COLOR: Color = Color::RGB(40, 80, 140);
+const ERROR_COLOR: Color = Color::RGB(255, 100, 100);
+
+/// What the player chose in the launcher this frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LauncherAction {
+    None,
+    /// Play the selected game
+    Play,
+    Quit,
+}
+
+/// The menu scene listing the games in a library directory
+pub struct Launcher {
+    library_dir: PathBuf,
+    trusted_keys_dir: PathBuf,
+    entries: Vec<LibraryEntry>,
+    selected: usize,
+    /// Shown under the list, e.g. why the last game stopped
+    message: Option<String>,
+}
+
+impl Launcher {
+    pub fn new(library_dir: PathBuf, trusted_keys_dir: PathBuf) -> Self {
+        let mut launcher = Self {
+            library_dir,
+            trusted_keys_dir,
+            entries: Vec::new(),
+            selected: 0,
+            message: None,
+        };
+        launcher.rescan();
+        launcher
+    }
+
+    /// Read the library directory again, keeping the selection on the same game
+    pub fn rescan(&mut self) {
+        let selected_name = self.selected().map(|entry| entry.name.clone());
+        self.entries = library::scan(&self.library_dir, &self.trusted_keys_dir);
+        self.selected = selected_name
+            .and_then(|name| self.entries.iter().position(|entry| entry.name == name))
+            .unwrap_or(0);
+    }
+
+    pub fn selected(&self) -> Option<&LibraryEntry> {
+        self.entries.get(self.selected)
+    }
+
+    pub fn set_message(&mut self, message: Option<String>) {
+        self.message = message;
+    }
+
+    pub fn handle_key(&mut self, key: Keycode) -> LauncherAction {
+        match key {
+            Keycode::Up | Keycode::W => {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            Keycode::Down | Keycode::S if self.selected + 1 < self.entries.len() => {
+                self.selected += 1;
+            }
+            Keycode::R => {
+                self.message = None;
+                self.rescan();
+            }
+            Keycode::Return | Keycode::KpEnter | Keycode::Space => {
+                match self.selected().map(|entry| &entry.game) {
+                    Some(Ok(_)) => return LauncherAction::Play,
+                    Some(Err(e)) => self.message = Some(format!("Can't play this game: {}", e)),
+                    None => {}
+                }
+            }
+            Keycode::Escape => return LauncherAction::Quit,
+            _ => {}
+        }
+        LauncherAction::None
+    }
+
+    pub fn draw(&self, renderer: &mut impl Renderer, width: u32, height: u32) {
+        let center_x = width as i32 / 2;
+        let height = height as i32;
+
+        renderer.draw_text("QRcade".to_string(), center_x, 50, TEXT_COLOR, 1.5);
+        renderer.draw_text(
+            format!("Library: {}", self.library_dir.display()),
+            center_x,
+            95,
+            DIM_COLOR,
+            0.6,
+        );
+
+        if self.entries.is_empty() {
+            renderer.draw_text(
+                "No games found. Pack one, or put QR images, .qrc or .lua files here.".to_string(),
+                center_x,
+                height / 2,
+                DIM_COLOR,
+                0.6,
+            );
+        }
+
+        // Scroll so the selection stays in view
+        let first = self
+            .selected
+            .saturating_sub(VISIBLE_ROWS - 1)
+            .min(self.entries.len().saturating_sub(VISIBLE_ROWS));
+        for (row, (index, entry)) in self
+            .entries
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(VISIBLE_ROWS)
+            .enumerate()
+        {
+            let y = LIST_TOP + row as i32 * ROW_HEIGHT;
+            if index == self.selected {
+                renderer.draw_rect(
+                    center_x,
+                    y,
+                    width - 80,
+                    ROW_HEIGHT as u32 - 4,
+                    HIGHLIGHT_COLOR,
+                );
+            }
+            let color = if entry.game.is_ok() {
+                TEXT_COLOR
+            } else {
+                DIM_COLOR
+            };
+            renderer.draw_text(entry.title().to_string(), center_x, y, color, 0.9);
+        }
+
+        if let Some(entry) = self.selected() {
+            let (details, color) = match &entry.game {
+                Ok(game) => {
+                    let author = game
+                        .cartridge
+                        .metadata
+                        .author
+                        .as_deref()
+                        .unwrap_or("unknown");
+                    (
+                        format!(
+                            "by {} - {} bytes - {}",
+                            author, game.size, game.verification
+                        ),
+                        TEXT_COLOR,
+                    )
+                }
+                Err(e) => (format!("Unreadable: {}", e), ERROR_COLOR),
+            };
+            renderer.draw_text(details, center_x, height - 80, color, 0.6);
+        }
+        if let Some(message) = &self.message {
+            renderer.draw_text(message.clone(), center_x, height - 110, ERROR_COLOR, 0.6);
+        }
+        renderer.draw_text(
+            "Up/Down to choose, Enter to play, R to rescan, Escape to quit".to_string(),
+            center_x,
+            height - 35,
+            DIM_COLOR,
+            0.5,
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use super::Manager;
 use crate::assets::asset_manager::{AssetManager, PrimitiveShape};
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::gamestate::GameState;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -22,8 +23,8 @@ impl Manager for CollisionManager {
 impl CollisionManager {
     pub fn check_collision(
         &self,
-        entity1: u32,
-        entity2: u32,
+        entity1: Entity,
+        entity2: Entity,
         assets: &AssetManager,
     ) -> Result<bool, &'static str> {
         let state = self
@@ -31,6 +32,9 @@ impl CollisionManager {
             .try_borrow()
             .map_err(|_| "Failed to borrow game state")?;
 
+        state.check_alive(entity1)?;
+        state.check_alive(entity2)?;
+
         let transform1 = state
             .transforms
             .get(&entity1)
@@ -115,8 +119,8 @@ impl CollisionManager {
 
     pub fn check_entity_tilemap_collision(
         &self,
-        entity_id: u32,
-        tilemap_id: u32,
+        entity_id: Entity,
+        tilemap_id: Entity,
         x: f32,
         y: f32,
         assets: &AssetManager,
@@ -126,6 +130,9 @@ impl CollisionManager {
             .try_borrow()
             .map_err(|_| "Failed to borrow game state")?;
 
+        state.check_alive(entity_id)?;
+        state.check_alive(tilemap_id)?;
+
         let sprite = state
             .sprites
             .get(&entity_id)
@@ -1,5 +1,7 @@
 use super::Manager;
 use crate::assets::asset_manager::{AssetManager, PrimitiveShape};
+use crate::ecs::components::draggable::Draggable;
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::gamestate::GameState;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -8,7 +10,7 @@ use tracing::debug;
 // Keep track of dragging state
 #[derive(Debug)]
 struct DraggingState {
-    entity_id: u32,
+    entity_id: Entity,
     offset_x: f32,
     offset_y: f32,
 }
@@ -45,7 +47,7 @@ impl DragDropManager {
     //     }
     // }
 
-    pub fn get_entity_at_point(&self, x: f32, y: f32) -> Result<Option<u32>, &'static str> {
+    pub fn get_entity_at_point(&self, x: f32, y: f32) -> Result<Option<Entity>, &'static str> {
         let state = self
             .state
             .try_borrow()
@@ -178,9 +180,19 @@ impl DragDropManager {
         Ok(None)
     }
 
+    pub fn make_draggable(&self, entity_id: Entity) -> Result<(), &'static str> {
+        let mut state = self
+            .state
+            .try_borrow_mut()
+            .map_err(|_| "Failed to borrow game state")?;
+        state.check_alive(entity_id)?;
+        state.add_draggable(entity_id, Draggable::new());
+        Ok(())
+    }
+
     pub fn start_dragging(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         mouse_x: f32,
         mouse_y: f32,
     ) -> Result<(), &'static str> {
@@ -190,6 +202,8 @@ impl DragDropManager {
                 .try_borrow()
                 .map_err(|_| "Failed to borrow game state")?;
 
+            state.check_alive(entity_id)?;
+
             let transform = state
                 .transforms
                 .get(&entity_id)
@@ -245,6 +259,7 @@ impl DragDropManager {
                     drag_state.entity_id, transform.x, transform.y
                 );
             }
+            state.sync_local_transform(drag_state.entity_id);
         }
         Ok(())
     }
@@ -254,7 +269,7 @@ impl DragDropManager {
         Ok(())
     }
 
-    pub fn is_entity_dragged(&self, entity_id: u32) -> bool {
+    pub fn is_entity_dragged(&self, entity_id: Entity) -> bool {
         if let Some(drag_state) = &*self.dragging_state.borrow() {
             drag_state.entity_id == entity_id
         } else {
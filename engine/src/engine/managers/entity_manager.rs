@@ -1,5 +1,7 @@
 use super::Manager;
 use crate::assets::asset_manager::{AssetManager, PrimitiveShape};
+use crate::ecs::components::entity::Entity;
+use crate::ecs::components::sprite::ShapeParams;
 use crate::GameState;
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -21,16 +23,24 @@ impl Manager for EntityManager {
 }
 
 impl EntityManager {
-    pub fn create_entity(&self) -> Result<u32, &'static str> {
+    pub fn create_entity(&self) -> Result<Entity, &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => Ok(state.create_entity()),
             Err(_) => Err("Failed to borrow game state"),
         }
     }
 
-    pub fn destroy_entity(&self, entity_id: u32) -> Result<(), &'static str> {
+    pub fn is_alive(&self, entity_id: Entity) -> Result<bool, &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => Ok(state.is_alive(entity_id)),
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn destroy_entity(&self, entity_id: Entity) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 state.destroy_entity(entity_id);
                 Ok(())
             }
@@ -40,10 +50,10 @@ impl EntityManager {
 
     pub fn add_sprite(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         asset_name: &str,
         color: (u8, u8, u8),
-        params: Option<mlua::Table>,
+        params: Option<&ShapeParams>,
         assets: &AssetManager,
     ) -> Result<(), &'static str> {
         let asset = assets.get_by_name(asset_name).ok_or("Asset not found")?;
@@ -51,8 +61,8 @@ impl EntityManager {
         let sprite = match &asset.shape {
             PrimitiveShape::Rectangle { width, height } => {
                 if let Some(params) = params {
-                    let new_width: f32 = params.get("width").unwrap_or(*width);
-                    let new_height: f32 = params.get("height").unwrap_or(*height);
+                    let new_width: f32 = params.get("width").copied().unwrap_or(*width);
+                    let new_height: f32 = params.get("height").copied().unwrap_or(*height);
                     crate::ecs::components::sprite::Sprite::new_rectangle(
                         new_width, new_height, color.0, color.1, color.2,
                     )
@@ -64,7 +74,7 @@ impl EntityManager {
             }
             PrimitiveShape::Circle { radius } => {
                 if let Some(params) = params {
-                    let new_radius: f32 = params.get("radius").unwrap_or(*radius);
+                    let new_radius: f32 = params.get("radius").copied().unwrap_or(*radius);
                     crate::ecs::components::sprite::Sprite::new_circle(
                         new_radius, color.0, color.1, color.2,
                     )
@@ -83,12 +93,12 @@ impl EntityManager {
                 y3,
             } => {
                 if let Some(params) = params {
-                    let nx1: f32 = params.get("x1").unwrap_or(*x1);
-                    let ny1: f32 = params.get("y1").unwrap_or(*y1);
-                    let nx2: f32 = params.get("x2").unwrap_or(*x2);
-                    let ny2: f32 = params.get("y2").unwrap_or(*y2);
-                    let nx3: f32 = params.get("x3").unwrap_or(*x3);
-                    let ny3: f32 = params.get("y3").unwrap_or(*y3);
+                    let nx1: f32 = params.get("x1").copied().unwrap_or(*x1);
+                    let ny1: f32 = params.get("y1").copied().unwrap_or(*y1);
+                    let nx2: f32 = params.get("x2").copied().unwrap_or(*x2);
+                    let ny2: f32 = params.get("y2").copied().unwrap_or(*y2);
+                    let nx3: f32 = params.get("x3").copied().unwrap_or(*x3);
+                    let ny3: f32 = params.get("y3").copied().unwrap_or(*y3);
                     crate::ecs::components::sprite::Sprite::new_triangle(
                         (color.0, color.1, color.2),
                         nx1,
@@ -112,8 +122,8 @@ impl EntityManager {
             }
             PrimitiveShape::Line { x2, y2 } => {
                 if let Some(params) = params {
-                    let nx2: f32 = params.get("x2").unwrap_or(*x2);
-                    let ny2: f32 = params.get("y2").unwrap_or(*y2);
+                    let nx2: f32 = params.get("x2").copied().unwrap_or(*x2);
+                    let ny2: f32 = params.get("y2").copied().unwrap_or(*y2);
                     crate::ecs::components::sprite::Sprite::new_line(
                         color.0, color.1, color.2, nx2, ny2,
                     )
@@ -127,6 +137,7 @@ impl EntityManager {
 
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 state.add_sprite(entity_id, sprite);
                 Ok(())
             }
@@ -138,16 +149,16 @@ impl EntityManager {
         if let Ok(state) = self.state.try_borrow() {
             info!("Starting entity debug dump");
 
-            for entity in &state.entities {
+            for entity in state.entities.iter() {
                 let entity_span = debug_span!("entity", id = ?entity).entered();
 
                 // Print transform information
-                if let Some(transform) = state.transforms.get(entity) {
+                if let Some(transform) = state.transforms.get(&entity) {
                     debug!(x = transform.x, y = transform.y, "Transform position");
                 }
 
                 // Print sprite information
-                if let Some(sprite) = state.sprites.get(entity) {
+                if let Some(sprite) = state.sprites.get(&entity) {
                     if let Some(asset) = assets.get_by_name(&sprite.asset_name) {
                         match &asset.shape {
                             PrimitiveShape::Rectangle { width, height } => {
@@ -215,6 +226,11 @@ impl EntityManager {
                 drop(entity_span);
             }
 
+            let orphaned = state.orphaned_components();
+            if !orphaned.is_empty() {
+                warn!(?orphaned, "Components outlived their entities");
+            }
+
             debug!("Entity debug dump complete");
             Ok(())
         } else {
@@ -1,3 +1,4 @@
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::gravity::Gravity;
 use crate::engine::managers::Manager;
 use crate::GameState;
@@ -24,15 +25,13 @@ impl Manager for GravityManager {
 impl GravityManager {
     pub fn add_downward_gravity(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         force: f32,
         terminal_velocity: f32,
     ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
-                if !state.entities.contains(&entity_id) {
-                    return Err("Entity does not exist");
-                }
+                state.check_alive(entity_id)?;
 
                 let gravity = Gravity::downward(force, terminal_velocity);
                 state.gravities.insert(entity_id, gravity);
@@ -44,15 +43,13 @@ impl GravityManager {
 
     pub fn add_attractive_gravity(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         force: f32,
         terminal_velocity: f32,
     ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
-                if !state.entities.contains(&entity_id) {
-                    return Err("Entity does not exist");
-                }
+                state.check_alive(entity_id)?;
 
                 let gravity = Gravity::attractive(force, terminal_velocity);
                 state.gravities.insert(entity_id, gravity);
@@ -64,15 +61,13 @@ impl GravityManager {
 
     pub fn add_repulsive_gravity(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         force: f32,
         terminal_velocity: f32,
     ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
-                if !state.entities.contains(&entity_id) {
-                    return Err("Entity does not exist");
-                }
+                state.check_alive(entity_id)?;
 
                 let gravity = Gravity::repulsive(force, terminal_velocity);
                 state.gravities.insert(entity_id, gravity);
@@ -82,9 +77,14 @@ impl GravityManager {
         }
     }
 
-    pub fn set_gravity_enabled(&self, entity_id: u32, enabled: bool) -> Result<(), &'static str> {
+    pub fn set_gravity_enabled(
+        &self,
+        entity_id: Entity,
+        enabled: bool,
+    ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 if let Some(gravity) = state.gravities.get_mut(&entity_id) {
                     gravity.enabled = enabled;
                     Ok(())
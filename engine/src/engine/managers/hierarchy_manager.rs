@@ -0,0 +1,90 @@
+use super::Manager;
+use crate::ecs::components::entity::Entity;
+use crate::ecs::components::gamestate::GameState;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::debug;
+
+#[derive(Debug)]
+pub struct HierarchyManager {
+    state: Rc<RefCell<GameState>>,
+}
+
+impl Manager for HierarchyManager {
+    fn new(state: Rc<RefCell<GameState>>) -> Self {
+        debug!("Creating new HierarchyManager");
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Rc<RefCell<GameState>> {
+        &self.state
+    }
+}
+
+impl HierarchyManager {
+    pub fn attach(&self, child_id: Entity, parent_id: Entity) -> Result<(), &'static str> {
+        match self.state.try_borrow_mut() {
+            Ok(mut state) => {
+                state.check_alive(child_id)?;
+                state.check_alive(parent_id)?;
+                debug!(child_id, parent_id, "Attaching entity");
+                state.attach(child_id, parent_id)
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn detach(&self, child_id: Entity) -> Result<bool, &'static str> {
+        match self.state.try_borrow_mut() {
+            Ok(mut state) => {
+                state.check_alive(child_id)?;
+                debug!(child_id, "Detaching entity");
+                Ok(state.detach(child_id))
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn get_parent(&self, entity_id: Entity) -> Result<Option<Entity>, &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => {
+                state.check_alive(entity_id)?;
+                Ok(state.parent_of(entity_id))
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn get_children(&self, entity_id: Entity) -> Result<Vec<Entity>, &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => {
+                state.check_alive(entity_id)?;
+                Ok(state.children_of(entity_id))
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn get_descendants(&self, entity_id: Entity) -> Result<Vec<Entity>, &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => {
+                state.check_alive(entity_id)?;
+                Ok(state.descendants_of(entity_id))
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn get_world_transform(&self, entity_id: Entity) -> Result<(f32, f32, f32), &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => {
+                state.check_alive(entity_id)?;
+                let transform = state
+                    .world_transform(entity_id)
+                    .ok_or("Entity does not have a transform component")?;
+                Ok((transform.x, transform.y, transform.rotation))
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+}
@@ -6,8 +6,12 @@ pub mod collision_manager;
 pub mod drag_drop_manager;
 pub mod entity_manager;
 pub mod gravity_manager;
+pub mod hierarchy_manager;
 pub mod input_manager;
+pub mod prefab_manager;
+pub mod scene_manager;
 pub mod state_manager;
+pub mod tag_manager;
 pub mod text_manager;
 pub mod tilemap_manager;
 pub mod timer_manager;
@@ -0,0 +1,47 @@
+use super::Manager;
+use crate::ecs::components::gamestate::GameState;
+use crate::ecs::components::prefab::Prefab;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::debug;
+
+#[derive(Debug)]
+pub struct PrefabManager {
+    state: Rc<RefCell<GameState>>,
+}
+
+impl Manager for PrefabManager {
+    fn new(state: Rc<RefCell<GameState>>) -> Self {
+        debug!("Creating new PrefabManager");
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Rc<RefCell<GameState>> {
+        &self.state
+    }
+}
+
+impl PrefabManager {
+    /// Defining a name again replaces the earlier prefab
+    pub fn define_prefab(&self, name: String, prefab: Prefab) -> Result<(), &'static str> {
+        match self.state.try_borrow_mut() {
+            Ok(mut state) => {
+                debug!(name, "Defining prefab");
+                state.prefabs.insert(name, prefab);
+                Ok(())
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn get_prefab(&self, name: &str) -> Result<Prefab, String> {
+        match self.state.try_borrow() {
+            Ok(state) => state
+                .prefabs
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("No prefab named '{}'", name)),
+            Err(_) => Err("Failed to borrow game state".to_string()),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use super::Manager;
+use crate::ecs::components::entity::Entity;
+use crate::ecs::components::gamestate::GameState;
+use crate::ecs::components::scene::{Scene, SceneInstance};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::debug;
+
+#[derive(Debug)]
+pub struct SceneManager {
+    state: Rc<RefCell<GameState>>,
+}
+
+impl Manager for SceneManager {
+    fn new(state: Rc<RefCell<GameState>>) -> Self {
+        debug!("Creating new SceneManager");
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Rc<RefCell<GameState>> {
+        &self.state
+    }
+}
+
+impl SceneManager {
+    /// Defining a name again replaces the earlier scene for later pushes
+    pub fn define_scene(&self, name: String, scene: Scene) -> Result<(), &'static str> {
+        match self.state.try_borrow_mut() {
+            Ok(mut state) => {
+                debug!(name, "Defining scene");
+                state.scenes.insert(name, Rc::new(scene));
+                Ok(())
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn has_scene(&self, name: &str) -> Result<bool, &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => Ok(state.scenes.contains_key(name)),
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    /// Push a new instance of a scene and return its callbacks; calling its
+    /// `on_enter` is up to the caller
+    pub fn push_scene(&self, name: &str) -> Result<Rc<Scene>, String> {
+        match self.state.try_borrow_mut() {
+            Ok(mut state) => {
+                let instance = state
+                    .push_scene(name)
+                    .ok_or_else(|| format!("No scene named '{}'", name))?;
+                debug!(name, instance = instance.0, "Pushed scene");
+                let top = state.scene_stack.last().expect("scene was just pushed");
+                Ok(Rc::clone(&top.scene))
+            }
+            Err(_) => Err("Failed to borrow game state".to_string()),
+        }
+    }
+
+    /// The instance on top of the stack and the scene it was pushed with
+    pub fn top_scene(&self) -> Result<Option<(SceneInstance, Rc<Scene>)>, &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => Ok(state
+                .scene_stack
+                .last()
+                .map(|top| (top.instance, Rc::clone(&top.scene)))),
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    /// Take an instance off the stack and return the entities it owned, for the
+    /// caller to destroy
+    pub fn remove_scene(&self, instance: SceneInstance) -> Result<Vec<Entity>, &'static str> {
+        match self.state.try_borrow_mut() {
+            Ok(mut state) => {
+                debug!(instance = instance.0, "Removing scene");
+                Ok(state.remove_scene(instance))
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    /// Names of the scenes on the stack, bottom first
+    pub fn get_scene_stack(&self) -> Result<Vec<String>, &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => Ok(state
+                .scene_stack
+                .iter()
+                .map(|scene| scene.name.clone())
+                .collect()),
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+}
@@ -1,17 +1,23 @@
 use crate::assets::asset_manager::{AssetManager, BuiltInAsset, PrimitiveShape};
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::gamestate::GameState;
-use crate::ecs::components::sprite::SpriteShapeData;
+use crate::ecs::components::gravity::GravityType;
+use crate::ecs::components::prefab::Prefab;
+use crate::ecs::components::scene::{Scene, SceneInstance};
+use crate::ecs::components::sprite::{ShapeParams, SpriteShapeData};
 use crate::ecs::components::text::{HorizontalAlign, Text, TextId, VerticalAlign};
 use crate::ecs::components::tilemap::{Tilemap, TilemapQuery, TilemapQueryResult};
 use crate::ecs::components::timer::TimerId;
 use crate::ecs::systems::input_system::InputSystem;
 use crate::engine::managers::{
     collision_manager::CollisionManager, drag_drop_manager::DragDropManager,
-    entity_manager::EntityManager, gravity_manager::GravityManager, input_manager::InputManager,
+    entity_manager::EntityManager, gravity_manager::GravityManager,
+    hierarchy_manager::HierarchyManager, input_manager::InputManager,
+    prefab_manager::PrefabManager, scene_manager::SceneManager, tag_manager::TagManager,
     text_manager::TextManager, tilemap_manager::TilemapManager, timer_manager::TimerManager,
     transform_manager::TransformManager, velocity_manager::VelocityManager, Manager,
 };
-use crate::{Renderer, Sdl2Renderer};
+use crate::Renderer;
 use mlua::Function;
 use sdl2::keyboard::Keycode;
 use sdl2::mouse::MouseButton;
@@ -34,6 +40,10 @@ pub struct StateManager {
     input_manager: InputManager,
     text_manager: TextManager,
     timer_manager: TimerManager,
+    tag_manager: TagManager,
+    hierarchy_manager: HierarchyManager,
+    prefab_manager: PrefabManager,
+    scene_manager: SceneManager,
 }
 
 impl StateManager {
@@ -51,28 +61,36 @@ impl StateManager {
             input_manager: InputManager::new_with_input_system(Rc::clone(&state), input_system),
             text_manager: TextManager::new(Rc::clone(&state)),
             timer_manager: TimerManager::new(Rc::clone(&state)),
+            tag_manager: TagManager::new(Rc::clone(&state)),
+            hierarchy_manager: HierarchyManager::new(Rc::clone(&state)),
+            prefab_manager: PrefabManager::new(Rc::clone(&state)),
+            scene_manager: SceneManager::new(Rc::clone(&state)),
         }
     }
 
     // ------------------------------------------------------------
     // Entity Management
     // ------------------------------------------------------------
-    pub fn create_entity(&self) -> Result<u32, &'static str> {
+    pub fn create_entity(&self) -> Result<Entity, &'static str> {
         self.entity_manager.create_entity()
     }
 
-    pub fn destroy_entity(&self, entity_id: u32) -> Result<(), &'static str> {
+    pub fn is_entity_alive(&self, entity_id: Entity) -> Result<bool, &'static str> {
+        self.entity_manager.is_alive(entity_id)
+    }
+
+    pub fn destroy_entity(&self, entity_id: Entity) -> Result<(), &'static str> {
         self.entity_manager.destroy_entity(entity_id)
     }
 
     pub fn add_sprite(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         asset_name: &str,
         r: u8,
         g: u8,
         b: u8,
-        params: Option<mlua::Table>,
+        params: Option<&ShapeParams>,
     ) -> Result<(), &'static str> {
         self.entity_manager
             .add_sprite(entity_id, asset_name, (r, g, b), params, &self.assets)
@@ -83,7 +101,7 @@ impl StateManager {
     // ------------------------------------------------------------
     pub fn set_transform(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         x: f32,
         y: f32,
         rotation: f32,
@@ -92,33 +110,37 @@ impl StateManager {
             .set_transform(entity_id, x, y, rotation)
     }
 
-    pub fn get_transform(&self, entity_id: u32) -> Result<(f32, f32, f32), &'static str> {
+    pub fn get_transform(&self, entity_id: Entity) -> Result<(f32, f32, f32), &'static str> {
         self.transform_manager.get_transform(entity_id)
     }
 
     // ------------------------------------------------------------
     // Velocity Management
     // ------------------------------------------------------------
-    pub fn set_velocity(&self, entity_id: u32, vx: f32, vy: f32) -> Result<(), &'static str> {
+    pub fn set_velocity(&self, entity_id: Entity, vx: f32, vy: f32) -> Result<(), &'static str> {
         self.velocity_manager.set_velocity(entity_id, vx, vy)
     }
 
-    pub fn get_velocity(&self, entity_id: u32) -> Result<(f32, f32), &'static str> {
+    pub fn get_velocity(&self, entity_id: Entity) -> Result<(f32, f32), &'static str> {
         self.velocity_manager.get_velocity(entity_id)
     }
 
-    pub fn set_zero_velocity(&self, entity_id: u32) -> Result<(), &'static str> {
+    pub fn set_zero_velocity(&self, entity_id: Entity) -> Result<(), &'static str> {
         self.velocity_manager.set_zero_velocity(entity_id)
     }
 
-    pub fn set_horizontal_velocity(&self, entity_id: u32, speed: f32) -> Result<(), &'static str> {
+    pub fn set_horizontal_velocity(
+        &self,
+        entity_id: Entity,
+        speed: f32,
+    ) -> Result<(), &'static str> {
         self.velocity_manager
             .set_horizontal_velocity(entity_id, speed)
     }
 
     pub fn set_rotation_velocity(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         dx: f32,
         dy: f32,
         angular: f32,
@@ -127,7 +149,11 @@ impl StateManager {
             .set_rotation_velocity(entity_id, dx, dy, angular)
     }
 
-    pub fn set_angular_velocity(&self, entity_id: u32, angular: f32) -> Result<(), &'static str> {
+    pub fn set_angular_velocity(
+        &self,
+        entity_id: Entity,
+        angular: f32,
+    ) -> Result<(), &'static str> {
         self.velocity_manager
             .set_angular_velocity(entity_id, angular)
     }
@@ -137,7 +163,7 @@ impl StateManager {
     // ------------------------------------------------------------
     pub fn add_downward_gravity(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         force: f32,
         terminal_velocity: f32,
     ) -> Result<(), &'static str> {
@@ -147,7 +173,7 @@ impl StateManager {
 
     pub fn add_attractive_gravity(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         force: f32,
         terminal_velocity: f32,
     ) -> Result<(), &'static str> {
@@ -157,7 +183,7 @@ impl StateManager {
 
     pub fn add_repulsive_gravity(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         force: f32,
         terminal_velocity: f32,
     ) -> Result<(), &'static str> {
@@ -165,22 +191,26 @@ impl StateManager {
             .add_repulsive_gravity(entity_id, force, terminal_velocity)
     }
 
-    pub fn set_gravity_enabled(&self, entity_id: u32, enabled: bool) -> Result<(), &'static str> {
+    pub fn set_gravity_enabled(
+        &self,
+        entity_id: Entity,
+        enabled: bool,
+    ) -> Result<(), &'static str> {
         self.gravity_manager.set_gravity_enabled(entity_id, enabled)
     }
 
     // ------------------------------------------------------------
     // Collision Management
     // ------------------------------------------------------------
-    pub fn check_collision(&self, entity1: u32, entity2: u32) -> Result<bool, &'static str> {
+    pub fn check_collision(&self, entity1: Entity, entity2: Entity) -> Result<bool, &'static str> {
         self.collision_manager
             .check_collision(entity1, entity2, &self.assets)
     }
 
     pub fn check_entity_tilemap_collision(
         &self,
-        entity_id: u32,
-        tilemap_id: u32,
+        entity_id: Entity,
+        tilemap_id: Entity,
         x: f32,
         y: f32,
     ) -> Result<bool, &'static str> {
@@ -198,7 +228,7 @@ impl StateManager {
     // ------------------------------------------------------------
     pub fn create_tilemap(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         width: u32,
         height: u32,
         tile_size: u32,
@@ -209,7 +239,7 @@ impl StateManager {
 
     pub fn set_tile(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         x: u32,
         y: u32,
         tile_id: u32,
@@ -220,26 +250,31 @@ impl StateManager {
             .set_tile(entity_id, x, y, tile_id, walkable, color)
     }
 
-    pub fn clear_tile(&self, entity_id: u32, x: u32, y: u32) -> Result<bool, &'static str> {
+    pub fn clear_tile(&self, entity_id: Entity, x: u32, y: u32) -> Result<bool, &'static str> {
         self.tilemap_manager.clear_tile(entity_id, x, y)
     }
 
     pub fn query_tilemap(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         query: TilemapQuery,
     ) -> Result<TilemapQueryResult, &'static str> {
         self.tilemap_manager.query_tilemap(entity_id, query)
     }
 
-    pub fn is_tile_walkable(&self, entity_id: u32, x: u32, y: u32) -> Result<bool, &'static str> {
+    pub fn is_tile_walkable(
+        &self,
+        entity_id: Entity,
+        x: u32,
+        y: u32,
+    ) -> Result<bool, &'static str> {
         self.tilemap_manager.is_tile_walkable(entity_id, x, y)
     }
 
     pub fn check_position_walkable(
         &self,
-        entity_id: u32,
-        tilemap_id: u32,
+        entity_id: Entity,
+        tilemap_id: Entity,
         x: f32,
         y: f32,
     ) -> Result<bool, &'static str> {
@@ -253,28 +288,28 @@ impl StateManager {
         )
     }
 
-    pub fn get_tilemap(&self, entity_id: u32) -> Result<Option<Tilemap>, &'static str> {
+    pub fn get_tilemap(&self, entity_id: Entity) -> Result<Option<Tilemap>, &'static str> {
         self.tilemap_manager.get_tilemap(entity_id)
     }
 
-    pub fn get_tile_size(&self, entity_id: u32) -> Result<u32, &'static str> {
+    pub fn get_tile_size(&self, entity_id: Entity) -> Result<u32, &'static str> {
         self.tilemap_manager.get_tile_size(entity_id)
     }
 
-    pub fn get_dimensions(&self, entity_id: u32) -> Result<(u32, u32), &'static str> {
+    pub fn get_dimensions(&self, entity_id: Entity) -> Result<(u32, u32), &'static str> {
         self.tilemap_manager.get_dimensions(entity_id)
     }
 
     // ------------------------------------------------------------
     // Drag and Drop Management
     // ------------------------------------------------------------
-    pub fn get_entity_at_point(&self, x: f32, y: f32) -> Result<Option<u32>, &'static str> {
+    pub fn get_entity_at_point(&self, x: f32, y: f32) -> Result<Option<Entity>, &'static str> {
         self.drag_drop_manager.get_entity_at_point(x, y)
     }
 
     pub fn start_dragging(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         mouse_x: f32,
         mouse_y: f32,
     ) -> Result<(), &'static str> {
@@ -291,10 +326,14 @@ impl StateManager {
         self.drag_drop_manager.end_dragging()
     }
 
-    pub fn is_entity_dragged(&self, entity_id: u32) -> bool {
+    pub fn is_entity_dragged(&self, entity_id: Entity) -> bool {
         self.drag_drop_manager.is_entity_dragged(entity_id)
     }
 
+    pub fn make_draggable(&self, entity_id: Entity) -> Result<(), &'static str> {
+        self.drag_drop_manager.make_draggable(entity_id)
+    }
+
     // ------------------------------------------------------------
     // Input Management
     // ------------------------------------------------------------
@@ -322,7 +361,7 @@ impl StateManager {
     // ------------------------------------------------------------
     pub fn add_text(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         text_id: TextId,
         color: Option<(u8, u8, u8)>,
         scale: Option<f32>,
@@ -333,25 +372,29 @@ impl StateManager {
             .add_text(entity_id, text_id, color, scale, h_align, v_align)
     }
 
-    pub fn update_text(&self, entity_id: u32, text_id: TextId) -> Result<(), &'static str> {
+    pub fn update_text(&self, entity_id: Entity, text_id: TextId) -> Result<(), &'static str> {
         self.text_manager.update_text(entity_id, text_id)
     }
 
-    pub fn set_text_color(&self, entity_id: u32, color: (u8, u8, u8)) -> Result<(), &'static str> {
+    pub fn set_text_color(
+        &self,
+        entity_id: Entity,
+        color: (u8, u8, u8),
+    ) -> Result<(), &'static str> {
         self.text_manager.set_text_color(entity_id, color)
     }
 
-    pub fn set_text_scale(&self, entity_id: u32, scale: f32) -> Result<(), &'static str> {
+    pub fn set_text_scale(&self, entity_id: Entity, scale: f32) -> Result<(), &'static str> {
         self.text_manager.set_text_scale(entity_id, scale)
     }
 
-    pub fn set_text_value(&self, entity_id: u32, value: String) -> Result<(), &'static str> {
+    pub fn set_text_value(&self, entity_id: Entity, value: String) -> Result<(), &'static str> {
         self.text_manager.set_text_value(entity_id, value)
     }
 
     pub fn set_text_alignment(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         h_align: HorizontalAlign,
         v_align: VerticalAlign,
     ) -> Result<(), &'static str> {
@@ -359,18 +402,177 @@ impl StateManager {
             .set_text_alignment(entity_id, h_align, v_align)
     }
 
-    pub fn set_text_visibility(&self, entity_id: u32, visible: bool) -> Result<(), &'static str> {
+    pub fn set_text_visibility(
+        &self,
+        entity_id: Entity,
+        visible: bool,
+    ) -> Result<(), &'static str> {
         self.text_manager.set_text_visibility(entity_id, visible)
     }
 
-    pub fn remove_text(&self, entity_id: u32) -> Result<(), &'static str> {
+    pub fn remove_text(&self, entity_id: Entity) -> Result<(), &'static str> {
         self.text_manager.remove_text(entity_id)
     }
 
-    pub fn get_text(&self, entity_id: u32) -> Result<Option<Text>, &'static str> {
+    pub fn get_text(&self, entity_id: Entity) -> Result<Option<Text>, &'static str> {
         self.text_manager.get_text(entity_id)
     }
 
+    // -----------------
+    // Tags and queries
+    // -----------------
+
+    pub fn add_tag(&self, entity_id: Entity, tag: String) -> Result<(), &'static str> {
+        self.tag_manager.add_tag(entity_id, tag)
+    }
+
+    pub fn remove_tag(&self, entity_id: Entity, tag: &str) -> Result<bool, &'static str> {
+        self.tag_manager.remove_tag(entity_id, tag)
+    }
+
+    pub fn has_tag(&self, entity_id: Entity, tag: &str) -> Result<bool, &'static str> {
+        self.tag_manager.has_tag(entity_id, tag)
+    }
+
+    pub fn get_tags(&self, entity_id: Entity) -> Result<Vec<String>, &'static str> {
+        self.tag_manager.get_tags(entity_id)
+    }
+
+    pub fn query(&self, components: &[String], tags: &[String]) -> Result<Vec<Entity>, String> {
+        self.tag_manager.query(components, tags)
+    }
+
+    // -----------------
+    // Hierarchy
+    // -----------------
+
+    pub fn attach(&self, child_id: Entity, parent_id: Entity) -> Result<(), &'static str> {
+        self.hierarchy_manager.attach(child_id, parent_id)
+    }
+
+    pub fn detach(&self, child_id: Entity) -> Result<bool, &'static str> {
+        self.hierarchy_manager.detach(child_id)
+    }
+
+    pub fn get_parent(&self, entity_id: Entity) -> Result<Option<Entity>, &'static str> {
+        self.hierarchy_manager.get_parent(entity_id)
+    }
+
+    pub fn get_children(&self, entity_id: Entity) -> Result<Vec<Entity>, &'static str> {
+        self.hierarchy_manager.get_children(entity_id)
+    }
+
+    pub fn get_descendants(&self, entity_id: Entity) -> Result<Vec<Entity>, &'static str> {
+        self.hierarchy_manager.get_descendants(entity_id)
+    }
+
+    pub fn get_world_transform(&self, entity_id: Entity) -> Result<(f32, f32, f32), &'static str> {
+        self.hierarchy_manager.get_world_transform(entity_id)
+    }
+
+    // -----------------
+    // Prefabs
+    // -----------------
+
+    pub fn define_prefab(&self, name: String, prefab: Prefab) -> Result<(), &'static str> {
+        self.prefab_manager.define_prefab(name, prefab)
+    }
+
+    /// Create an entity from a prefab with `overrides` merged in. An entity that
+    /// fails partway is destroyed again, so scripts never see half a prefab.
+    pub fn spawn(&self, name: &str, overrides: &Prefab) -> Result<Entity, String> {
+        let prefab = self.prefab_manager.get_prefab(name)?.merged(overrides);
+        let entity_id = self.create_entity()?;
+        if let Err(e) = self.build_prefab(entity_id, &prefab) {
+            self.destroy_entity(entity_id)?;
+            return Err(format!("Could not spawn '{}': {}", name, e));
+        }
+        Ok(entity_id)
+    }
+
+    fn build_prefab(&self, entity_id: Entity, prefab: &Prefab) -> Result<(), &'static str> {
+        if let Some(transform) = &prefab.transform {
+            self.set_transform(
+                entity_id,
+                transform.x.unwrap_or(0.0),
+                transform.y.unwrap_or(0.0),
+                transform.rotation.unwrap_or(0.0),
+            )?;
+        }
+        if let Some(shape) = &prefab.shape {
+            let name = shape.name.as_deref().ok_or("shape needs a name")?;
+            let (r, g, b) = shape.color.unwrap_or((255, 255, 255));
+            let params = (!shape.params.is_empty()).then_some(&shape.params);
+            self.add_sprite(entity_id, name, r, g, b, params)?;
+        }
+        if let Some(velocity) = &prefab.velocity {
+            self.set_velocity(
+                entity_id,
+                velocity.dx.unwrap_or(0.0),
+                velocity.dy.unwrap_or(0.0),
+            )?;
+            if let Some(angular) = velocity.angular {
+                self.set_angular_velocity(entity_id, angular)?;
+            }
+        }
+        if let Some(gravity) = &prefab.gravity {
+            let force = gravity.force.ok_or("gravity needs a force")?;
+            let terminal_velocity = gravity
+                .terminal_velocity
+                .ok_or("gravity needs a terminal_velocity")?;
+            match gravity
+                .gravity_type
+                .as_ref()
+                .unwrap_or(&GravityType::Downward)
+            {
+                GravityType::Downward => {
+                    self.add_downward_gravity(entity_id, force, terminal_velocity)?
+                }
+                GravityType::Attractive => {
+                    self.add_attractive_gravity(entity_id, force, terminal_velocity)?
+                }
+                GravityType::Repulsive => {
+                    self.add_repulsive_gravity(entity_id, force, terminal_velocity)?
+                }
+            }
+        }
+        if prefab.draggable == Some(true) {
+            self.make_draggable(entity_id)?;
+        }
+        for tag in prefab.tags.iter().flatten() {
+            self.add_tag(entity_id, tag.clone())?;
+        }
+        Ok(())
+    }
+
+    // -----------------
+    // Scenes
+    // -----------------
+
+    pub fn define_scene(&self, name: String, scene: Scene) -> Result<(), &'static str> {
+        self.scene_manager.define_scene(name, scene)
+    }
+
+    pub fn has_scene(&self, name: &str) -> Result<bool, &'static str> {
+        self.scene_manager.has_scene(name)
+    }
+
+    pub fn push_scene(&self, name: &str) -> Result<Rc<Scene>, String> {
+        self.scene_manager.push_scene(name)
+    }
+
+    pub fn top_scene(&self) -> Result<Option<(SceneInstance, Rc<Scene>)>, &'static str> {
+        self.scene_manager.top_scene()
+    }
+
+    pub fn remove_scene(&self, instance: SceneInstance) -> Result<Vec<Entity>, &'static str> {
+        self.scene_manager.remove_scene(instance)
+    }
+
+    pub fn get_scene_stack(&self) -> Result<Vec<String>, &'static str> {
+        self.scene_manager.get_scene_stack()
+    }
+
     // -----------------
     // Timer
     // -----------------
@@ -403,7 +605,7 @@ impl StateManager {
         x: i32,
         y: i32,
         color: (u8, u8, u8),
-        renderer: &mut Sdl2Renderer,
+        renderer: &mut impl Renderer,
         scale: f32,
         debug: bool,
     ) {
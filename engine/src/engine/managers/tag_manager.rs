@@ -0,0 +1,100 @@
+use super::Manager;
+use crate::ecs::components::entity::Entity;
+use crate::ecs::components::gamestate::GameState;
+use crate::ecs::components::tag::Tags;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::debug;
+
+#[derive(Debug)]
+pub struct TagManager {
+    state: Rc<RefCell<GameState>>,
+}
+
+impl Manager for TagManager {
+    fn new(state: Rc<RefCell<GameState>>) -> Self {
+        debug!("Creating new TagManager");
+        Self { state }
+    }
+
+    fn get_state(&self) -> &Rc<RefCell<GameState>> {
+        &self.state
+    }
+}
+
+impl TagManager {
+    pub fn add_tag(&self, entity_id: Entity, tag: String) -> Result<(), &'static str> {
+        match self.state.try_borrow_mut() {
+            Ok(mut state) => {
+                state.check_alive(entity_id)?;
+                debug!(entity_id, tag, "Adding tag");
+                match state.tags.get_mut(&entity_id) {
+                    Some(tags) => {
+                        tags.names.insert(tag);
+                    }
+                    None => {
+                        let mut tags = Tags::new();
+                        tags.names.insert(tag);
+                        state.tags.insert(entity_id, tags);
+                    }
+                }
+                Ok(())
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    /// Whether the entity had the tag
+    pub fn remove_tag(&self, entity_id: Entity, tag: &str) -> Result<bool, &'static str> {
+        match self.state.try_borrow_mut() {
+            Ok(mut state) => {
+                state.check_alive(entity_id)?;
+                let Some(tags) = state.tags.get_mut(&entity_id) else {
+                    return Ok(false);
+                };
+                let removed = tags.names.remove(tag);
+                // Untagged entities keep no component, so tag queries skip them
+                if tags.names.is_empty() {
+                    state.tags.remove(&entity_id);
+                }
+                Ok(removed)
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn has_tag(&self, entity_id: Entity, tag: &str) -> Result<bool, &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => {
+                state.check_alive(entity_id)?;
+                Ok(state
+                    .tags
+                    .get(&entity_id)
+                    .is_some_and(|tags| tags.contains(tag)))
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    /// The entity's tags in alphabetical order
+    pub fn get_tags(&self, entity_id: Entity) -> Result<Vec<String>, &'static str> {
+        match self.state.try_borrow() {
+            Ok(state) => {
+                state.check_alive(entity_id)?;
+                Ok(state
+                    .tags
+                    .get(&entity_id)
+                    .map(|tags| tags.names.iter().cloned().collect())
+                    .unwrap_or_default())
+            }
+            Err(_) => Err("Failed to borrow game state"),
+        }
+    }
+
+    pub fn query(&self, components: &[String], tags: &[String]) -> Result<Vec<Entity>, String> {
+        match self.state.try_borrow() {
+            Ok(state) => state.query(components, tags),
+            Err(_) => Err("Failed to borrow game state".to_string()),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use super::Manager;
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::gamestate::GameState;
 use crate::ecs::components::text::{HorizontalAlign, Text, TextId, VerticalAlign};
 use std::cell::RefCell;
@@ -24,7 +25,7 @@ impl Manager for TextManager {
 impl TextManager {
     pub fn add_text(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         text_id: TextId,
         color: Option<(u8, u8, u8)>,
         scale: Option<f32>,
@@ -33,10 +34,7 @@ impl TextManager {
     ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
-                if !state.entities.contains(&entity_id) {
-                    error!(entity_id, "Attempted to add text to non-existent entity");
-                    return Err("Entity does not exist");
-                }
+                state.check_alive(entity_id)?;
 
                 let mut text = Text::new(text_id);
                 debug!(entity_id, ?text_id, "Creating new text component");
@@ -78,9 +76,10 @@ impl TextManager {
         }
     }
 
-    pub fn update_text(&self, entity_id: u32, text_id: TextId) -> Result<(), &'static str> {
+    pub fn update_text(&self, entity_id: Entity, text_id: TextId) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 if let Some(text) = state.texts.get_mut(&entity_id) {
                     debug!(entity_id, ?text_id, "Updating text content");
                     text.text_id = text_id;
@@ -103,9 +102,14 @@ impl TextManager {
         }
     }
 
-    pub fn set_text_color(&self, entity_id: u32, color: (u8, u8, u8)) -> Result<(), &'static str> {
+    pub fn set_text_color(
+        &self,
+        entity_id: Entity,
+        color: (u8, u8, u8),
+    ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 if let Some(text) = state.texts.get_mut(&entity_id) {
                     debug!(
                         entity_id,
@@ -134,9 +138,10 @@ impl TextManager {
         }
     }
 
-    pub fn set_text_scale(&self, entity_id: u32, scale: f32) -> Result<(), &'static str> {
+    pub fn set_text_scale(&self, entity_id: Entity, scale: f32) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 if let Some(text) = state.texts.get_mut(&entity_id) {
                     debug!(entity_id, scale, "Setting text scale");
                     text.scale = scale;
@@ -161,12 +166,13 @@ impl TextManager {
 
     pub fn set_text_alignment(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         h_align: HorizontalAlign,
         v_align: VerticalAlign,
     ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 if let Some(text) = state.texts.get_mut(&entity_id) {
                     debug!(entity_id, ?h_align, ?v_align, "Setting text alignment");
                     text.alignment.h_align = h_align;
@@ -190,9 +196,14 @@ impl TextManager {
         }
     }
 
-    pub fn set_text_visibility(&self, entity_id: u32, visible: bool) -> Result<(), &'static str> {
+    pub fn set_text_visibility(
+        &self,
+        entity_id: Entity,
+        visible: bool,
+    ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 if let Some(text) = state.texts.get_mut(&entity_id) {
                     debug!(entity_id, visible, "Setting text visibility");
                     text.set_visibility(visible);
@@ -215,9 +226,10 @@ impl TextManager {
         }
     }
 
-    pub fn remove_text(&self, entity_id: u32) -> Result<(), &'static str> {
+    pub fn remove_text(&self, entity_id: Entity) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 debug!(entity_id, "Removing text component");
                 state.texts.remove(&entity_id);
                 Ok(())
@@ -232,9 +244,10 @@ impl TextManager {
         }
     }
 
-    pub fn get_text(&self, entity_id: u32) -> Result<Option<Text>, &'static str> {
+    pub fn get_text(&self, entity_id: Entity) -> Result<Option<Text>, &'static str> {
         match self.state.try_borrow() {
             Ok(state) => {
+                state.check_alive(entity_id)?;
                 let text = state.texts.get(&entity_id).cloned();
                 debug!(
                     entity_id,
@@ -253,9 +266,10 @@ impl TextManager {
         }
     }
 
-    pub fn set_text_value(&self, entity_id: u32, value: String) -> Result<(), &'static str> {
+    pub fn set_text_value(&self, entity_id: Entity, value: String) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 if let Some(text) = state.texts.get_mut(&entity_id) {
                     text.set_text_value(value);
                     Ok(())
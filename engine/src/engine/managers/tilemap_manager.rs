@@ -1,4 +1,5 @@
 use super::Manager;
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::gamestate::GameState;
 use crate::ecs::components::tilemap::{Tile, Tilemap, TilemapQuery, TilemapQueryResult};
 use std::cell::RefCell;
@@ -24,20 +25,14 @@ impl Manager for TilemapManager {
 impl TilemapManager {
     pub fn create_tilemap(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         width: u32,
         height: u32,
         tile_size: u32,
     ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
-                if !state.entities.contains(&entity_id) {
-                    error!(
-                        entity_id,
-                        "Attempted to create tilemap for non-existent entity"
-                    );
-                    return Err("Entity does not exist");
-                }
+                state.check_alive(entity_id)?;
 
                 debug!(entity_id, width, height, tile_size, "Creating new tilemap");
                 let tilemap = Tilemap::new(width, height, tile_size);
@@ -54,9 +49,10 @@ impl TilemapManager {
         }
     }
 
-    pub fn get_tilemap(&self, entity_id: u32) -> Result<Option<Tilemap>, &'static str> {
+    pub fn get_tilemap(&self, entity_id: Entity) -> Result<Option<Tilemap>, &'static str> {
         match self.state.try_borrow() {
             Ok(state) => {
+                state.check_alive(entity_id)?;
                 let has_tilemap = state.tilemaps.contains_key(&entity_id);
                 debug!(entity_id, has_tilemap, "Retrieved tilemap");
                 Ok(state.tilemaps.get(&entity_id).cloned())
@@ -73,7 +69,7 @@ impl TilemapManager {
 
     pub fn set_tile(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         x: u32,
         y: u32,
         tile_id: u32,
@@ -82,6 +78,7 @@ impl TilemapManager {
     ) -> Result<(), &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 let tilemap = match state.tilemaps.get_mut(&entity_id) {
                     Some(tm) => tm,
                     None => {
@@ -120,9 +117,10 @@ impl TilemapManager {
         }
     }
 
-    pub fn clear_tile(&self, entity_id: u32, x: u32, y: u32) -> Result<bool, &'static str> {
+    pub fn clear_tile(&self, entity_id: Entity, x: u32, y: u32) -> Result<bool, &'static str> {
         match self.state.try_borrow_mut() {
             Ok(mut state) => {
+                state.check_alive(entity_id)?;
                 let tilemap = match state.tilemaps.get_mut(&entity_id) {
                     Some(tm) => tm,
                     None => {
@@ -149,11 +147,12 @@ impl TilemapManager {
 
     pub fn query_tilemap(
         &self,
-        entity_id: u32,
+        entity_id: Entity,
         query: TilemapQuery,
     ) -> Result<TilemapQueryResult, &'static str> {
         match self.state.try_borrow() {
             Ok(state) => {
+                state.check_alive(entity_id)?;
                 let tilemap = match state.tilemaps.get(&entity_id) {
                     Some(tm) => tm,
                     None => {
@@ -179,9 +178,15 @@ impl TilemapManager {
         }
     }
 
-    pub fn is_tile_walkable(&self, entity_id: u32, x: u32, y: u32) -> Result<bool, &'static str> {
+    pub fn is_tile_walkable(
+        &self,
+        entity_id: Entity,
+        x: u32,
+        y: u32,
+    ) -> Result<bool, &'static str> {
         match self.state.try_borrow() {
             Ok(state) => {
+                state.check_alive(entity_id)?;
                 let tilemap = match state.tilemaps.get(&entity_id) {
                     Some(tm) => tm,
                     None => {
@@ -209,8 +214,8 @@ impl TilemapManager {
 
     pub fn check_position_walkable(
         &self,
-        entity_id: u32,
-        tilemap_id: u32,
+        entity_id: Entity,
+        tilemap_id: Entity,
         x: f32,
         y: f32,
         collision_manager: &super::collision_manager::CollisionManager,
@@ -218,6 +223,8 @@ impl TilemapManager {
     ) -> Result<bool, &'static str> {
         match self.state.try_borrow() {
             Ok(state) => {
+                state.check_alive(entity_id)?;
+                state.check_alive(tilemap_id)?;
                 if !state.tilemaps.contains_key(&tilemap_id) {
                     warn!(
                         entity_id,
@@ -259,9 +266,10 @@ impl TilemapManager {
         }
     }
 
-    pub fn get_tile_size(&self, entity_id: u32) -> Result<u32, &'static str> {
+    pub fn get_tile_size(&self, entity_id: Entity) -> Result<u32, &'static str> {
         match self.state.try_borrow() {
             Ok(state) => {
+                state.check_alive(entity_id)?;
                 let tilemap = match state.tilemaps.get(&entity_id) {
                     Some(tm) => tm,
                     None => {
@@ -290,9 +298,10 @@ impl TilemapManager {
         }
     }
 
-    pub fn get_dimensions(&self, entity_id: u32) -> Result<(u32, u32), &'static str> {
+    pub fn get_dimensions(&self, entity_id: Entity) -> Result<(u32, u32), &'static str> {
         match self.state.try_borrow() {
             Ok(state) => {
+                state.check_alive(entity_id)?;
                 let tilemap = match state.tilemaps.get(&entity_id) {
                     Some(tm) => tm,
                     None => {
@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::rc::Rc;
+use crate::ecs::components::entity::Entity;
 use crate::engine::managers::Manager;
 use crate::GameState;
 use tracing::{debug, error, warn};
@@ -23,13 +24,14 @@ impl Manager for TransformManager {
 impl TransformManager {
    pub fn set_transform(
        &self,
-       entity_id: u32,
+       entity_id: Entity,
        x: f32,
        y: f32,
        rotation: f32,
    ) -> Result<(), &'static str> {
        match self.state.try_borrow_mut() {
            Ok(mut state) => {
+              state.check_alive(entity_id)?;
                debug!(
                    entity_id,
                    x,
@@ -38,7 +40,8 @@ impl TransformManager {
                    "Setting entity transform"
                );
                
-               state.add_transform(
+               // Relative to the entity's parent, if it has one
+               state.set_local_transform(
                    entity_id,
                    crate::ecs::components::transform::Transform::new(x, y, rotation),
                );
@@ -53,10 +56,11 @@ impl TransformManager {
        }
    }
 
-   pub fn get_transform(&self, entity_id: u32) -> Result<(f32, f32, f32), &'static str> {
+   pub fn get_transform(&self, entity_id: Entity) -> Result<(f32, f32, f32), &'static str> {
        match self.state.try_borrow() {
            Ok(state) => {
-               match state.transforms.get(&entity_id) {
+              state.check_alive(entity_id)?;
+               match state.local_transform(entity_id) {
                    Some(transform) => {
                        debug!(
                            entity_id,
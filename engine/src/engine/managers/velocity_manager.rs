@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::rc::Rc;
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::velocity::Velocity;
 use crate::engine::managers::Manager;
 use crate::GameState;
@@ -22,9 +23,10 @@ impl Manager for VelocityManager {
 }
 
 impl VelocityManager {
-   pub fn set_velocity(&self, entity_id: u32, vx: f32, vy: f32) -> Result<(), &'static str> {
+   pub fn set_velocity(&self, entity_id: Entity, vx: f32, vy: f32) -> Result<(), &'static str> {
        match self.state.try_borrow_mut() {
            Ok(mut state) => {
+              state.check_alive(entity_id)?;
                debug!(
                    entity_id,
                    vx,
@@ -41,9 +43,10 @@ impl VelocityManager {
        }
    }
 
-   pub fn get_velocity(&self, entity_id: u32) -> Result<(f32, f32), &'static str> {
+   pub fn get_velocity(&self, entity_id: Entity) -> Result<(f32, f32), &'static str> {
        match self.state.try_borrow() {
            Ok(state) => {
+              state.check_alive(entity_id)?;
                match state.velocities.get(&entity_id) {
                    Some(velocity) => {
                        debug!(
@@ -67,9 +70,10 @@ impl VelocityManager {
        }
    }
 
-   pub fn set_zero_velocity(&self, entity_id: u32) -> Result<(), &'static str> {
+   pub fn set_zero_velocity(&self, entity_id: Entity) -> Result<(), &'static str> {
        match self.state.try_borrow_mut() {
            Ok(mut state) => {
+              state.check_alive(entity_id)?;
                if let Some(velocity) = state.velocities.get_mut(&entity_id) {
                    debug!(entity_id, "Setting zero velocity");
                    *velocity = Velocity::zero();
@@ -86,9 +90,14 @@ impl VelocityManager {
        }
    }
 
-   pub fn set_horizontal_velocity(&self, entity_id: u32, speed: f32) -> Result<(), &'static str> {
+   pub fn set_horizontal_velocity(
+       &self,
+       entity_id: Entity,
+       speed: f32,
+   ) -> Result<(), &'static str> {
        match self.state.try_borrow_mut() {
            Ok(mut state) => {
+              state.check_alive(entity_id)?;
                if let Some(velocity) = state.velocities.get_mut(&entity_id) {
                    debug!(entity_id, speed, "Setting horizontal velocity");
                    *velocity = Velocity::horizontal(speed);
@@ -107,13 +116,14 @@ impl VelocityManager {
 
    pub fn set_rotation_velocity(
        &self,
-       entity_id: u32,
+       entity_id: Entity,
        dx: f32,
        dy: f32,
        angular: f32,
    ) -> Result<(), &'static str> {
        match self.state.try_borrow_mut() {
            Ok(mut state) => {
+              state.check_alive(entity_id)?;
                if let Some(velocity) = state.velocities.get_mut(&entity_id) {
                    debug!(
                        entity_id,
@@ -136,9 +146,10 @@ impl VelocityManager {
        }
    }
 
-   pub fn set_angular_velocity(&self, entity_id: u32, angular: f32) -> Result<(), &'static str> {
+   pub fn set_angular_velocity(&self, entity_id: Entity, angular: f32) -> Result<(), &'static str> {
        match self.state.try_borrow_mut() {
            Ok(mut state) => {
+              state.check_alive(entity_id)?;
                if let Some(velocity) = state.velocities.get_mut(&entity_id) {
                    debug!(
                        entity_id,
@@ -1,3 +1,7 @@
+pub mod bench;
+pub mod headless;
 pub mod input;
+pub mod launcher;
 pub mod rendering;
+pub mod timestep;
 pub mod managers;
@@ -1,4 +1,6 @@
+pub mod recording;
 pub mod sdl;
+pub mod software;
 
 use sdl2::pixels::Color;
 // use sdl2::rect::Rect;
@@ -6,6 +8,10 @@ use sdl2::pixels::Color;
 // use sdl2::video::Window;
 // use sdl2::Sdl;
 
+/// Size of the game window, and of frames rendered without one
+pub const WINDOW_WIDTH: u32 = 800;
+pub const WINDOW_HEIGHT: u32 = 600;
+
 // A general trait for any rendering implementation
 pub trait Renderer {
     fn clear(&mut self);
@@ -18,5 +24,31 @@ pub trait Renderer {
     fn draw_text(&mut self, text: String, x: i32, y: i32, color: Color, scale: f32);
 }
 
+/// Draws nothing, for headless runs that only need the game logic
+pub struct NullRenderer;
+
+impl Renderer for NullRenderer {
+    fn clear(&mut self) {}
+    fn present(&mut self) {}
+    fn draw_rect(&mut self, _x: i32, _y: i32, _width: u32, _height: u32, _color: Color) {}
+    fn draw_circle(&mut self, _center_x: i32, _center_y: i32, _radius: u32, _color: Color) {}
+    fn draw_line(&mut self, _x1: i32, _y1: i32, _x2: i32, _y2: i32, _color: Color) {}
+    fn draw_bounding_box(&mut self, _x: i32, _y: i32, _width: u32, _height: u32, _color: Color) {}
+    fn draw_triangle(
+        &mut self,
+        _x1: i32,
+        _y1: i32,
+        _x2: i32,
+        _y2: i32,
+        _x3: i32,
+        _y3: i32,
+        _color: Color,
+    ) {
+    }
+    fn draw_text(&mut self, _text: String, _x: i32, _y: i32, _color: Color, _scale: f32) {}
+}
+
+pub use recording::{DrawCall, RecordingRenderer};
 // Re-export SDL renderer implementation
 pub use sdl::Sdl2Renderer;
+pub use software::SoftwareRenderer;
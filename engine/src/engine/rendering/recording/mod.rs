@@ -0,0 +1,213 @@
+use crate::engine::rendering::Renderer;
+use sdl2::pixels::Color;
+use std::fmt;
+
+/// One call a [`RecordingRenderer`] received
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawCall {
+    Rect {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        color: Color,
+    },
+    Circle {
+        x: i32,
+        y: i32,
+        radius: u32,
+        color: Color,
+    },
+    Line {
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        color: Color,
+    },
+    BoundingBox {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        color: Color,
+    },
+    Triangle {
+        points: [(i32, i32); 3],
+        color: Color,
+    },
+    Text {
+        text: String,
+        x: i32,
+        y: i32,
+        color: Color,
+        scale: f32,
+    },
+}
+
+/// Keeps the draw calls of every presented frame instead of drawing them, so a
+/// headless run can be checked or compared against an earlier one.
+#[derive(Debug, Default)]
+pub struct RecordingRenderer {
+    frames: Vec<Vec<DrawCall>>,
+    current: Vec<DrawCall>,
+}
+
+impl RecordingRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The draw calls of each presented frame, oldest first
+    pub fn frames(&self) -> &[Vec<DrawCall>] {
+        &self.frames
+    }
+
+    fn record(&mut self, call: DrawCall) {
+        self.current.push(call);
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn clear(&mut self) {
+        self.current.clear();
+    }
+
+    fn present(&mut self) {
+        self.frames.push(std::mem::take(&mut self.current));
+    }
+
+    fn draw_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        self.record(DrawCall::Rect {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+
+    fn draw_circle(&mut self, center_x: i32, center_y: i32, radius: u32, color: Color) {
+        self.record(DrawCall::Circle {
+            x: center_x,
+            y: center_y,
+            radius,
+            color,
+        });
+    }
+
+    fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: Color) {
+        self.record(DrawCall::Line {
+            x1,
+            y1,
+            x2,
+            y2,
+            color,
+        });
+    }
+
+    fn draw_bounding_box(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        self.record(DrawCall::BoundingBox {
+            x,
+            y,
+            width,
+            height,
+            color,
+        });
+    }
+
+    fn draw_triangle(
+        &mut self,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        x3: i32,
+        y3: i32,
+        color: Color,
+    ) {
+        self.record(DrawCall::Triangle {
+            points: [(x1, y1), (x2, y2), (x3, y3)],
+            color,
+        });
+    }
+
+    fn draw_text(&mut self, text: String, x: i32, y: i32, color: Color, scale: f32) {
+        self.record(DrawCall::Text {
+            text,
+            x,
+            y,
+            color,
+            scale,
+        });
+    }
+}
+
+/// Writes a color as `#rrggbbaa`
+struct Hex(Color);
+
+impl fmt::Display for Hex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Color { r, g, b, a } = self.0;
+        write!(f, "#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+    }
+}
+
+/// One call per line, in the order they were made
+impl fmt::Display for DrawCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DrawCall::Rect {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => {
+                write!(f, "rect {} {} {} {} {}", x, y, width, height, Hex(*color))
+            }
+            DrawCall::Circle {
+                x,
+                y,
+                radius,
+                color,
+            } => {
+                write!(f, "circle {} {} {} {}", x, y, radius, Hex(*color))
+            }
+            DrawCall::Line {
+                x1,
+                y1,
+                x2,
+                y2,
+                color,
+            } => {
+                write!(f, "line {} {} {} {} {}", x1, y1, x2, y2, Hex(*color))
+            }
+            DrawCall::BoundingBox {
+                x,
+                y,
+                width,
+                height,
+                color,
+            } => {
+                write!(f, "box {} {} {} {} {}", x, y, width, height, Hex(*color))
+            }
+            DrawCall::Triangle { points, color } => {
+                write!(f, "triangle")?;
+                for (x, y) in points {
+                    write!(f, " {} {}", x, y)?;
+                }
+                write!(f, " {}", Hex(*color))
+            }
+            DrawCall::Text {
+                text,
+                x,
+                y,
+                color,
+                scale,
+            } => {
+                write!(f, "text {} {} {} {} {:?}", x, y, Hex(*color), scale, text)
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+pub mod font;
+
+use crate::engine::rendering::Renderer;
+use image::{Rgba, RgbaImage};
+use sdl2::pixels::Color;
+
+/// Font pixels per screen pixel at text scale 1.0, about the height of the
+/// 24pt font the SDL renderer uses
+const TEXT_PIXEL_SIZE: f32 = 3.0;
+
+/// Draws into an in-memory image instead of a window, so frames can be rendered
+/// without a display: cartridge thumbnails, screenshots and headless runs.
+pub struct SoftwareRenderer {
+    image: RgbaImage,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut renderer = SoftwareRenderer {
+            image: RgbaImage::new(width, height),
+        };
+        renderer.clear();
+        renderer
+    }
+
+    pub fn image_mut(&mut self) -> &mut RgbaImage {
+        &mut self.image
+    }
+
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    /// Width in pixels of `text` drawn with `draw_text` at `scale`
+    pub fn text_width(text: &str, scale: f32) -> u32 {
+        text.chars().count() as u32 * font::ADVANCE * text_pixel_size(scale)
+    }
+
+    /// Height in pixels of a line drawn with `draw_text` at `scale`
+    pub fn text_height(scale: f32) -> u32 {
+        font::GLYPH_HEIGHT * text_pixel_size(scale)
+    }
+
+    /// Fill a rectangle given by its top-left corner, clipped to the image
+    fn fill(&mut self, left: i32, top: i32, width: u32, height: u32, color: Color) {
+        for y in top.max(0)..(top + height as i32).min(self.image.height() as i32) {
+            for x in left.max(0)..(left + width as i32).min(self.image.width() as i32) {
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    fn blend(&mut self, x: i32, y: i32, color: Color) {
+        if x < 0 || y < 0 || x >= self.image.width() as i32 || y >= self.image.height() as i32 {
+            return;
+        }
+        let pixel = self.image.get_pixel_mut(x as u32, y as u32);
+        let alpha = color.a as u32;
+        let mix = |under: u8, over: u8| {
+            ((over as u32 * alpha + under as u32 * (255 - alpha)) / 255) as u8
+        };
+        *pixel = Rgba([
+            mix(pixel[0], color.r),
+            mix(pixel[1], color.g),
+            mix(pixel[2], color.b),
+            255,
+        ]);
+    }
+}
+
+fn text_pixel_size(scale: f32) -> u32 {
+    (TEXT_PIXEL_SIZE * scale).round().max(1.0) as u32
+}
+
+impl Renderer for SoftwareRenderer {
+    fn clear(&mut self) {
+        // The same backdrop as the SDL renderer
+        for pixel in self.image.pixels_mut() {
+            *pixel = Rgba([40, 40, 40, 255]);
+        }
+    }
+
+    fn present(&mut self) {}
+
+    fn draw_rect(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        let half_w = width as i32 / 2;
+        let half_h = height as i32 / 2;
+        self.fill(x - half_w, y - half_h, width, height, color);
+    }
+
+    fn draw_circle(&mut self, center_x: i32, center_y: i32, radius: u32, color: Color) {
+        // Scan lines between the two edges of the circle, like the SDL renderer
+        for dy in -(radius as i32)..=radius as i32 {
+            let delta_x = ((radius as f32).powi(2) - (dy as f32).powi(2)).sqrt() as i32;
+            self.fill(
+                center_x - delta_x,
+                center_y + dy,
+                (delta_x * 2 + 1) as u32,
+                1,
+                color,
+            );
+        }
+    }
+
+    fn draw_line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: Color) {
+        // Bresenham's algorithm
+        let dx = (x2 - x1).abs();
+        let dy = -(y2 - y1).abs();
+        let step_x = if x1 < x2 { 1 } else { -1 };
+        let step_y = if y1 < y2 { 1 } else { -1 };
+        let mut error = dx + dy;
+        let (mut x, mut y) = (x1, y1);
+        loop {
+            self.blend(x, y, color);
+            if x == x2 && y == y2 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    fn draw_bounding_box(&mut self, x: i32, y: i32, width: u32, height: u32, color: Color) {
+        let half_w = width as i32 / 2;
+        let half_h = height as i32 / 2;
+        let (left, right) = (x - half_w, x + half_w);
+        let (top, bottom) = (y - half_h, y + half_h);
+
+        self.draw_line(left, top, right, top, color);
+        self.draw_line(right, top, right, bottom, color);
+        self.draw_line(right, bottom, left, bottom, color);
+        self.draw_line(left, bottom, left, top, color);
+    }
+
+    fn draw_triangle(
+        &mut self,
+        x1: i32,
+        y1: i32,
+        x2: i32,
+        y2: i32,
+        x3: i32,
+        y3: i32,
+        color: Color,
+    ) {
+        self.draw_line(x1, y1, x2, y2, color);
+        self.draw_line(x2, y2, x3, y3, color);
+        self.draw_line(x3, y3, x1, y1, color);
+    }
+
+    /// Text in the built-in bitmap font, centered on `(x, y)` like the SDL renderer
+    fn draw_text(&mut self, text: String, x: i32, y: i32, color: Color, scale: f32) {
+        let size = text_pixel_size(scale);
+        let width = Self::text_width(&text, scale) as i32;
+        let left = x - width / 2;
+        let top = y - Self::text_height(scale) as i32 / 2;
+
+        for (index, c) in text.chars().enumerate() {
+            let glyph_left = left + (index as u32 * font::ADVANCE * size) as i32;
+            for (column, bits) in font::glyph(c).iter().enumerate() {
+                for row in 0..font::GLYPH_HEIGHT {
+                    if bits & (1 << row) != 0 {
+                        self.fill(
+                            glyph_left + (column as u32 * size) as i32,
+                            top + (row * size) as i32,
+                            size,
+                            size,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
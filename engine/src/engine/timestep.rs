@@ -0,0 +1,67 @@
+use crate::cartridge::container::CartridgeMetadata;
+use tracing::debug;
+
+/// Fixed updates per second for games that don't set a rate
+pub const DEFAULT_STEP_RATE: u32 = 60;
+
+/// The fastest rate a cartridge may ask for
+pub const MAX_STEP_RATE: u32 = 1000;
+
+/// Steps run in one frame at most. After a stall, such as the window being
+/// dragged, the game slows down instead of simulating the whole gap at once.
+pub const MAX_STEPS_PER_FRAME: u32 = 5;
+
+/// Fixed timestep for physics and `on_fixed_update`. Frames take however long
+/// they take, but the simulation always advances in steps of the same length, so
+/// gravity and friction behave the same at 30 and 144 frames per second. Each
+/// frame's time is added to an accumulator and as many whole steps as fit are run;
+/// what's left over is how far the frame is between the last two physics states,
+/// which rendering interpolates by.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    /// `rate` fixed updates per second, kept between 1 and [`MAX_STEP_RATE`]
+    pub fn new(rate: u32) -> Self {
+        Self {
+            step: 1.0 / rate.clamp(1, MAX_STEP_RATE) as f32,
+            accumulator: 0.0,
+        }
+    }
+
+    /// The rate a cartridge asks for, or the default
+    pub fn for_cartridge(metadata: &CartridgeMetadata) -> Self {
+        Self::new(metadata.step_rate.unwrap_or(DEFAULT_STEP_RATE))
+    }
+
+    /// Length of one step in seconds, the `delta_time` of every fixed update
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Add a frame's time and return how many steps to run for it
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += frame_time.max(0.0);
+        let steps = (self.accumulator / self.step) as u32;
+        if steps > MAX_STEPS_PER_FRAME {
+            debug!(
+                steps,
+                max = MAX_STEPS_PER_FRAME,
+                "Dropping fixed steps after a long frame"
+            );
+            self.accumulator %= self.step;
+            return MAX_STEPS_PER_FRAME;
+        }
+        self.accumulator -= steps as f32 * self.step;
+        steps
+    }
+
+    /// How far the frame is from the previous physics state to the current
+    /// one, from 0 to 1
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+}
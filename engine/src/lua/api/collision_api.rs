@@ -1,3 +1,4 @@
+use crate::ecs::components::entity::Entity;
 use crate::engine::managers::state_manager::StateManager;
 use mlua::{Lua, Result as LuaResult};
 use std::cell::RefCell;
@@ -9,7 +10,7 @@ pub fn register_collision_api(
 ) -> LuaResult<()> {
     let is_colliding = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity1, entity2): (u32, u32)| {
+        lua.create_function(move |_, (entity1, entity2): (Entity, Entity)| {
             manager
                 .borrow()
                 .check_collision(entity1, entity2)
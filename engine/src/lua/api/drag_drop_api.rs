@@ -1,4 +1,4 @@
-use crate::ecs::components::draggable::Draggable;
+use crate::ecs::components::entity::Entity;
 use crate::engine::managers::state_manager::StateManager;
 use mlua::{Lua, Result as LuaResult};
 use std::cell::RefCell;
@@ -23,7 +23,7 @@ pub fn register_drag_drop_api(
     // function to start dragging an entity
     let start_drag = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, x, y): (u32, f32, f32)| {
+        lua.create_function(move |_, (entity_id, x, y): (Entity, f32, f32)| {
             manager
                 .borrow_mut()
                 .start_dragging(entity_id, x, y)
@@ -56,7 +56,7 @@ pub fn register_drag_drop_api(
     // function to check if an entity is being dragged
     let is_dragging = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, entity_id: u32| {
+        lua.create_function(move |_, entity_id: Entity| {
             Ok(manager.borrow().is_entity_dragged(entity_id))
         })?
     };
@@ -64,25 +64,20 @@ pub fn register_drag_drop_api(
     // Function to make an entity draggable
     let make_entity_draggable = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, entity_id: u32| {
-            if let Ok(mut state) = manager.borrow_mut().state.try_borrow_mut() {
-                if state.entities.contains(&entity_id) {
-                    state.add_draggable(entity_id, Draggable::new());
-                    Ok(())
-                } else {
-                    Err(mlua::Error::runtime("Entity does not exist"))
-                }
-            } else {
-                Err(mlua::Error::runtime("Failed to borrow game state"))
-            }
+        lua.create_function(move |_, entity_id: Entity| {
+            manager
+                .borrow()
+                .make_draggable(entity_id)
+                .map_err(mlua::Error::runtime)
         })?
     };
 
     // Function to remove a draggable component
     let remove_entity_draggable = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, entity_id: u32| {
+        lua.create_function(move |_, entity_id: Entity| {
             if let Ok(mut state) = manager.borrow_mut().state.try_borrow_mut() {
+                state.check_alive(entity_id).map_err(mlua::Error::runtime)?;
                 state.remove_draggable(entity_id);
                 Ok(())
             } else {
@@ -94,8 +89,9 @@ pub fn register_drag_drop_api(
     // Function to enable/disable dragging for an entity
     let set_draggable_enabled = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, enabled): (u32, bool)| {
+        lua.create_function(move |_, (entity_id, enabled): (Entity, bool)| {
             if let Ok(mut state) = manager.borrow_mut().state.try_borrow_mut() {
+                state.check_alive(entity_id).map_err(mlua::Error::runtime)?;
                 if let Some(draggable) = state.draggables.get_mut(&entity_id) {
                     draggable.set_enabled(enabled);
                     Ok(())
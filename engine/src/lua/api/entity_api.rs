@@ -1,23 +1,28 @@
+use crate::ecs::components::entity::Entity;
 use crate::engine::managers::state_manager::StateManager;
+use crate::lua::call_on_destroy;
 use mlua::{Lua, Result as LuaResult};
-use std::rc::Rc;
 use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 pub fn register_entity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) -> LuaResult<()> {
     // create_entity
     let create_entity = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, ()| manager.borrow_mut().create_entity().map_err(mlua::Error::runtime))?
+        lua.create_function(move |_, ()| {
+            manager
+                .borrow_mut()
+                .create_entity()
+                .map_err(mlua::Error::runtime)
+        })?
     };
 
     // destroy_entity
     let destroy_entity = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, entity_id: u32| {
-            manager
-                .borrow_mut()
-                .destroy_entity(entity_id)
-                .map_err(mlua::Error::runtime)
+        lua.create_function(move |lua, entity_id: Entity| {
+            destroy_with_hooks(lua, &manager, entity_id)
         })?
     };
 
@@ -26,3 +31,92 @@ pub fn register_entity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
 
     Ok(())
 }
+
+/// Entities whose on_destroy hook is running, so destroying one from its own hook
+/// doesn't call the hook again
+#[derive(Default)]
+struct InHook(HashSet<Entity>);
+
+fn mark_in_hook(lua: &Lua, entity_id: Entity, in_hook: bool) -> bool {
+    if lua.app_data_ref::<InHook>().is_none() {
+        lua.set_app_data(InHook::default());
+    }
+    let mut marked = lua.app_data_mut::<InHook>().expect("set above");
+    if in_hook {
+        marked.0.insert(entity_id)
+    } else {
+        marked.0.remove(&entity_id)
+    }
+}
+
+/// What `destroy_entity` does: call `on_destroy` for the entity and its
+/// descendants, then destroy them. Popping a scene destroys its entities with this too.
+pub fn destroy_with_hooks(
+    lua: &Lua,
+    manager: &Rc<RefCell<StateManager>>,
+    entity_id: Entity,
+) -> LuaResult<()> {
+    let alive = manager
+        .borrow()
+        .is_entity_alive(entity_id)
+        .map_err(mlua::Error::runtime)?;
+    if !alive {
+        return Err(mlua::Error::runtime(
+            "Entity does not exist or has been destroyed",
+        ));
+    }
+
+    // Children are destroyed with their parent and get the hook too, parents
+    // first. No borrow is held while a hook runs, so it can use the whole API.
+    let is_alive = |entity: Entity| {
+        manager
+            .borrow()
+            .is_entity_alive(entity)
+            .map_err(mlua::Error::runtime)
+    };
+    let mut doomed = vec![entity_id];
+    let mut hooked = 0;
+    loop {
+        // Rescanned after every round of hooks, since a hook may attach new
+        // children to a doomed entity
+        for parent in doomed.clone() {
+            if !is_alive(parent)? {
+                continue;
+            }
+            let descendants = manager
+                .borrow()
+                .get_descendants(parent)
+                .map_err(mlua::Error::runtime)?;
+            for descendant in descendants {
+                if !doomed.contains(&descendant) {
+                    doomed.push(descendant);
+                }
+            }
+        }
+        if hooked == doomed.len() {
+            break;
+        }
+
+        for &next in &doomed[hooked..] {
+            // An earlier hook may have destroyed it already
+            if is_alive(next)? && mark_in_hook(lua, next, true) {
+                let hook = call_on_destroy(lua, next);
+                mark_in_hook(lua, next, false);
+                hook?;
+            }
+        }
+        hooked = doomed.len();
+    }
+
+    // Destroying a parent takes its children with it, but a hook may have
+    // detached some of them, or destroyed the entity itself
+    for entity in doomed {
+        if is_alive(entity)? {
+            manager
+                .borrow_mut()
+                .destroy_entity(entity)
+                .map_err(mlua::Error::runtime)?;
+        }
+    }
+    Ok(())
+}
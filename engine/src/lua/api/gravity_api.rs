@@ -1,3 +1,4 @@
+use crate::ecs::components::entity::Entity;
 use crate::engine::managers::state_manager::StateManager;
 use mlua::{Lua, Result as LuaResult};
 use std::cell::RefCell;
@@ -8,7 +9,7 @@ pub fn register_gravity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     let add_downward_gravity = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_, (entity_id, force, terminal_velocity): (u32, f32, f32)| {
+            move |_, (entity_id, force, terminal_velocity): (Entity, f32, f32)| {
                 manager
                     .borrow_mut()
                     .add_downward_gravity(entity_id, force, terminal_velocity)
@@ -21,7 +22,7 @@ pub fn register_gravity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     let add_attractive_gravity = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_, (entity_id, force, terminal_velocity): (u32, f32, f32)| {
+            move |_, (entity_id, force, terminal_velocity): (Entity, f32, f32)| {
                 manager
                     .borrow_mut()
                     .add_attractive_gravity(entity_id, force, terminal_velocity)
@@ -34,7 +35,7 @@ pub fn register_gravity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     let add_repulsive_gravity = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_, (entity_id, force, terminal_velocity): (u32, f32, f32)| {
+            move |_, (entity_id, force, terminal_velocity): (Entity, f32, f32)| {
                 manager
                     .borrow_mut()
                     .add_repulsive_gravity(entity_id, force, terminal_velocity)
@@ -46,7 +47,7 @@ pub fn register_gravity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     // Enable/disable gravity for entity
     let set_gravity_enabled = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, enabled): (u32, bool)| {
+        lua.create_function(move |_, (entity_id, enabled): (Entity, bool)| {
             manager
                 .borrow_mut()
                 .set_gravity_enabled(entity_id, enabled)
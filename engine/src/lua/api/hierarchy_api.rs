@@ -0,0 +1,74 @@
+use crate::ecs::components::entity::Entity;
+use crate::engine::managers::state_manager::StateManager;
+use mlua::{Lua, Result as LuaResult};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub fn register_hierarchy_api(
+    lua: &Lua,
+    state_manager: Rc<RefCell<StateManager>>,
+) -> LuaResult<()> {
+    // attach
+    let attach = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, (child_id, parent_id): (Entity, Entity)| {
+            manager
+                .borrow()
+                .attach(child_id, parent_id)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // detach
+    let detach = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, child_id: Entity| {
+            manager
+                .borrow()
+                .detach(child_id)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // get_parent
+    let get_parent = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, entity_id: Entity| {
+            manager
+                .borrow()
+                .get_parent(entity_id)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // get_children
+    let get_children = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, entity_id: Entity| {
+            manager
+                .borrow()
+                .get_children(entity_id)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // get_world_transform
+    let get_world_transform = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, entity_id: Entity| {
+            manager
+                .borrow()
+                .get_world_transform(entity_id)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    lua.globals().set("attach", attach)?;
+    lua.globals().set("detach", detach)?;
+    lua.globals().set("get_parent", get_parent)?;
+    lua.globals().set("get_children", get_children)?;
+    lua.globals()
+        .set("get_world_transform", get_world_transform)?;
+
+    Ok(())
+}
@@ -2,10 +2,10 @@ use crate::engine::managers::state_manager::StateManager;
 use mlua::{Lua, Result as LuaResult};
 use sdl2::keyboard::Keycode;
 use sdl2::mouse::MouseButton;
-use std::rc::Rc;
 use std::cell::RefCell;
+use std::rc::Rc;
 
-fn string_to_keycode(key: &str) -> Option<Keycode> {
+pub fn string_to_keycode(key: &str) -> Option<Keycode> {
     match key.to_uppercase().as_str() {
         "A" => Some(Keycode::A),
         "B" => Some(Keycode::B),
@@ -45,7 +45,7 @@ fn string_to_keycode(key: &str) -> Option<Keycode> {
 }
 
 // helper function to convert string to mouse button
-fn string_to_mousebutton(button: &str) -> Option<MouseButton> {
+pub fn string_to_mousebutton(button: &str) -> Option<MouseButton> {
     match button.to_uppercase().as_str() {
         "LEFT" => Some(MouseButton::Left),
         "RIGHT" => Some(MouseButton::Right),
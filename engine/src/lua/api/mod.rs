@@ -1,11 +1,15 @@
 pub mod collision_api;
+pub mod drag_drop_api;
 pub mod entity_api;
+pub mod gravity_api;
+pub mod hierarchy_api;
 pub mod input_api;
+pub mod prefab_api;
+pub mod query_api;
 pub mod renderable_api;
-pub mod transform_api;
-pub mod velocity_api;
-pub mod tilemap_api;
-pub mod gravity_api;
-pub mod drag_drop_api;
+pub mod scene_api;
 pub mod text_api;
+pub mod tilemap_api;
 pub mod timer_api;
+pub mod transform_api;
+pub mod velocity_api;
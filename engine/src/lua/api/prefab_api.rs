@@ -0,0 +1,179 @@
+use crate::ecs::components::gravity::GravityType;
+use crate::ecs::components::prefab::{GravitySpec, Prefab, ShapeSpec, TransformSpec, VelocitySpec};
+use crate::ecs::components::sprite::SHAPE_PARAMS;
+use crate::engine::managers::state_manager::StateManager;
+use mlua::{FromLua, Lua, Result as LuaResult, Table, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const COMPONENTS: &[&str] = &[
+    "transform",
+    "shape",
+    "velocity",
+    "gravity",
+    "draggable",
+    "tags",
+];
+
+/// Read a prefab, or the overrides passed to `spawn`. Unknown components and
+/// fields are errors rather than ignored, so a typo can't quietly spawn an
+/// entity with a part missing.
+pub fn prefab_from_table(lua: &Lua, table: Table) -> LuaResult<Prefab> {
+    let mut prefab = Prefab::default();
+    for pair in table.pairs::<Value, Value>() {
+        let (key, value) = pair?;
+        let component = field_name(&key, "prefab", COMPONENTS)?;
+        match component.as_str() {
+            "transform" => {
+                let fields = component_fields(lua, value, "transform")?;
+                prefab.transform = Some(TransformSpec {
+                    x: number(lua, &fields, "transform", "x")?,
+                    y: number(lua, &fields, "transform", "y")?,
+                    rotation: number(lua, &fields, "transform", "rotation")?,
+                });
+                check_fields(&fields, "transform", &["x", "y", "rotation"])?;
+            }
+            "shape" => prefab.shape = Some(shape(lua, value)?),
+            "velocity" => {
+                let fields = component_fields(lua, value, "velocity")?;
+                prefab.velocity = Some(VelocitySpec {
+                    dx: number(lua, &fields, "velocity", "dx")?,
+                    dy: number(lua, &fields, "velocity", "dy")?,
+                    angular: number(lua, &fields, "velocity", "angular")?,
+                });
+                check_fields(&fields, "velocity", &["dx", "dy", "angular"])?;
+            }
+            "gravity" => {
+                let fields = component_fields(lua, value, "gravity")?;
+                let gravity_type = match fields.get::<Option<String>>("type")?.as_deref() {
+                    None => None,
+                    Some("downward") => Some(GravityType::Downward),
+                    Some("attractive") => Some(GravityType::Attractive),
+                    Some("repulsive") => Some(GravityType::Repulsive),
+                    Some(other) => {
+                        return Err(mlua::Error::runtime(format!(
+                            "Unknown gravity type '{}', expected downward, attractive or repulsive",
+                            other
+                        )))
+                    }
+                };
+                prefab.gravity = Some(GravitySpec {
+                    gravity_type,
+                    force: number(lua, &fields, "gravity", "force")?,
+                    terminal_velocity: number(lua, &fields, "gravity", "terminal_velocity")?,
+                });
+                check_fields(&fields, "gravity", &["type", "force", "terminal_velocity"])?;
+            }
+            "draggable" => prefab.draggable = Some(bool::from_lua(value, lua)?),
+            "tags" => {
+                prefab.tags = Some(match value {
+                    Value::String(tag) => vec![tag.to_str()?.to_string()],
+                    value => Vec::<String>::from_lua(value, lua)?,
+                })
+            }
+            _ => unreachable!("field_name only returns listed components"),
+        }
+    }
+    Ok(prefab)
+}
+
+/// `shape = {name = "circle", color = {255, 0, 0}, radius = 10}`: every field
+/// besides the name and color is a size from `SHAPE_PARAMS`
+fn shape(lua: &Lua, value: Value) -> LuaResult<ShapeSpec> {
+    let fields = component_fields(lua, value, "shape")?;
+    let mut shape = ShapeSpec::default();
+    for pair in fields.pairs::<Value, Value>() {
+        let (key, value) = pair?;
+        let name = field_name(&key, "shape", &[&["name", "color"], SHAPE_PARAMS].concat())?;
+        match name.as_str() {
+            "name" => shape.name = Some(String::from_lua(value, lua)?),
+            "color" => {
+                let color = Table::from_lua(value, lua)?;
+                shape.color = Some((color.get(1)?, color.get(2)?, color.get(3)?));
+            }
+            size => {
+                if let Some(value) = number(lua, &fields, "shape", size)? {
+                    shape.params.insert(size.to_string(), value);
+                }
+            }
+        }
+    }
+    Ok(shape)
+}
+
+fn component_fields(lua: &Lua, value: Value, component: &str) -> LuaResult<Table> {
+    Table::from_lua(value, lua).map_err(|_| {
+        mlua::Error::runtime(format!("Prefab component '{}' must be a table", component))
+    })
+}
+
+fn field_name(key: &Value, component: &str, known: &[&str]) -> LuaResult<String> {
+    let Value::String(name) = key else {
+        return Err(mlua::Error::runtime(format!(
+            "{} fields must be named, expected one of: {}",
+            component,
+            known.join(", ")
+        )));
+    };
+    let name = name.to_str()?.to_string();
+    if !known.contains(&name.as_str()) {
+        return Err(mlua::Error::runtime(format!(
+            "Unknown {} field '{}', expected one of: {}",
+            component,
+            name,
+            known.join(", ")
+        )));
+    }
+    Ok(name)
+}
+
+fn check_fields(fields: &Table, component: &str, known: &[&str]) -> LuaResult<()> {
+    for pair in fields.pairs::<Value, Value>() {
+        let (key, _) = pair?;
+        field_name(&key, component, known)?;
+    }
+    Ok(())
+}
+
+fn number(lua: &Lua, fields: &Table, component: &str, field: &str) -> LuaResult<Option<f32>> {
+    match fields.get::<Value>(field)? {
+        Value::Nil => Ok(None),
+        value => f32::from_lua(value, lua)
+            .map(Some)
+            .map_err(|_| mlua::Error::runtime(format!("{}.{} must be a number", component, field))),
+    }
+}
+
+pub fn register_prefab_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) -> LuaResult<()> {
+    // define_prefab
+    let define_prefab = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |lua, (name, components): (String, Table)| {
+            let prefab = prefab_from_table(lua, components)?;
+            manager
+                .borrow()
+                .define_prefab(name, prefab)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // spawn: the new entity's ID
+    let spawn = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |lua, (name, overrides): (String, Option<Table>)| {
+            let overrides = match overrides {
+                Some(overrides) => prefab_from_table(lua, overrides)?,
+                None => Prefab::default(),
+            };
+            manager
+                .borrow()
+                .spawn(&name, &overrides)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    lua.globals().set("define_prefab", define_prefab)?;
+    lua.globals().set("spawn", spawn)?;
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use crate::ecs::components::entity::Entity;
+use crate::engine::managers::state_manager::StateManager;
+use mlua::{FromLua, Lua, Result as LuaResult, Value};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+/// Component or tag names given as a table of strings, a single string, or nil
+fn names(lua: &Lua, value: Value) -> LuaResult<Vec<String>> {
+    match value {
+        Value::Nil => Ok(Vec::new()),
+        Value::String(name) => Ok(vec![name.to_str()?.to_string()]),
+        value => Vec::<String>::from_lua(value, lua),
+    }
+}
+
+fn run_query(
+    lua: &Lua,
+    manager: &Rc<RefCell<StateManager>>,
+    components: Value,
+    tags: Value,
+) -> LuaResult<Vec<Entity>> {
+    let components = names(lua, components)?;
+    let tags = names(lua, tags)?;
+    manager
+        .borrow()
+        .query(&components, &tags)
+        .map_err(mlua::Error::runtime)
+}
+
+pub fn register_query_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) -> LuaResult<()> {
+    // add_tag
+    let add_tag = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, (entity_id, tag): (Entity, String)| {
+            manager
+                .borrow()
+                .add_tag(entity_id, tag)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // remove_tag
+    let remove_tag = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, (entity_id, tag): (Entity, String)| {
+            manager
+                .borrow()
+                .remove_tag(entity_id, &tag)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // has_tag
+    let has_tag = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, (entity_id, tag): (Entity, String)| {
+            manager
+                .borrow()
+                .has_tag(entity_id, &tag)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // get_tags
+    let get_tags = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, entity_id: Entity| {
+            manager
+                .borrow()
+                .get_tags(entity_id)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // query: an array of the matching entity IDs
+    let query = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |lua, (components, tags): (Value, Value)| {
+            run_query(lua, &manager, components, tags)
+        })?
+    };
+
+    // each_entity: an iterator for a generic `for` over the matching entities. It
+    // walks the matches from when the loop started and skips any destroyed since.
+    let each_entity = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |lua, (components, tags): (Value, Value)| {
+            let matches = run_query(lua, &manager, components, tags)?;
+            let next = Cell::new(0);
+            let manager = Rc::clone(&manager);
+            lua.create_function(move |_, ()| {
+                while let Some(&entity_id) = matches.get(next.get()) {
+                    next.set(next.get() + 1);
+                    let alive = manager
+                        .borrow()
+                        .is_entity_alive(entity_id)
+                        .map_err(mlua::Error::runtime)?;
+                    if alive {
+                        return Ok(Some(entity_id));
+                    }
+                }
+                Ok(None)
+            })
+        })?
+    };
+
+    // count_entities
+    let count_entities = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |lua, (components, tags): (Value, Value)| {
+            Ok(run_query(lua, &manager, components, tags)?.len())
+        })?
+    };
+
+    lua.globals().set("add_tag", add_tag)?;
+    lua.globals().set("remove_tag", remove_tag)?;
+    lua.globals().set("has_tag", has_tag)?;
+    lua.globals().set("get_tags", get_tags)?;
+    lua.globals().set("query", query)?;
+    lua.globals().set("each_entity", each_entity)?;
+    lua.globals().set("count_entities", count_entities)?;
+
+    Ok(())
+}
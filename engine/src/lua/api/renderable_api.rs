@@ -1,7 +1,23 @@
+use crate::ecs::components::entity::Entity;
+use crate::ecs::components::sprite::ShapeParams;
 use crate::engine::managers::state_manager::StateManager;
-use mlua::{Lua, Result as LuaResult, Table};
+use mlua::{FromLua, Lua, Result as LuaResult, Table, Value};
 use std::cell::RefCell;
 use std::rc::Rc;
+use tracing::debug;
+
+/// The sizes in an `add_shape` params table. Fields that aren't numbers are left
+/// out, so the shape keeps its default for them.
+fn shape_params(lua: &Lua, params: Table) -> LuaResult<ShapeParams> {
+    let mut sizes = ShapeParams::new();
+    for pair in params.pairs::<Value, Value>() {
+        let (key, value) = pair?;
+        if let (Value::String(key), Ok(size)) = (key, f32::from_lua(value, lua)) {
+            sizes.insert(key.to_str()?.to_string(), size);
+        }
+    }
+    Ok(sizes)
+}
 
 pub fn register_renderable_api(
     lua: &Lua,
@@ -11,19 +27,20 @@ pub fn register_renderable_api(
     let add_shape = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_,
+            move |lua,
                   (entity_id, shape_name, r, g, b, params): (
-                u32,
+                Entity,
                 String,
                 u8,
                 u8,
                 u8,
                 Option<Table>,
             )| {
+                let params = params.map(|params| shape_params(lua, params)).transpose()?;
                 // We can now directly add the sprite using the asset name
                 manager
                     .borrow_mut()
-                    .add_sprite(entity_id, &shape_name, r, g, b, params)
+                    .add_sprite(entity_id, &shape_name, r, g, b, params.as_ref())
                     .map_err(mlua::Error::runtime)
             },
         )?
@@ -31,6 +48,6 @@ pub fn register_renderable_api(
 
     // Register our primary shape-adding function
     lua.globals().set("add_shape", add_shape)?;
-    println!("Registered shape rendering functions");
+    debug!("Registered shape rendering functions");
     Ok(())
 }
@@ -0,0 +1,162 @@
+use crate::ecs::components::scene::Scene;
+use crate::engine::managers::state_manager::StateManager;
+use crate::lua::api::entity_api::destroy_with_hooks;
+use mlua::{FromLua, Function, Lua, Result as LuaResult, Table, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const SCENE_FIELDS: &[&str] = &[
+    "on_enter",
+    "on_frame",
+    "on_fixed_update",
+    "on_exit",
+    "draw_when_paused",
+];
+
+/// `{on_enter = ..., on_frame = ..., on_exit = ..., draw_when_paused = true}`, every
+/// field optional. Unknown fields are errors, so a misspelled callback isn't
+/// silently never called.
+fn scene_from_table(lua: &Lua, table: Table) -> LuaResult<Scene> {
+    let mut scene = Scene::default();
+    for pair in table.pairs::<Value, Value>() {
+        let (key, value) = pair?;
+        let field = match &key {
+            Value::String(name) => name.to_str()?.to_string(),
+            _ => String::new(),
+        };
+        match field.as_str() {
+            "on_enter" => scene.on_enter = Some(Function::from_lua(value, lua)?),
+            "on_frame" => scene.on_frame = Some(Function::from_lua(value, lua)?),
+            "on_fixed_update" => scene.on_fixed_update = Some(Function::from_lua(value, lua)?),
+            "on_exit" => scene.on_exit = Some(Function::from_lua(value, lua)?),
+            "draw_when_paused" => scene.draw_when_paused = bool::from_lua(value, lua)?,
+            _ => {
+                return Err(mlua::Error::runtime(format!(
+                    "Unknown scene field '{}', expected one of: {}",
+                    key.to_string()?,
+                    SCENE_FIELDS.join(", ")
+                )))
+            }
+        }
+    }
+    Ok(scene)
+}
+
+/// Push a scene and run its `on_enter`. Entities created from then on belong to it.
+fn enter_scene(manager: &Rc<RefCell<StateManager>>, name: &str) -> LuaResult<()> {
+    let scene = manager
+        .borrow()
+        .push_scene(name)
+        .map_err(mlua::Error::runtime)?;
+    if let Some(on_enter) = &scene.on_enter {
+        on_enter.call::<()>(())?;
+    }
+    Ok(())
+}
+
+/// Run the top scene's `on_exit`, then take it off the stack and destroy the
+/// entities it owns
+fn leave_scene(lua: &Lua, manager: &Rc<RefCell<StateManager>>) -> LuaResult<()> {
+    let top = manager.borrow().top_scene().map_err(mlua::Error::runtime)?;
+    let Some((instance, scene)) = top else {
+        return Err(mlua::Error::runtime("No scene to pop"));
+    };
+    if let Some(on_exit) = &scene.on_exit {
+        on_exit.call::<()>(())?;
+    }
+
+    let owned = manager
+        .borrow()
+        .remove_scene(instance)
+        .map_err(mlua::Error::runtime)?;
+    for entity_id in owned {
+        // Destroying a parent has already taken its children, and a hook may
+        // have destroyed others
+        let alive = manager
+            .borrow()
+            .is_entity_alive(entity_id)
+            .map_err(mlua::Error::runtime)?;
+        if alive {
+            destroy_with_hooks(lua, manager, entity_id)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn register_scene_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) -> LuaResult<()> {
+    // define_scene
+    let define_scene = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |lua, (name, callbacks): (String, Table)| {
+            let scene = scene_from_table(lua, callbacks)?;
+            manager
+                .borrow()
+                .define_scene(name, scene)
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    // push_scene: pauses the current scene
+    let push_scene = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, name: String| enter_scene(&manager, &name))?
+    };
+
+    // pop_scene: resumes the scene below
+    let pop_scene = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |lua, ()| leave_scene(lua, &manager))?
+    };
+
+    // switch_scene: replaces the current scene, or pushes onto an empty stack
+    let switch_scene = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |lua, name: String| {
+            // Checked first so a typo doesn't leave the stack one scene short
+            let defined = manager
+                .borrow()
+                .has_scene(&name)
+                .map_err(mlua::Error::runtime)?;
+            if !defined {
+                return Err(mlua::Error::runtime(format!("No scene named '{}'", name)));
+            }
+            let top = manager.borrow().top_scene().map_err(mlua::Error::runtime)?;
+            if top.is_some() {
+                leave_scene(lua, &manager)?;
+            }
+            enter_scene(&manager, &name)
+        })?
+    };
+
+    // current_scene: the name of the scene on top, or nil
+    let current_scene = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, ()| {
+            let stack = manager
+                .borrow()
+                .get_scene_stack()
+                .map_err(mlua::Error::runtime)?;
+            Ok(stack.last().cloned())
+        })?
+    };
+
+    // get_scene_stack: names of the pushed scenes, bottom first
+    let get_scene_stack = {
+        let manager = Rc::clone(&state_manager);
+        lua.create_function(move |_, ()| {
+            manager
+                .borrow()
+                .get_scene_stack()
+                .map_err(mlua::Error::runtime)
+        })?
+    };
+
+    lua.globals().set("define_scene", define_scene)?;
+    lua.globals().set("push_scene", push_scene)?;
+    lua.globals().set("pop_scene", pop_scene)?;
+    lua.globals().set("switch_scene", switch_scene)?;
+    lua.globals().set("current_scene", current_scene)?;
+    lua.globals().set("get_scene_stack", get_scene_stack)?;
+
+    Ok(())
+}
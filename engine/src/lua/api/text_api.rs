@@ -1,3 +1,4 @@
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::text::{HorizontalAlign, TextId, VerticalAlign};
 use crate::engine::managers::state_manager::StateManager;
 use mlua::{Lua, Result as LuaResult, Table};
@@ -73,7 +74,7 @@ pub fn register_text_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) ->
     let add_text = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_, (entity_id, text_id_str, params): (u32, String, Option<Table>)| {
+            move |_, (entity_id, text_id_str, params): (Entity, String, Option<Table>)| {
                 let text_id = string_to_text_id(&text_id_str)
                     .ok_or_else(|| mlua::Error::runtime("Invalid text ID"))?;
 
@@ -116,7 +117,7 @@ pub fn register_text_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) ->
     // Function to update text
     let update_text = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, text_id_str): (u32, String)| {
+        lua.create_function(move |_, (entity_id, text_id_str): (Entity, String)| {
             let text_id = string_to_text_id(&text_id_str)
                 .ok_or_else(|| mlua::Error::runtime("Invalid text ID"))?;
 
@@ -130,7 +131,7 @@ pub fn register_text_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) ->
     // Function to set text color
     let set_text_color = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, r, g, b): (u32, u8, u8, u8)| {
+        lua.create_function(move |_, (entity_id, r, g, b): (Entity, u8, u8, u8)| {
             manager
                 .borrow_mut()
                 .set_text_color(entity_id, (r, g, b))
@@ -141,7 +142,7 @@ pub fn register_text_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) ->
     // Function to set text scale
     let set_text_scale = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, scale): (u32, f32)| {
+        lua.create_function(move |_, (entity_id, scale): (Entity, f32)| {
             manager
                 .borrow_mut()
                 .set_text_scale(entity_id, scale)
@@ -152,7 +153,7 @@ pub fn register_text_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) ->
     // Function to set text visibility
     let set_text_visibility = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, visible): (u32, bool)| {
+        lua.create_function(move |_, (entity_id, visible): (Entity, bool)| {
             manager
                 .borrow_mut()
                 .set_text_visibility(entity_id, visible)
@@ -162,7 +163,7 @@ pub fn register_text_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) ->
 
     let set_text_value = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, value): (u32, String)| {
+        lua.create_function(move |_, (entity_id, value): (Entity, String)| {
             manager
                 .borrow_mut()
                 .set_text_value(entity_id, value)
@@ -172,7 +173,7 @@ pub fn register_text_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>) ->
 
     let get_text = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |lua, entity_id: u32| -> mlua::Result<mlua::Value> {
+        lua.create_function(move |lua, entity_id: Entity| -> mlua::Result<mlua::Value> {
             match manager.borrow().get_text(entity_id) {
                 Ok(maybe_text) => {
                     if let Some(text) = maybe_text {
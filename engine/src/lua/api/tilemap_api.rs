@@ -1,3 +1,4 @@
+use crate::ecs::components::entity::Entity;
 use crate::ecs::components::tilemap::{TilemapQuery, TilemapQueryResult};
 use crate::engine::managers::state_manager::StateManager;
 use mlua::{Lua, Result as LuaResult};
@@ -9,7 +10,7 @@ pub fn register_tilemap_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     let create_tilemap = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_, (entity_id, width, height, tile_size): (u32, u32, u32, u32)| {
+            move |_, (entity_id, width, height, tile_size): (Entity, u32, u32, u32)| {
                 manager
                     .borrow_mut()
                     .create_tilemap(entity_id, width, height, tile_size)
@@ -21,7 +22,7 @@ pub fn register_tilemap_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     // Get tilemap
     let get_tilemap = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |lua, entity_id: u32| {
+        lua.create_function(move |lua, entity_id: Entity| {
             match manager.borrow().get_tilemap(entity_id) {
                 Ok(Some(tilemap)) => {
                     // Create a Lua table to represent the tilemap
@@ -68,7 +69,7 @@ pub fn register_tilemap_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
         lua.create_function(
             move |_,
                   (entity_id, x, y, tile_id, walkable, r, g, b): (
-                u32,
+                Entity,
                 u32,
                 u32,
                 u32,
@@ -88,7 +89,7 @@ pub fn register_tilemap_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     // Clear tile
     let clear_tile = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, x, y): (u32, u32, u32)| {
+        lua.create_function(move |_, (entity_id, x, y): (Entity, u32, u32)| {
             manager
                 .borrow_mut()
                 .clear_tile(entity_id, x, y)
@@ -100,7 +101,7 @@ pub fn register_tilemap_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     let query_tilemap = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |lua, (entity_id, query_type, args): (u32, String, mlua::Table)| {
+            move |lua, (entity_id, query_type, args): (Entity, String, mlua::Table)| {
                 let query = match query_type.as_str() {
                     "dimensions" => TilemapQuery::Dimensions,
                     "tile" => {
@@ -203,7 +204,7 @@ pub fn register_tilemap_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     // Check if position is walkable
     let is_walkable = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, x, y): (u32, u32, u32)| {
+        lua.create_function(move |_, (entity_id, x, y): (Entity, u32, u32)| {
             manager
                 .borrow()
                 .is_tile_walkable(entity_id, x, y)
@@ -215,7 +216,7 @@ pub fn register_tilemap_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
     let check_position_walkable = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_, (entity_id, tilemap_id, x, y): (u32, u32, f32, f32)| {
+            move |_, (entity_id, tilemap_id, x, y): (Entity, Entity, f32, f32)| {
                 manager
                     .borrow()
                     .check_position_walkable(entity_id, tilemap_id, x, y)
@@ -226,7 +227,7 @@ pub fn register_tilemap_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
 
     let get_dimensions = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, entity_id: u32| {
+        lua.create_function(move |_, entity_id: Entity| {
             manager
                 .borrow()
                 .get_dimensions(entity_id)
@@ -236,7 +237,7 @@ pub fn register_tilemap_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>)
 
     let get_tile_size = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, entity_id: u32| {
+        lua.create_function(move |_, entity_id: Entity| {
             manager
                 .borrow()
                 .get_tile_size(entity_id)
@@ -1,3 +1,4 @@
+use crate::ecs::components::entity::Entity;
 use crate::engine::managers::state_manager::StateManager;
 use mlua::{Lua, Result as LuaResult};
 use std::cell::RefCell;
@@ -10,7 +11,7 @@ pub fn register_transform_api(
     let set_transform = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_, (entity_id, x, y, rotation): (u32, f32, f32, f32)| {
+            move |_, (entity_id, x, y, rotation): (Entity, f32, f32, f32)| {
                 manager
                     .borrow_mut()
                     .set_transform(entity_id, x, y, rotation)
@@ -21,7 +22,7 @@ pub fn register_transform_api(
 
     let get_transform = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, entity_id: u32| {
+        lua.create_function(move |_, entity_id: Entity| {
             manager
                 .borrow()
                 .get_transform(entity_id)
@@ -1,3 +1,4 @@
+use crate::ecs::components::entity::Entity;
 use crate::engine::managers::state_manager::StateManager;
 use mlua::{Lua, Result as LuaResult};
 use std::cell::RefCell;
@@ -8,7 +9,7 @@ pub fn register_velocity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>
     let set_velocity = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_, (entity_id, velocity_x, velocity_y): (u32, f32, f32)| {
+            move |_, (entity_id, velocity_x, velocity_y): (Entity, f32, f32)| {
                 manager
                     .borrow_mut()
                     .set_velocity(entity_id, velocity_x, velocity_y)
@@ -20,7 +21,7 @@ pub fn register_velocity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>
     // Register get_velocity
     let get_velocity = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, entity_id: u32| {
+        lua.create_function(move |_, entity_id: Entity| {
             manager
                 .borrow()
                 .get_velocity(entity_id)
@@ -31,7 +32,7 @@ pub fn register_velocity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>
     // New zero velocity
     let set_zero_velocity = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, entity_id: u32| {
+        lua.create_function(move |_, entity_id: Entity| {
             manager
                 .borrow_mut()
                 .set_zero_velocity(entity_id)
@@ -42,7 +43,7 @@ pub fn register_velocity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>
     // New horizontal velocity
     let set_horizontal_velocity = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, speed): (u32, f32)| {
+        lua.create_function(move |_, (entity_id, speed): (Entity, f32)| {
             manager
                 .borrow_mut()
                 .set_horizontal_velocity(entity_id, speed)
@@ -54,7 +55,7 @@ pub fn register_velocity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>
     let set_rotation_velocity = {
         let manager = Rc::clone(&state_manager);
         lua.create_function(
-            move |_, (entity_id, dx, dy, angular): (u32, f32, f32, f32)| {
+            move |_, (entity_id, dx, dy, angular): (Entity, f32, f32, f32)| {
                 manager
                     .borrow_mut()
                     .set_rotation_velocity(entity_id, dx, dy, angular)
@@ -66,7 +67,7 @@ pub fn register_velocity_api(lua: &Lua, state_manager: Rc<RefCell<StateManager>>
     // New angular velocity
     let set_angular_velocity = {
         let manager = Rc::clone(&state_manager);
-        lua.create_function(move |_, (entity_id, angular): (u32, f32)| {
+        lua.create_function(move |_, (entity_id, angular): (Entity, f32)| {
             manager
                 .borrow_mut()
                 .set_angular_velocity(entity_id, angular)
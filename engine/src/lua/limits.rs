@@ -0,0 +1,268 @@
+use crate::cartridge::container::CartridgeMetadata;
+use mlua::{Debug, Error, HookTriggers, Lua, Result as LuaResult, Table, Thread, VmState};
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+/// Instructions a single callback may run before the game is paused. A busy
+/// `on_frame` runs well under a million; this only stops runaway loops.
+pub const DEFAULT_INSTRUCTION_BUDGET: u32 = 10_000_000;
+
+/// Bytes the game's Lua state may allocate
+pub const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Instructions between budget checks. Checking every instruction would make
+/// every game noticeably slower.
+const CHECK_INTERVAL: u32 = 1000;
+
+/// Instructions charged for each switch into or out of a coroutine. Setting a hook
+/// restarts its count, so a loop that keeps resuming a coroutine would otherwise
+/// never reach a check.
+const SWITCH_COST: u32 = 100;
+
+/// Wraps `coroutine.resume` and `coroutine.wrap` so the budget follows the game into
+/// its coroutines: a hook only runs on the thread it was set on.
+const COROUTINE_SOURCE: &str = r#"
+local coroutine, attach, detach = ...
+local create, resume, error = coroutine.create, coroutine.resume, error
+
+local function resumed(...)
+    detach()
+    return ...
+end
+
+coroutine.resume = function(co, ...)
+    attach(co)
+    return resumed(resume(co, ...))
+end
+
+local function wrapped(ok, ...)
+    detach()
+    if not ok then
+        error((...), 0)
+    end
+    return ...
+end
+
+coroutine.wrap = function(f)
+    local co = create(f)
+    return function(...)
+        attach(co)
+        return wrapped(resume(co, ...))
+    end
+end
+"#;
+
+/// How much a game may compute and allocate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptLimits {
+    /// Lua instructions each call into the game may run: the main chunk, `on_start`,
+    /// every `on_frame` and `on_end` each get the full budget
+    pub instruction_budget: u32,
+    pub memory_limit: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            instruction_budget: DEFAULT_INSTRUCTION_BUDGET,
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+        }
+    }
+}
+
+impl ScriptLimits {
+    /// The limits a cartridge asks for, with the defaults for anything it leaves out
+    pub fn for_cartridge(metadata: &CartridgeMetadata) -> Self {
+        let defaults = Self::default();
+        Self {
+            instruction_budget: metadata
+                .instruction_budget
+                .unwrap_or(defaults.instruction_budget),
+            memory_limit: metadata
+                .memory_limit
+                .map_or(defaults.memory_limit, |limit| limit as usize),
+        }
+    }
+}
+
+/// Why the engine stopped running a game's code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitExceeded {
+    Instructions { callback: &'static str, budget: u32 },
+    Memory { limit: usize },
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitExceeded::Instructions { callback, budget } => write!(
+                f,
+                "{} ran more than {} Lua instructions without returning",
+                callback, budget
+            ),
+            LimitExceeded::Memory { limit } => write!(
+                f,
+                "the game used more than its {} KiB of Lua memory",
+                limit / 1024
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl LimitExceeded {
+    /// The limit behind a failed call into the game, if that is why it failed
+    pub fn from_error(error: &Error) -> Option<&LimitExceeded> {
+        error.downcast_ref::<LimitExceeded>()
+    }
+}
+
+/// Instruction count of the callback that is running, shared with the hooks
+#[derive(Debug)]
+struct Budget {
+    limits: ScriptLimits,
+    used: Cell<u32>,
+    /// The callback being counted, `None` between calls into the game
+    callback: Cell<Option<&'static str>>,
+    exceeded: Cell<bool>,
+}
+
+impl Budget {
+    fn triggers(&self) -> HookTriggers {
+        // Once over budget, fail on every instruction so `pcall` can't swallow it:
+        // the first instruction back in an unprotected frame fails too
+        let every = if self.exceeded.get() {
+            1
+        } else {
+            CHECK_INTERVAL
+        };
+        HookTriggers::new().every_nth_instruction(every)
+    }
+
+    /// Charge instructions to the running callback, failing once it is over budget
+    fn spend(self: &Rc<Self>, lua: &Lua, instructions: u32) -> LuaResult<()> {
+        let Some(callback) = self.callback.get() else {
+            return Ok(());
+        };
+        if !self.exceeded.get() {
+            let used = self.used.get().saturating_add(instructions);
+            self.used.set(used);
+            if used <= self.limits.instruction_budget {
+                return Ok(());
+            }
+            self.exceeded.set(true);
+            self.attach(lua);
+        }
+        Err(Error::runtime(format!(
+            "{} is over its instruction budget",
+            callback
+        )))
+    }
+
+    /// Count instructions on the thread that is running
+    fn attach(self: &Rc<Self>, lua: &Lua) {
+        let budget = Rc::clone(self);
+        lua.set_hook(self.triggers(), move |lua, _: Debug| {
+            budget.spend(lua, CHECK_INTERVAL)?;
+            Ok(VmState::Continue)
+        });
+    }
+
+    /// Count instructions on a coroutine about to be resumed
+    fn attach_thread(self: &Rc<Self>, thread: &Thread) {
+        let budget = Rc::clone(self);
+        thread.set_hook(self.triggers(), move |lua, _: Debug| {
+            budget.spend(lua, CHECK_INTERVAL)?;
+            Ok(VmState::Continue)
+        });
+    }
+}
+
+/// Cap the memory of a game's Lua state and count the instructions of every call
+/// made through [`with_budget`]. Call it before the game's code is loaded.
+pub fn install_limits(lua: &Lua, limits: ScriptLimits) -> LuaResult<()> {
+    lua.set_memory_limit(limits.memory_limit)?;
+
+    let budget = Rc::new(Budget {
+        limits,
+        used: Cell::new(0),
+        callback: Cell::new(None),
+        exceeded: Cell::new(false),
+    });
+    lua.set_app_data(Rc::clone(&budget));
+
+    let Ok(coroutine) = lua.globals().get::<Table>("coroutine") else {
+        return Ok(());
+    };
+    let attach = {
+        let budget = Rc::clone(&budget);
+        lua.create_function(move |lua, thread: Thread| {
+            if budget.callback.get().is_some() {
+                budget.spend(lua, SWITCH_COST)?;
+                budget.attach_thread(&thread);
+            }
+            Ok(())
+        })?
+    };
+    let detach = lua.create_function(move |lua, ()| {
+        if budget.callback.get().is_some() {
+            budget.attach(lua);
+            budget.spend(lua, SWITCH_COST)?;
+        }
+        Ok(())
+    })?;
+    lua.load(COROUTINE_SOURCE)
+        .set_name("=limits")
+        .call::<()>((coroutine, attach, detach))
+}
+
+/// Run one call into the game's code under its instruction budget. Going over the
+/// budget or the memory limit fails with a [`LimitExceeded`] error.
+pub fn with_budget<R>(
+    lua: &Lua,
+    callback: &'static str,
+    call: impl FnOnce() -> LuaResult<R>,
+) -> LuaResult<R> {
+    let Some(budget) = lua
+        .app_data_ref::<Rc<Budget>>()
+        .map(|budget| Rc::clone(&budget))
+    else {
+        return call();
+    };
+
+    budget.used.set(0);
+    budget.exceeded.set(false);
+    budget.callback.set(Some(callback));
+    budget.attach(lua);
+    let result = call();
+    lua.remove_hook();
+    budget.callback.set(None);
+
+    result.map_err(|e| {
+        if budget.exceeded.get() {
+            Error::external(LimitExceeded::Instructions {
+                callback,
+                budget: budget.limits.instruction_budget,
+            })
+        } else if is_memory_error(&e) {
+            Error::external(LimitExceeded::Memory {
+                limit: budget.limits.memory_limit,
+            })
+        } else {
+            e
+        }
+    })
+}
+
+/// Memory errors reach Rust wrapped in whatever callbacks they passed through
+fn is_memory_error(error: &Error) -> bool {
+    match error {
+        Error::MemoryError(_) => true,
+        Error::CallbackError { cause, .. } | Error::WithContext { cause, .. } => {
+            is_memory_error(cause)
+        }
+        _ => false,
+    }
+}
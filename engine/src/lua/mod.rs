@@ -1,31 +1,221 @@
 pub mod api;
+pub mod limits;
+pub mod sandbox;
 
-use mlua::{Function, Lua, Result as LuaResult};
+use crate::cartridge::abi;
+use crate::cartridge::manifest::ApiManifest;
+use crate::cartridge::Cartridge;
+use crate::ecs::components::entity::Entity;
+use crate::ecs::components::scene::Scene;
+use crate::ecs::{GameState, InputSystem};
+use crate::engine::managers::state_manager::StateManager;
+use limits::with_budget;
+use mlua::{ChunkMode, Function, Lua, Result as LuaResult, Value};
+use std::cell::RefCell;
+use std::rc::Rc;
 
 pub use api::{
     collision_api::register_collision_api, drag_drop_api::register_drag_drop_api,
     entity_api::register_entity_api, gravity_api::register_gravity_api,
-    input_api::register_input_api, renderable_api::register_renderable_api,
+    hierarchy_api::register_hierarchy_api, input_api::register_input_api,
+    prefab_api::register_prefab_api, query_api::register_query_api,
+    renderable_api::register_renderable_api, scene_api::register_scene_api,
     text_api::register_text_api, tilemap_api::register_tilemap_api, timer_api::register_timer_api,
     transform_api::register_transform_api, velocity_api::register_velocity_api,
 };
 
+type RegisterFn = fn(&Lua, Rc<RefCell<StateManager>>) -> LuaResult<()>;
+
+/// Every engine API module, named after its source file in `src/lua/api`. A module's
+/// position is its id in cartridge manifests, so new modules go at the end.
+pub const API_MODULES: &[(&str, RegisterFn)] = &[
+    ("entity_api", register_entity_api),
+    ("transform_api", register_transform_api),
+    ("renderable_api", register_renderable_api),
+    ("input_api", register_input_api),
+    ("collision_api", register_collision_api),
+    ("tilemap_api", register_tilemap_api),
+    ("velocity_api", register_velocity_api),
+    ("gravity_api", register_gravity_api),
+    ("drag_drop_api", register_drag_drop_api),
+    ("text_api", register_text_api),
+    ("timer_api", register_timer_api),
+    ("query_api", register_query_api),
+    ("hierarchy_api", register_hierarchy_api),
+    ("prefab_api", register_prefab_api),
+    ("scene_api", register_scene_api),
+];
+
+/// Register the engine API modules a game declared into its Lua state
+pub fn register_engine_api(
+    lua: &Lua,
+    state_manager: Rc<RefCell<StateManager>>,
+    manifest: ApiManifest,
+) -> LuaResult<()> {
+    for (name, register) in API_MODULES {
+        if manifest.contains(name) {
+            register(lua, Rc::clone(&state_manager))?;
+        }
+    }
+    Ok(())
+}
+
+/// The globals each API module adds to a fresh Lua state, sorted by name. Tables
+/// such as `TEXT` and `timer` are listed by field: `TEXT.GAME_OVER`, `timer.set_interval`.
+pub fn api_module_globals() -> LuaResult<Vec<(&'static str, Vec<String>)>> {
+    let mut modules = Vec::new();
+    for (module, register) in API_MODULES {
+        let lua = Lua::new();
+        let builtin: Vec<String> = lua
+            .globals()
+            .pairs::<String, Value>()
+            .filter_map(|pair| pair.ok().map(|(name, _)| name))
+            .collect();
+
+        // Registering only stores closures, so a throwaway state is enough
+        let state_manager = Rc::new(RefCell::new(StateManager::new(
+            Rc::new(RefCell::new(GameState::new())),
+            Rc::new(RefCell::new(InputSystem::new())),
+        )));
+        register(&lua, state_manager)?;
+
+        let mut names = Vec::new();
+        for pair in lua.globals().pairs::<String, Value>() {
+            let (name, value) = pair?;
+            if builtin.contains(&name) {
+                continue;
+            }
+            match value {
+                Value::Table(table) => {
+                    for field in table.pairs::<String, Value>() {
+                        let (field, _) = field?;
+                        names.push(format!("{}.{}", name, field));
+                    }
+                }
+                _ => names.push(name),
+            }
+        }
+        // Lua table order isn't stable across builds
+        names.sort();
+        modules.push((*module, names));
+    }
+    Ok(modules)
+}
+
+/// Define the prefabs the packer moved into the cartridge's prefab table, then run the
+/// game's main chunk, which defines its callbacks.
+///
+/// Untrusted games only load source: Lua does not verify bytecode, so a hand-crafted
+/// chunk could get out of the sandbox. A bytecode cartridge runs its fallback source
+/// instead, and is refused without one.
+pub fn load_game(
+    lua: &Lua,
+    state_manager: &Rc<RefCell<StateManager>>,
+    game: &Cartridge,
+    trusted: bool,
+) -> LuaResult<()> {
+    let code = if trusted || !abi::is_bytecode(&game.code) {
+        &game.code
+    } else {
+        game.fallback_source.as_ref().ok_or_else(|| {
+            mlua::Error::runtime(
+                "The cartridge is precompiled bytecode, which only runs with --trusted; \
+                 repack it without --bytecode, or with --fallback-source",
+            )
+        })?
+    };
+    for (name, prefab) in &game.prefabs {
+        state_manager
+            .borrow()
+            .define_prefab(name.clone(), prefab.clone())
+            .map_err(mlua::Error::runtime)?;
+    }
+    let chunk_name = game.metadata.title.as_deref().unwrap_or("cartridge");
+    with_budget(lua, "the main chunk", || {
+        let chunk = lua.load(code).set_name(format!("={}", chunk_name));
+        if trusted {
+            chunk.exec()
+        } else {
+            chunk.set_mode(ChunkMode::Text).exec()
+        }
+    })
+}
+
+// Each callback runs under its own instruction budget, see `limits::with_budget`
 pub fn call_on_start(lua: &Lua) -> LuaResult<()> {
     if let Ok(on_start) = lua.globals().get::<Function>("on_start") {
-        on_start.call::<()>(())?;
+        with_budget(lua, "on_start", || on_start.call::<()>(()))?;
     }
     Ok(())
 }
 
-pub fn call_on_frame(lua: &Lua, delta_time: f32) -> LuaResult<()> {
+/// The game's `on_frame`, then that of the scene on top of the scene stack. Scenes
+/// further down are paused and aren't called.
+pub fn call_on_frame(
+    lua: &Lua,
+    state_manager: &Rc<RefCell<StateManager>>,
+    delta_time: f32,
+) -> LuaResult<()> {
     if let Ok(on_frame) = lua.globals().get::<Function>("on_frame") {
-        on_frame.call::<()>(delta_time)?;
+        with_budget(lua, "on_frame", || on_frame.call::<()>(delta_time))?;
+    }
+    call_top_scene(
+        lua,
+        state_manager,
+        "the scene's on_frame",
+        delta_time,
+        |scene| scene.on_frame.clone(),
+    )
+}
+
+/// Called before every fixed step with the step's length, like `call_on_frame`
+pub fn call_on_fixed_update(
+    lua: &Lua,
+    state_manager: &Rc<RefCell<StateManager>>,
+    step: f32,
+) -> LuaResult<()> {
+    if let Ok(on_fixed_update) = lua.globals().get::<Function>("on_fixed_update") {
+        with_budget(lua, "on_fixed_update", || on_fixed_update.call::<()>(step))?;
+    }
+    call_top_scene(
+        lua,
+        state_manager,
+        "the scene's on_fixed_update",
+        step,
+        |scene| scene.on_fixed_update.clone(),
+    )
+}
+
+fn call_top_scene(
+    lua: &Lua,
+    state_manager: &Rc<RefCell<StateManager>>,
+    name: &'static str,
+    delta_time: f32,
+    callback: fn(&Scene) -> Option<Function>,
+) -> LuaResult<()> {
+    // Looked up after the game's own callback, which may have switched scenes
+    let top = state_manager
+        .borrow()
+        .top_scene()
+        .map_err(mlua::Error::runtime)?;
+    if let Some(callback) = top.and_then(|(_, scene)| callback(&scene)) {
+        with_budget(lua, name, || callback.call::<()>(delta_time))?;
+    }
+    Ok(())
+}
+
+/// Called from `destroy_entity` while the entity still has its components. It runs
+/// inside the budget of whichever callback destroyed the entity.
+pub fn call_on_destroy(lua: &Lua, entity: Entity) -> LuaResult<()> {
+    if let Ok(on_destroy) = lua.globals().get::<Function>("on_destroy") {
+        on_destroy.call::<()>(entity)?;
     }
     Ok(())
 }
+
 pub fn call_on_end(lua: &Lua) -> LuaResult<()> {
     if let Ok(on_end) = lua.globals().get::<Function>("on_end") {
-        on_end.call::<()>(())?;
+        with_budget(lua, "on_end", || on_end.call::<()>(()))?;
     }
     Ok(())
 }
@@ -0,0 +1,106 @@
+use mlua::{Lua, LuaOptions, Result as LuaResult, StdLib, Table};
+
+/// `os` functions that only read the clock, enough to seed `math.random` and time things
+const SAFE_OS_FUNCTIONS: &[&str] = &["time", "clock", "date", "difftime"];
+
+/// Names a cartridge gets a clear error for instead of `nil`. Base functions in this
+/// list are removed: they load code from files or bytes, or reach into the collector.
+const BLOCKED_GLOBALS: &[&str] = &[
+    "io",
+    "package",
+    "debug",
+    "require",
+    "dofile",
+    "loadfile",
+    "load",
+    "collectgarbage",
+];
+const BLOCKED_OS_FUNCTIONS: &[&str] = &[
+    "execute",
+    "exit",
+    "getenv",
+    "remove",
+    "rename",
+    "setlocale",
+    "tmpname",
+];
+const BLOCKED_STRING_FUNCTIONS: &[&str] = &["dump"];
+
+/// Builds the `__index` metamethod that reports a blocked name. Written in Lua so
+/// `error(..., 2)` points at the line of the game that touched it.
+const GUARD_SOURCE: &str = r#"
+local blocked, prefix = ...
+local error, format = error, string.format
+return function(_, key)
+    if blocked[key] then
+        error(format("'%s%s' is not available to cartridges: the sandbox only allows "
+            .. "the safe standard library and the QRcade API (run local scripts with "
+            .. "--trusted to lift it)", prefix, key), 2)
+    end
+end
+"#;
+
+/// Create the Lua state a game runs in.
+///
+/// Untrusted games get a sandbox: the QRcade API is registered on top of the base
+/// library, `string`, `table`, `math`, `utf8`, `coroutine` and a clock-only `os`.
+/// Nothing in it can touch files, the environment or other processes. Trusted mode
+/// is the full standard library, for developers running their own scripts.
+///
+/// Lua does not verify bytecode, so the sandbox only limits what well-formed code
+/// can reach. `load_game` keeps bytecode out of it by loading untrusted games as
+/// text only.
+pub fn create_lua(trusted: bool) -> LuaResult<Lua> {
+    if trusted {
+        return Ok(Lua::new());
+    }
+
+    // Everything but `os` is kept in full; `os` is loaded only to copy the clock out of it
+    let libraries = StdLib::COROUTINE
+        | StdLib::TABLE
+        | StdLib::STRING
+        | StdLib::UTF8
+        | StdLib::MATH
+        | StdLib::OS;
+    let lua = Lua::new_with(libraries, LuaOptions::default())?;
+    let globals = lua.globals();
+
+    for name in BLOCKED_GLOBALS {
+        globals.raw_remove(*name)?;
+    }
+    guard(&lua, &globals, "", BLOCKED_GLOBALS)?;
+
+    let os: Table = globals.get("os")?;
+    let safe_os = lua.create_table()?;
+    for name in SAFE_OS_FUNCTIONS {
+        safe_os.set(*name, os.get::<mlua::Value>(*name)?)?;
+    }
+    guard(&lua, &safe_os, "os.", BLOCKED_OS_FUNCTIONS)?;
+    globals.set("os", safe_os)?;
+
+    let string: Table = globals.get("string")?;
+    for name in BLOCKED_STRING_FUNCTIONS {
+        string.raw_remove(*name)?;
+    }
+    guard(&lua, &string, "string.", BLOCKED_STRING_FUNCTIONS)?;
+
+    Ok(lua)
+}
+
+/// Make reading any of `blocked` from `table` raise an error naming it.
+/// Every other missing key still reads as `nil`.
+fn guard(lua: &Lua, table: &Table, prefix: &str, blocked: &[&str]) -> LuaResult<()> {
+    let blocked_set = lua.create_table()?;
+    for name in blocked {
+        blocked_set.set(*name, true)?;
+    }
+    let index: mlua::Function = lua
+        .load(GUARD_SOURCE)
+        .set_name("=sandbox")
+        .call((blocked_set, prefix))?;
+
+    let metatable = lua.create_table()?;
+    metatable.set("__index", index)?;
+    table.set_metatable(Some(metatable));
+    Ok(())
+}
@@ -1,71 +1,109 @@
 mod assets;
+mod cartridge;
 mod ecs;
 mod engine;
 mod lua;
 
+use cartridge::library;
+use cartridge::manifest::ApiManifest;
+use cartridge::signing;
+use cartridge::text;
+use cartridge::Cartridge;
 use ecs::{render_system, DragDropSystem, GameState, InputSystem, MovementSystem, PhysicsSystem};
+use engine::headless::{write_recording, HeadlessOptions};
+use engine::input::{InputEvent, InputSource, ScriptedInput};
+use engine::launcher::{Launcher, LauncherAction};
 use engine::managers::state_manager::StateManager;
-use engine::rendering::{Renderer, Sdl2Renderer};
+use engine::rendering::{
+    NullRenderer, RecordingRenderer, Renderer, Sdl2Renderer, SoftwareRenderer, WINDOW_HEIGHT,
+    WINDOW_WIDTH,
+};
+use engine::timestep::FixedTimestep;
+use lua::limits::{install_limits, with_budget, LimitExceeded, ScriptLimits};
+use lua::sandbox::create_lua;
 use lua::{
-    call_on_end, call_on_frame, call_on_start, register_collision_api, register_drag_drop_api,
-    register_entity_api, register_gravity_api, register_input_api, register_renderable_api,
-    register_text_api, register_tilemap_api, register_timer_api, register_transform_api,
-    register_velocity_api,
+    call_on_end, call_on_fixed_update, call_on_frame, call_on_start, load_game,
+    register_engine_api,
 };
 
-use mlua::{Lua, Result as LuaResult};
+use mlua::{Lua, Result as LuaResult, Value};
 use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
 use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::EventPump;
 use std::cell::RefCell;
 use std::env;
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use tracing::{debug, error, info, trace};
+use std::time::Duration;
+use tracing::{debug, error, info, trace, warn};
+
+/// The launcher only redraws a menu, so it doesn't need to spin as fast as a game
+const LAUNCHER_FRAME_TIME: Duration = Duration::from_millis(16);
 
 struct EngineConfig {
     debug_mode: bool,
     window_width: u32,
     window_height: u32,
     window_title: &'static str,
-    script_path: String,
+    /// A script, a packed cartridge, or the QR code image(s) / directory of a cartridge.
+    /// Empty to pick a game from the launcher instead
+    script_paths: Vec<PathBuf>,
+    /// Games listed by the launcher
+    library_dir: PathBuf,
+    /// Public keys of the publishers whose signed cartridges show as verified
+    trusted_keys_dir: PathBuf,
+    /// Give the game the full Lua standard library instead of the sandbox
+    trusted: bool,
 }
 
 impl Default for EngineConfig {
     fn default() -> Self {
         Self {
             debug_mode: false,
-            window_width: 800,
-            window_height: 600,
+            window_width: WINDOW_WIDTH,
+            window_height: WINDOW_HEIGHT,
             window_title: "QRcade",
-            script_path: "resources/lua_scripts/example_script.lua".to_string(),
+            script_paths: Vec::new(),
+            library_dir: PathBuf::from(library::DEFAULT_LIBRARY_DIR),
+            trusted_keys_dir: PathBuf::from(signing::DEFAULT_TRUSTED_KEYS_DIR),
+            trusted: false,
         }
     }
 }
 
-fn load_lua_script(lua: &Lua, filepath: &str) -> Result<(), mlua::Error> {
-    // Verify the script file exists
-    if !Path::new(filepath).exists() {
-        return Err(mlua::Error::external(format!(
-            "Script file not found: {}",
-            filepath
-        )));
-    }
-    let script_content = fs::read(filepath)
-        .map_err(|e| mlua::Error::external(format!("Failed to read file: {}", e)))?;
+/// When a game's main loop stops without the player leaving it, for headless runs.
+/// The default runs until the player leaves, on the real clock.
+#[derive(Debug, Default)]
+struct LoopLimits {
+    /// Frames to run at most
+    max_frames: Option<u64>,
+    /// Lua expression that stops the loop once it is true after a frame
+    until: Option<String>,
+    /// Advance every frame by this many seconds instead of the time that passed
+    frame_time: Option<f32>,
+    /// Stop when the game is paused over its script limits instead of showing
+    /// the pause overlay until the player leaves
+    stop_on_pause: bool,
+}
 
-    lua.load(&script_content).exec()?;
-    Ok(())
+/// What to tell the player when the game's code hit one of its limits. Any other
+/// error still stops the engine.
+fn pause_reason(error: &mlua::Error) -> Option<String> {
+    let limit = LimitExceeded::from_error(error)?;
+    eprintln!("Game paused: {}", limit);
+    error!(%limit, "Paused game over its script limits");
+    Some(limit.to_string())
 }
 
 fn setup(
-    _state_manager: Rc<RefCell<StateManager>>,
+    state_manager: Rc<RefCell<StateManager>>,
     lua: &Lua,
-    script_path: &str,
+    game: &Cartridge,
+    trusted: bool,
 ) -> Result<(), mlua::Error> {
-    // Load and run the initialization Lua script
-    load_lua_script(lua, script_path)?;
+    // Load and run the game's Lua chunk
+    load_game(lua, &state_manager, game, trusted)?;
 
     // Call the Lua on_start function if it exists
     call_on_start(lua)?;
@@ -74,8 +112,6 @@ fn setup(
 
 fn update(
     state_manager: Rc<RefCell<StateManager>>,
-    movement_system: &MovementSystem,
-    physics_system: &mut PhysicsSystem,
     drag_drop_system: &mut DragDropSystem,
     input_system: Rc<RefCell<InputSystem>>,
     lua: &Lua,
@@ -97,7 +133,7 @@ fn update(
     lua.globals().set("mouse_y", mouse_y as f32)?;
 
     // Call Lua update function first to get any new movement commands
-    call_on_frame(lua, delta_time)?;
+    call_on_frame(lua, &state_manager, delta_time)?;
 
     // update drag and drop system - THIS IS CRUCIAL
     if let Ok(mut state) = state_manager.borrow().state.try_borrow_mut() {
@@ -107,50 +143,130 @@ fn update(
         error!("Failed to borrow state for drag drop system update");
     }
 
-    // Process movement commands
-    movement_system.update(delta_time);
+    Ok(())
+}
+
+/// Movement and physics advance in fixed steps however long the frame took, so
+/// they behave the same at any frame rate
+fn fixed_update(
+    state_manager: Rc<RefCell<StateManager>>,
+    movement_system: &MovementSystem,
+    physics_system: &mut PhysicsSystem,
+    lua: &Lua,
+    timestep: &mut FixedTimestep,
+    delta_time: f32,
+) -> Result<(), mlua::Error> {
+    let step = timestep.step();
+    for _ in 0..timestep.advance(delta_time) {
+        if let Ok(mut state) = state_manager.borrow().state.try_borrow_mut() {
+            state.snapshot_transforms();
+        }
+
+        call_on_fixed_update(lua, &state_manager, step)?;
 
-    // Update physics simulation
-    if let Ok(mut state) = state_manager.borrow().state.try_borrow_mut() {
-        physics_system.update(&mut state, delta_time);
+        // Process movement commands
+        movement_system.update(step);
+
+        // Update physics simulation
+        if let Ok(mut state) = state_manager.borrow().state.try_borrow_mut() {
+            physics_system.update(&mut state, step);
+
+            // Children follow their parents before anything is drawn or collides
+            state.resolve_world_transforms();
+        }
     }
 
     Ok(())
 }
 
-fn render(state_manager: Rc<RefCell<StateManager>>, renderer: &mut Sdl2Renderer, debug: bool) {
+fn render(
+    state_manager: Rc<RefCell<StateManager>>,
+    renderer: &mut impl Renderer,
+    config: &EngineConfig,
+    paused: Option<&str>,
+    alpha: f32,
+) {
     tracing::debug!("Starting render frame");
     renderer.clear();
-    render_system(state_manager, renderer, 1.0, debug);
+    render_system(state_manager, renderer, 1.0, alpha, config.debug_mode);
+    if let Some(reason) = paused {
+        draw_pause_overlay(renderer, reason, config);
+    }
     renderer.present();
     tracing::debug!("Completed render frame");
 }
 
-fn main() -> LuaResult<()> {
-    // Initialize tracing subscriber with a specific filter level
-    tracing_subscriber::fmt()
-        .with_env_filter("info,qrcade=debug")
-        .with_file(true)
-        .with_line_number(true)
-        .with_thread_ids(true)
-        .with_thread_names(true)
-        .init();
-
-    // Parse command line arguments
-    let args: Vec<String> = env::args().collect();
+/// Banner over the last frame of a game that was paused for going over its limits
+fn draw_pause_overlay(renderer: &mut impl Renderer, reason: &str, config: &EngineConfig) {
+    let center_x = config.window_width as i32 / 2;
+    let center_y = config.window_height as i32 / 2;
+    renderer.draw_rect(
+        center_x,
+        center_y,
+        config.window_width,
+        120,
+        Color::RGBA(0, 0, 0, 220),
+    );
+    renderer.draw_text(
+        "Game paused".to_string(),
+        center_x,
+        center_y - 30,
+        Color::RGB(255, 80, 80),
+        1.0,
+    );
+    renderer.draw_text(
+        reason.to_string(),
+        center_x,
+        center_y + 10,
+        Color::RGB(255, 255, 255),
+        0.5,
+    );
+    renderer.draw_text(
+        "Press Escape to quit".to_string(),
+        center_x,
+        center_y + 40,
+        Color::RGB(180, 180, 180),
+        0.5,
+    );
+}
 
-    // Create engine configuration
-    let mut config = EngineConfig {
-        debug_mode: std::env::var("DEBUG").is_ok(),
-        ..Default::default()
-    };
+/// Create the window and the event pump shared by the launcher and every game
+fn open_window(config: &EngineConfig) -> (Sdl2Renderer, EventPump) {
+    let renderer = Sdl2Renderer::new(
+        config.window_title,
+        config.window_width,
+        config.window_height,
+    );
+    let event_pump = renderer.sdl_context.event_pump().unwrap();
+    (renderer, event_pump)
+}
 
-    // If a script path is provided as an argument, use it
-    if args.len() > 1 {
-        config.script_path = args[1].clone();
-    }
+/// How a game's main loop ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GameExit {
+    /// The player pressed Escape
+    Finished,
+    /// The window was closed, which quits the engine
+    Quit,
+    /// `LoopLimits::max_frames` frames were run
+    FrameLimit,
+    /// The `LoopLimits::until` condition was true after this frame
+    Condition { frame: u64 },
+    /// The game went over its script limits in this frame
+    Paused { frame: u64 },
+}
 
-    info!("Loading script: {}", config.script_path);
+/// Run a game until the player leaves it or one of `loop_limits` stops it. Every call
+/// starts from a fresh `GameState`, `StateManager` and Lua state, so games never see
+/// each other's leftovers.
+fn run_game(
+    config: &EngineConfig,
+    game: &Cartridge,
+    renderer: &mut impl Renderer,
+    input: &mut impl InputSource,
+    loop_limits: &LoopLimits,
+) -> LuaResult<GameExit> {
+    let game_title = game.metadata.title.as_deref().unwrap_or("cartridge");
 
     // Initialize Gamestate with debug mode
     let input_system = Rc::new(RefCell::new(InputSystem::new()));
@@ -166,75 +282,79 @@ fn main() -> LuaResult<()> {
     let mut physics_system = PhysicsSystem::new();
     let mut drag_drop_system = DragDropSystem::new();
 
-    // Create the renderer with configurations
-    let mut renderer = Sdl2Renderer::new(
-        config.window_title,
-        config.window_width,
-        config.window_height,
+    // Set up lua environment
+    if config.trusted {
+        warn!("Running in trusted mode: the game has full access to io, os and the filesystem");
+    }
+    let lua = create_lua(config.trusted)?;
+    let limits = ScriptLimits::for_cartridge(&game.metadata);
+    info!(
+        instruction_budget = limits.instruction_budget,
+        memory_limit = limits.memory_limit,
+        "Applying script limits"
     );
+    install_limits(&lua, limits)?;
+    let mut timestep = FixedTimestep::for_cartridge(&game.metadata);
+    info!(step = timestep.step(), "Using a fixed timestep");
+    // Plain scripts and older cartridges don't declare their modules and get all of them
+    let manifest = game.metadata.api_modules.unwrap_or_else(ApiManifest::all);
+    info!(modules = %manifest, "Registering API modules");
+    register_engine_api(&lua, Rc::clone(&state_manager), manifest)?;
+
+    // Compiled once, before the game runs, so a typo fails straight away
+    let until = match &loop_limits.until {
+        Some(condition) => Some(
+            lua.load(format!("return {}", condition))
+                .set_name("=--until")
+                .into_function()?,
+        ),
+        None => None,
+    };
 
-    // Create an SDL event pump for handling input
-    let sdl_context = &renderer.sdl_context;
-    let mut event_pump = sdl_context.event_pump().unwrap();
-
-    // Set up lua environment
-    let lua = Lua::new();
-    register_entity_api(&lua, Rc::clone(&state_manager))?;
-    register_transform_api(&lua, Rc::clone(&state_manager))?;
-    register_renderable_api(&lua, Rc::clone(&state_manager))?;
-    register_input_api(&lua, Rc::clone(&state_manager))?;
-    register_collision_api(&lua, Rc::clone(&state_manager))?;
-    register_tilemap_api(&lua, Rc::clone(&state_manager))?;
-    register_velocity_api(&lua, Rc::clone(&state_manager))?;
-    register_gravity_api(&lua, Rc::clone(&state_manager))?;
-    register_drag_drop_api(&lua, Rc::clone(&state_manager))?;
-    register_text_api(&lua, Rc::clone(&state_manager))?;
-    register_timer_api(&lua, Rc::clone(&state_manager))?;
+    // A game over its limits is paused with a diagnostic instead of freezing the engine
+    let mut paused = None;
 
     // Run setup
-    match setup(Rc::clone(&state_manager), &lua, &config.script_path) {
+    match setup(Rc::clone(&state_manager), &lua, game, config.trusted) {
         Ok(_) => {
             if config.debug_mode {
                 state_manager.borrow().debug_print_entities().unwrap();
             }
         }
-        Err(e) => {
-            eprintln!("Failed to load script '{}': {}", config.script_path, e);
-            return Err(e);
-        }
+        Err(e) => match pause_reason(&e) {
+            Some(reason) => paused = Some(reason),
+            None => {
+                eprintln!("Failed to load script '{}': {}", game_title, e);
+                return Err(e);
+            }
+        },
     }
 
     // Set up time tracking
     let mut last_time = std::time::Instant::now();
 
     // Main loop simulation
-    let mut running = true;
-    while running {
+    let mut frame: u64 = 0;
+    let mut exit = None;
+    while exit.is_none() {
         let current_time = std::time::Instant::now();
-        let delta_time = current_time.duration_since(last_time).as_secs_f32();
+        let delta_time = loop_limits
+            .frame_time
+            .unwrap_or_else(|| current_time.duration_since(last_time).as_secs_f32());
         last_time = current_time;
 
         // Handle events
-        for event in event_pump.poll_iter() {
+        for event in input.poll() {
             match event {
-                Event::Quit { .. } => running = false, // Handle window close
-                Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => running = false, // Handle Escape key
-                Event::KeyDown {
-                    keycode: Some(code),
-                    ..
-                } => {
+                InputEvent::Quit => exit = Some(GameExit::Quit), // Handle window close
+                InputEvent::Exit => exit = Some(GameExit::Finished), // Handle Escape key
+                InputEvent::KeyDown(code) => {
                     input_system.borrow_mut().set_key_pressed(code);
                 }
-                Event::KeyUp {
-                    keycode: Some(code),
-                    ..
-                } => {
+                InputEvent::KeyUp(code) => {
                     input_system.borrow_mut().set_key_released(code);
                 }
-                Event::MouseMotion { x, y, .. } => {
+                InputEvent::MouseMotion { x, y } => {
                     debug!("Mouse moved to: ({}, {})", x, y);
                     // this will update both position and any active drag operation
                     input_system.borrow_mut().update_mouse_position(x, y);
@@ -245,7 +365,7 @@ fn main() -> LuaResult<()> {
                         .update_dragged_entity(x as f32, y as f32)
                         .unwrap_or_else(|e| error!("Error updating dragged entity: {}", e));
                 }
-                Event::MouseButtonDown { mouse_btn, .. } => {
+                InputEvent::MouseButtonDown(mouse_btn) => {
                     debug!("Mouse button down: {:?}", mouse_btn);
                     input_system
                         .borrow_mut()
@@ -274,7 +394,7 @@ fn main() -> LuaResult<()> {
                         }
                     }
                 }
-                Event::MouseButtonUp { mouse_btn, .. } => {
+                InputEvent::MouseButtonUp(mouse_btn) => {
                     debug!("Mouse button up: {:?}", mouse_btn);
                     input_system
                         .borrow_mut()
@@ -285,29 +405,363 @@ fn main() -> LuaResult<()> {
                         drag_drop_system.end_drag();
                     }
                 }
-                _ => {}
             }
         }
 
         // Update game state
-        {
-            update(
+        if paused.is_none() {
+            let result = update(
                 Rc::clone(&state_manager),
-                &movement_system,
-                &mut physics_system,
                 &mut drag_drop_system,
                 input_system.clone(),
                 &lua,
                 delta_time,
-            )?;
+            )
+            .and_then(|_| {
+                fixed_update(
+                    Rc::clone(&state_manager),
+                    &movement_system,
+                    &mut physics_system,
+                    &lua,
+                    &mut timestep,
+                    delta_time,
+                )
+            });
+            if let Err(e) = result {
+                paused = Some(pause_reason(&e).ok_or(e)?);
+            }
         }
 
         // Render
         {
-            render(Rc::clone(&state_manager), &mut renderer, config.debug_mode);
+            render(
+                Rc::clone(&state_manager),
+                renderer,
+                config,
+                paused.as_deref(),
+                timestep.alpha(),
+            );
+        }
+
+        // Runs without a player stop on their own
+        if exit.is_none() {
+            if paused.is_some() {
+                if loop_limits.stop_on_pause {
+                    exit = Some(GameExit::Paused { frame });
+                }
+            } else if let Some(until) = &until {
+                let value = with_budget(&lua, "the --until condition", || until.call::<Value>(()))?;
+                if !matches!(value, Value::Nil | Value::Boolean(false)) {
+                    exit = Some(GameExit::Condition { frame });
+                }
+            }
+        }
+        frame += 1;
+        if exit.is_none() && loop_limits.max_frames.is_some_and(|max| frame >= max) {
+            exit = Some(GameExit::FrameLimit);
         }
     }
 
-    call_on_end(&lua)?;
+    // A paused game is stopped mid-callback, so it doesn't get to clean up
+    if paused.is_none() {
+        if let Err(e) = call_on_end(&lua) {
+            if pause_reason(&e).is_none() {
+                return Err(e);
+            }
+        }
+    }
+    Ok(exit.unwrap_or(GameExit::Finished))
+}
+
+/// Show the library menu and play the games picked from it until the player quits
+fn run_launcher(
+    config: &EngineConfig,
+    renderer: &mut Sdl2Renderer,
+    event_pump: &mut EventPump,
+) -> LuaResult<()> {
+    let mut launcher = Launcher::new(config.library_dir.clone(), config.trusted_keys_dir.clone());
+
+    loop {
+        let mut action = LauncherAction::None;
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. } => action = LauncherAction::Quit,
+                Event::KeyDown {
+                    keycode: Some(code),
+                    ..
+                } => action = launcher.handle_key(code),
+                _ => {}
+            }
+            if action != LauncherAction::None {
+                break;
+            }
+        }
+
+        match action {
+            LauncherAction::Quit => return Ok(()),
+            LauncherAction::Play => {
+                let Some(entry) = launcher.selected() else {
+                    continue;
+                };
+                let Ok(selected) = &entry.game else {
+                    continue;
+                };
+                info!(paths = ?entry.paths, "Loading game from library");
+                let game = selected.cartridge.clone();
+                println!("{}", selected.verification);
+                info!(verification = %selected.verification, "Checked cartridge signature");
+
+                match run_game(config, &game, renderer, event_pump, &LoopLimits::default()) {
+                    Ok(GameExit::Quit) => return Ok(()),
+                    Ok(_) => launcher.set_message(None),
+                    Err(e) => {
+                        error!(error = %e, "Game stopped with an error");
+                        let reason = e.to_string();
+                        let first_line = reason.lines().next().unwrap_or_default();
+                        launcher.set_message(Some(format!(
+                            "{} stopped: {}",
+                            game.metadata.title.as_deref().unwrap_or("The game"),
+                            first_line
+                        )));
+                    }
+                }
+                // The game may have been repacked while it was running
+                launcher.rescan();
+            }
+            LauncherAction::None => {}
+        }
+
+        renderer.clear();
+        launcher.draw(renderer, config.window_width, config.window_height);
+        renderer.present();
+        std::thread::sleep(LAUNCHER_FRAME_TIME);
+    }
+}
+
+/// Run a game without a window for `headless`, see `HEADLESS_USAGE`
+fn run_headless(args: &[String]) -> Result<(), String> {
+    let options = HeadlessOptions::from_args(args)?;
+    let mut config = EngineConfig {
+        debug_mode: std::env::var("DEBUG").is_ok(),
+        script_paths: options.paths.clone(),
+        trusted: options.trusted,
+        ..Default::default()
+    };
+    if let Ok(dir) = std::env::var("QRCADE_TRUSTED_KEYS") {
+        config.trusted_keys_dir = PathBuf::from(dir);
+    }
+
+    let game = cartridge::loader::read_game(&config.script_paths).map_err(|e| e.to_string())?;
+    println!("{}", signing::verify(&game, &config.trusted_keys_dir));
+
+    let mut input = match &options.input {
+        Some(path) => {
+            let script = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read input script '{}': {}", path.display(), e))?;
+            ScriptedInput::parse(&script)?
+        }
+        None => ScriptedInput::new(),
+    };
+    let limits = LoopLimits {
+        max_frames: Some(options.frames),
+        until: options.until.clone(),
+        frame_time: Some(options.frame_time()),
+        stop_on_pause: true,
+    };
+
+    // The last frame is saved even when the game fails, to show where it got to
+    let exit = if let Some(path) = &options.screenshot {
+        let mut renderer = SoftwareRenderer::new(config.window_width, config.window_height);
+        let exit = run_game(&config, &game, &mut renderer, &mut input, &limits);
+        renderer
+            .into_image()
+            .save(path)
+            .map_err(|e| format!("Failed to save screenshot '{}': {}", path.display(), e))?;
+        exit
+    } else if let Some(path) = &options.record {
+        let mut renderer = RecordingRenderer::new();
+        let exit = run_game(&config, &game, &mut renderer, &mut input, &limits);
+        write_recording(path, renderer.frames())
+            .map_err(|e| format!("Failed to write recording '{}': {}", path.display(), e))?;
+        exit
+    } else {
+        run_game(&config, &game, &mut NullRenderer, &mut input, &limits)
+    }
+    .map_err(|e| e.to_string())?;
+
+    match exit {
+        GameExit::Condition { frame } => {
+            println!("--until was true after frame {}", frame);
+            Ok(())
+        }
+        GameExit::FrameLimit if options.until.is_some() => Err(format!(
+            "--until was still false after {} frames",
+            options.frames
+        )),
+        GameExit::FrameLimit => {
+            println!("Ran {} frames", options.frames);
+            Ok(())
+        }
+        GameExit::Finished | GameExit::Quit => {
+            println!("The input script left the game");
+            Ok(())
+        }
+        GameExit::Paused { frame } => Err(format!(
+            "The game went over its script limits in frame {}",
+            frame
+        )),
+    }
+}
+
+fn main() -> LuaResult<()> {
+    // Initialize tracing subscriber with a specific filter level
+    tracing_subscriber::fmt()
+        .with_env_filter("info,qrcade=debug")
+        .with_file(true)
+        .with_line_number(true)
+        .with_thread_ids(true)
+        .with_thread_names(true)
+        .init();
+
+    // Parse command line arguments
+    let args: Vec<String> = env::args().collect();
+
+    // Tooling subcommands run instead of the engine
+    if args.get(1).map(String::as_str) == Some("pack") {
+        if let Err(e) = cartridge::packer::run(&args[2..]) {
+            eprintln!("Failed to pack cartridge: {}", e);
+            return Err(mlua::Error::external(e));
+        }
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("analyze") {
+        if let Err(e) = cartridge::analyzer::run(&args[2..]) {
+            eprintln!("Failed to analyze script: {}", e);
+            return Err(mlua::Error::external(e));
+        }
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("robustness") {
+        if let Err(e) = cartridge::robustness::run(&args[2..]) {
+            eprintln!("Robustness check failed: {}", e);
+            return Err(mlua::Error::external(e));
+        }
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("bench") {
+        if let Err(e) = engine::bench::run(&args[2..]) {
+            eprintln!("Benchmark failed: {}", e);
+            return Err(mlua::Error::external(e));
+        }
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("headless") {
+        if let Err(e) = run_headless(&args[2..]) {
+            eprintln!("Headless run failed: {}", e);
+            return Err(mlua::Error::external(e));
+        }
+        return Ok(());
+    }
+    if args.get(1).map(String::as_str) == Some("keygen") {
+        if let Err(e) = cartridge::signing::run(&args[2..]) {
+            eprintln!("Failed to generate key: {}", e);
+            return Err(mlua::Error::external(e));
+        }
+        return Ok(());
+    }
+
+    // Create engine configuration
+    let mut config = EngineConfig {
+        debug_mode: std::env::var("DEBUG").is_ok(),
+        ..Default::default()
+    };
+    if let Ok(dir) = std::env::var("QRCADE_TRUSTED_KEYS") {
+        config.trusted_keys_dir = PathBuf::from(dir);
+    }
+    if let Ok(dir) = std::env::var("QRCADE_LIBRARY") {
+        config.library_dir = PathBuf::from(dir);
+    }
+
+    // `--trusted` lifts the sandbox for developers running their own scripts
+    let (flags, paths): (Vec<&String>, Vec<&String>) =
+        args[1..].iter().partition(|arg| arg.starts_with("--"));
+    for flag in flags {
+        match flag.as_str() {
+            "--trusted" => config.trusted = true,
+            other => {
+                eprintln!("Unknown option: {}", other);
+                return Err(mlua::Error::external(format!("Unknown option: {}", other)));
+            }
+        }
+    }
+
+    // Without a game to play, open the launcher on the library
+    if paths.is_empty() {
+        info!(library = %config.library_dir.display(), "Opening launcher");
+        let (mut renderer, mut event_pump) = open_window(&config);
+        return run_launcher(&config, &mut renderer, &mut event_pump);
+    }
+
+    // A cartridge pasted as text (Base45, Base64url or a qrcade: URI) instead of
+    // file paths; several texts are the chunks of one cartridge
+    let pasted = paths
+        .iter()
+        .all(|arg| !Path::new(arg.as_str()).exists() && text::decode(arg).is_some());
+
+    // Several paths are the chunks of one cartridge split across multiple QR codes
+    let game_name = if pasted {
+        "pasted cartridge".to_string()
+    } else {
+        config.script_paths = paths.iter().map(PathBuf::from).collect();
+        config
+            .script_paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    info!("Loading script: {}", game_name);
+
+    // Read and verify the game before opening a window, so a bad scan is
+    // reported as a bad cartridge rather than a failure halfway through startup
+    if let Some(missing) = config.script_paths.iter().find(|path| !path.exists()) {
+        eprintln!("Script file not found: {}", missing.display());
+        return Err(mlua::Error::external(format!(
+            "Script file not found: {}",
+            missing.display()
+        )));
+    }
+    let loaded = if pasted {
+        let texts: Vec<String> = paths.into_iter().cloned().collect();
+        cartridge::loader::read_text(&texts)
+    } else {
+        cartridge::loader::read_game(&config.script_paths)
+    };
+    let game = match loaded {
+        Ok(game) => game,
+        Err(e) => {
+            if e.is_bad_cartridge() {
+                eprintln!("Bad cartridge '{}': {}", game_name, e);
+            } else {
+                eprintln!("Failed to read game '{}': {}", game_name, e);
+            }
+            return Err(mlua::Error::external(e));
+        }
+    };
+
+    // Tell the player who made the game before any of its code runs
+    let verification = signing::verify(&game, &config.trusted_keys_dir);
+    println!("{}", verification);
+    info!(%verification, "Checked cartridge signature");
+
+    let (mut renderer, mut event_pump) = open_window(&config);
+    run_game(
+        &config,
+        &game,
+        &mut renderer,
+        &mut event_pump,
+        &LoopLimits::default(),
+    )?;
     Ok(())
 }